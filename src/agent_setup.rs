@@ -1,23 +1,83 @@
 //! Static information needed to set up a Pacman game
 use crate::game_state::GhostType;
+use crate::ghost::GhostBehavior;
+use crate::grid::transform::GridTransform;
 use crate::grid::GridValue::{o, O};
-use crate::grid::{ComputedGrid, Direction, GridValue};
+use crate::grid::{facing_direction, ComputedGrid, Direction, GridValue};
 use crate::standard_grids::StandardGrid;
 use anyhow::{anyhow, Error};
 use rapier2d::na::Point2;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Number of frames between ghosts leaving the ghost house, on grids other than the official one
+const GHOST_HOUSE_WAIT: usize = 10;
+
+/// Find the shortest path a ghost can take between two cells, including both ends
+fn ghost_path(
+    grid: &ComputedGrid,
+    start: &Point2<u8>,
+    end: &Point2<u8>,
+) -> Result<Vec<(Point2<u8>, Direction)>, Error> {
+    let mut dist = grid
+        .ghost_dist(start, end)
+        .ok_or(anyhow!("Ghosts can't leave the ghost house"))?;
+    let mut path = vec![(*start, Direction::Up)];
+    let mut p = *start;
+    while dist > 0 {
+        let next = grid
+            .ghost_neighbors(&p)
+            .into_iter()
+            .find(|n| grid.ghost_dist(n, end) == Some(dist - 1))
+            .expect("a cell next to a path should be closer to its end");
+        path.push((next, facing_direction(&p, &next)));
+        p = next;
+        dist -= 1;
+    }
+    Ok(path)
+}
 
 /// Static information needed to set up a ghost for Pacman game
-#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct GhostSetup {
     /// The ghost's starting path; where it goes when it first spawns
     pub start_path: Vec<(Point2<u8>, Direction)>,
-    /// The ghost's color; determines behavior
+    /// The ghost's color; determines behavior, unless there is a custom behavior
     pub color: GhostType,
     /// The ghost's scatter point; where it goes when it's not chasing Pacman
     pub scatter_point: Point2<u8>,
+    /// Replaces the behavior of the arcade ghost of this color
+    ///
+    /// Custom behaviors are not serialized, so they are lost when a replay is saved.
+    #[serde(skip)]
+    pub custom_behavior: Option<Arc<dyn GhostBehavior>>,
+}
+
+impl GhostSetup {
+    /// Get the ghost's behavior
+    pub fn behavior(&self) -> &dyn GhostBehavior {
+        match &self.custom_behavior {
+            Some(behavior) => behavior.as_ref(),
+            None => self.color.behavior(),
+        }
+    }
 }
 
+/// Custom behaviors are equal only if they are the same instance
+impl PartialEq for GhostSetup {
+    fn eq(&self, other: &Self) -> bool {
+        self.start_path == other.start_path
+            && self.color == other.color
+            && self.scatter_point == other.scatter_point
+            && match (&self.custom_behavior, &other.custom_behavior) {
+                (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+                (a, b) => a.is_none() && b.is_none(),
+            }
+    }
+}
+
+impl Eq for GhostSetup {}
+
 /// Static information needed to set up a Pacman game
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PacmanAgentSetup {
@@ -27,12 +87,12 @@ pub struct PacmanAgentSetup {
     pacman_start: (Point2<u8>, Direction),
     /// The ghosts
     ghosts: Vec<GhostSetup>,
-    /// The times when ghosts swap between chase and scatter mode
-    state_swap_times: Vec<u32>,
     /// The path that ghosts take upon respawning
     ghost_respawn_path: Vec<(Point2<u8>, Direction)>,
     /// This is the location where a ghost will reappear after being eaten by Pacman.
     ghost_home_pos: (Point2<u8>, Direction),
+    /// Where fruit appears; by default, the first cherry position in the grid, if there is one
+    fruit_position: Option<Point2<u8>>,
 }
 
 impl PacmanAgentSetup {
@@ -41,7 +101,6 @@ impl PacmanAgentSetup {
         grid: ComputedGrid,
         pacman_start: (Point2<u8>, Direction),
         ghosts: Vec<GhostSetup>,
-        state_swap_times: Vec<u32>,
         ghost_respawn_path: Vec<(Point2<u8>, Direction)>,
     ) -> Result<Self, Error> {
         let start_value = grid
@@ -79,16 +138,111 @@ impl PacmanAgentSetup {
             }
         }
 
+        let fruit_position = grid
+            .walkable_nodes()
+            .iter()
+            .find(|p| grid.at(p) == Some(GridValue::c))
+            .copied();
+
         Ok(Self {
             grid,
             pacman_start,
             ghosts,
-            state_swap_times,
             ghost_respawn_path,
             ghost_home_pos: (Point2::new(12, 15), Direction::Up),
+            fruit_position,
         })
     }
 
+    /// Create a setup for a standard or custom grid, with Pacman starting where Pacbot does
+    ///
+    /// The official Pacman grid gets the default setup. On any other grid, the ghosts start in
+    /// the ghost house and leave it one at a time through its first door, and each scatters to
+    /// its own corner; grids without a ghost house have no ghosts. Fails if Pacbot's starting
+    /// cell isn't an empty walkable cell.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::agent_setup::PacmanAgentSetup;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let setup = PacmanAgentSetup::from_standard_grid(&StandardGrid::Pacman).unwrap();
+    /// assert_eq!(setup, PacmanAgentSetup::default());
+    /// ```
+    pub fn from_standard_grid(standard_grid: &StandardGrid) -> Result<Self, Error> {
+        if *standard_grid == StandardGrid::Pacman {
+            return Ok(Self::default());
+        }
+
+        let grid = standard_grid.compute_grid();
+        let pacbot = standard_grid.get_default_pacbot_isometry().translation;
+        let pacman_start = grid
+            .node_nearest(pacbot.x, pacbot.y)
+            .ok_or(anyhow!("Pacman start position is not walkable"))?;
+
+        let mut ghosts = vec![];
+        let mut ghost_respawn_path = vec![];
+        if let Some(door) = grid.ghost_graph().doors().first() {
+            let exit = grid
+                .ghost_neighbors(door)
+                .into_iter()
+                .find(|p| grid.at(p).is_some_and(|v| v.walkable()))
+                .ok_or(anyhow!("Ghost house door doesn't lead out of the house"))?;
+            let house: Vec<Point2<u8>> = grid
+                .ghost_graph()
+                .nodes()
+                .iter()
+                .filter(|p| grid.in_ghost_house(p))
+                .copied()
+                .collect();
+            ghost_respawn_path = ghost_path(&grid, door, &exit)?;
+
+            let (right, top) = (grid.width() as f32 - 1.0, grid.height() as f32 - 1.0);
+            let corners = [
+                (GhostType::Red, right, top),
+                (GhostType::Pink, 0.0, top),
+                (GhostType::Orange, 0.0, 0.0),
+                (GhostType::Blue, right, 0.0),
+            ];
+            for (i, (color, x, y)) in corners.into_iter().enumerate() {
+                let start = house[i % house.len()];
+                let path = ghost_path(&grid, &start, &exit)?;
+                // wait in the house, so that the ghosts leave one at a time
+                let mut start_path = vec![path[0]; 1 + GHOST_HOUSE_WAIT * i];
+                start_path.extend(&path[1..]);
+                let scatter_point = *grid
+                    .walkable_nodes()
+                    .iter()
+                    .min_by(|a, b| {
+                        let da = (a.x as f32 - x).powi(2) + (a.y as f32 - y).powi(2);
+                        let db = (b.x as f32 - x).powi(2) + (b.y as f32 - y).powi(2);
+                        da.total_cmp(&db)
+                    })
+                    .ok_or(anyhow!("Grid has no walkable cells"))?;
+                ghosts.push(GhostSetup {
+                    start_path,
+                    color,
+                    scatter_point,
+                    custom_behavior: None,
+                });
+            }
+        }
+
+        let ghost_home_pos = ghost_respawn_path
+            .first()
+            .copied()
+            .unwrap_or((pacman_start, Direction::Up));
+        let mut setup = Self::new(
+            grid,
+            (pacman_start, Direction::Right),
+            ghosts,
+            ghost_respawn_path,
+        )?;
+        setup.ghost_home_pos = ghost_home_pos;
+        Ok(setup)
+    }
+
     /// Get the grid
     pub fn grid(&self) -> &ComputedGrid {
         &self.grid
@@ -104,11 +258,6 @@ impl PacmanAgentSetup {
         &self.ghosts
     }
 
-    /// Get the times when ghosts swap between chase and scatter mode
-    pub fn state_swap_times(&self) -> &Vec<u32> {
-        &self.state_swap_times
-    }
-
     /// Get the path that ghosts take upon respawning
     pub fn ghost_respawn_path(&self) -> &Vec<(Point2<u8>, Direction)> {
         &self.ghost_respawn_path
@@ -118,6 +267,114 @@ impl PacmanAgentSetup {
     pub fn ghost_home_pos(&self) -> &(Point2<u8>, Direction) {
         &self.ghost_home_pos
     }
+
+    /// Get the position where fruit appears, if there is one
+    pub fn fruit_position(&self) -> Option<Point2<u8>> {
+        self.fruit_position
+    }
+
+    /// Create a copy of this setup with fruit appearing somewhere else, or not at all
+    ///
+    /// Fails if the position is not walkable.
+    pub fn with_fruit_position(
+        mut self,
+        fruit_position: Option<Point2<u8>>,
+    ) -> Result<Self, Error> {
+        if let Some(p) = &fruit_position {
+            if !self
+                .grid
+                .at(p)
+                .ok_or(anyhow!("Fruit position doesn't exist"))?
+                .walkable()
+            {
+                return Err(anyhow!("Fruit position is not walkable"));
+            }
+        }
+        self.fruit_position = fruit_position;
+        Ok(self)
+    }
+
+    /// Create a copy of this setup on a transformed grid, with every position and direction
+    /// moved to match
+    ///
+    /// Fails if a crop removes any of the positions, or if the transformed setup is invalid.
+    pub fn transformed(&self, transform: &GridTransform) -> Result<Self, Error> {
+        let grid = self.grid.grid();
+        let place_path = |path: &Vec<(Point2<u8>, Direction)>, what: &str| {
+            path.iter()
+                .map(|p| transform.place(grid, p, what))
+                .collect::<Result<Vec<_>, Error>>()
+        };
+
+        let ghosts = self
+            .ghosts
+            .iter()
+            .map(|ghost| {
+                Ok(GhostSetup {
+                    start_path: place_path(&ghost.start_path, "Ghost start path")?,
+                    color: ghost.color,
+                    scatter_point: transform.point(grid, &ghost.scatter_point).ok_or(anyhow!(
+                        "Ghost scatter point is outside the transformed grid"
+                    ))?,
+                    custom_behavior: ghost.custom_behavior.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut setup = Self::new(
+            transform.computed_grid(&self.grid)?,
+            transform.place(grid, &self.pacman_start, "Pacman start position")?,
+            ghosts,
+            place_path(&self.ghost_respawn_path, "Ghost respawn path")?,
+        )?;
+        setup.ghost_home_pos =
+            transform.place(grid, &self.ghost_home_pos, "Ghost home position")?;
+        setup.fruit_position = self
+            .fruit_position
+            .map(|p| {
+                transform
+                    .point(grid, &p)
+                    .ok_or(anyhow!("Fruit position is outside the transformed grid"))
+            })
+            .transpose()?;
+        Ok(setup)
+    }
+
+    /// Give every ghost of a color a custom behavior
+    pub fn with_ghost_behavior(
+        mut self,
+        color: GhostType,
+        behavior: Arc<dyn GhostBehavior>,
+    ) -> Self {
+        for ghost in &mut self.ghosts {
+            if ghost.color == color {
+                ghost.custom_behavior = Some(behavior.clone());
+            }
+        }
+        self
+    }
+
+    /// Create a copy of this setup that is mirrored horizontally
+    pub fn mirrored(&self) -> Result<Self, Error> {
+        self.transformed(&GridTransform::Mirror)
+    }
+
+    /// Create a copy of this setup that is rotated by 180 degrees
+    pub fn rotated_180(&self) -> Result<Self, Error> {
+        self.transformed(&GridTransform::Rotate180)
+    }
+
+    /// Create a copy of this setup with the grid cropped to a rectangle, as in [`Grid::cropped`]
+    ///
+    /// [`Grid::cropped`]: crate::grid::Grid::cropped
+    pub fn cropped(&self, x: u8, y: u8, width: u8, height: u8) -> Result<Self, Error> {
+        self.transformed(&GridTransform::Crop {
+            x,
+            y,
+            width,
+            height,
+        })
+    }
 }
 
 impl Default for PacmanAgentSetup {
@@ -133,6 +390,7 @@ impl Default for PacmanAgentSetup {
                 ],
                 color: GhostType::Red,
                 scatter_point: Point2::new(26, 29),
+                custom_behavior: None,
             },
             GhostSetup {
                 start_path: vec![
@@ -144,6 +402,7 @@ impl Default for PacmanAgentSetup {
                 ],
                 color: GhostType::Pink,
                 scatter_point: Point2::new(1, 29),
+                custom_behavior: None,
             },
             GhostSetup {
                 start_path: vec![
@@ -192,6 +451,7 @@ impl Default for PacmanAgentSetup {
                 ],
                 color: GhostType::Orange,
                 scatter_point: Point2::new(1, 1),
+                custom_behavior: None,
             },
             GhostSetup {
                 start_path: vec![
@@ -224,6 +484,7 @@ impl Default for PacmanAgentSetup {
                 ],
                 color: GhostType::Blue,
                 scatter_point: Point2::new(26, 1),
+                custom_behavior: None,
             },
         ];
 
@@ -231,7 +492,6 @@ impl Default for PacmanAgentSetup {
             grid,
             pacman_start,
             ghosts,
-            vec![35, 135, 170, 270, 295, 395, 420],
             vec![
                 (Point2::new(12, 15), Direction::Up),
                 (Point2::new(12, 17), Direction::Up),
@@ -244,6 +504,7 @@ impl Default for PacmanAgentSetup {
                 (Point2::new(13, 19), Direction::Up),
             ],
         )
+        .and_then(|setup| setup.with_fruit_position(Some(Point2::new(13, 13))))
         .expect("Default PacmanAgentSetup is invalid")
     }
 }
@@ -251,9 +512,130 @@ impl Default for PacmanAgentSetup {
 #[cfg(test)]
 mod tests {
     use crate::agent_setup::PacmanAgentSetup;
+    use crate::custom_grids::{CustomGrid, SoftBoundaries};
+    use crate::game_rules::GameRules;
+    use crate::game_state::PacmanState;
+    use crate::grid::generator::{generate_maze, MazeOptions};
+    use crate::grid::{Direction, GridValue};
+    use crate::standard_grids::StandardGrid;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rapier2d::na::{Isometry2, Point2, Vector2};
+
+    /// A generated maze, with Pacbot starting on an empty cell
+    fn generated_grid(ghost_house: bool) -> StandardGrid {
+        let mut grid = generate_maze(&MazeOptions {
+            ghost_house,
+            ..Default::default()
+        })
+        .unwrap();
+        let start = (0..grid.width())
+            .flat_map(|x| (0..grid.height()).map(move |y| (x, y)))
+            .find(|&(x, y)| grid[x][y] == GridValue::o)
+            .unwrap();
+        grid[start.0][start.1] = GridValue::e;
+        StandardGrid::Custom(Box::new(
+            CustomGrid::new(
+                "Generated".to_string(),
+                grid.clone(),
+                vec![],
+                Isometry2::new(Vector2::new(start.0 as f32, start.1 as f32), 0.0),
+                SoftBoundaries::around(&grid),
+            )
+            .unwrap(),
+        ))
+    }
+
+    #[test]
+    fn setups_for_other_grids() {
+        let rules = GameRules::default();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let setup = PacmanAgentSetup::from_standard_grid(&generated_grid(true)).unwrap();
+        assert_eq!(setup.ghosts().len(), 4);
+        let grid = setup.grid();
+        let mut state = PacmanState::new(&setup, &rules);
+        state.reset(&setup, &rules, false);
+        assert!(state
+            .ghosts
+            .iter()
+            .all(|g| grid.in_ghost_house(&g.agent.location)));
+        // every ghost leaves the house
+        let mut left = vec![false; state.ghosts.len()];
+        for _ in 0..100 {
+            state.resume();
+            state.step(&setup, &rules, &mut rng, false);
+            for (left, ghost) in left.iter_mut().zip(&state.ghosts) {
+                *left |= !grid.in_ghost_house(&ghost.agent.location);
+            }
+        }
+        assert!(left.iter().all(|left| *left));
+
+        let setup = PacmanAgentSetup::from_standard_grid(&generated_grid(false)).unwrap();
+        assert!(setup.ghosts().is_empty());
+        let mut state = PacmanState::new(&setup, &rules);
+        for _ in 0..10 {
+            state.resume();
+            state.step(&setup, &rules, &mut rng, false);
+        }
+
+        // the other standard grids can be played too
+        for grid in StandardGrid::get_all() {
+            PacmanAgentSetup::from_standard_grid(&grid).unwrap();
+        }
+    }
 
     #[test]
     fn default_grid_setup() {
         PacmanAgentSetup::default();
     }
+
+    #[test]
+    fn transformed_setups() {
+        let setup = PacmanAgentSetup::default();
+
+        let mirrored = setup.mirrored().unwrap();
+        assert_eq!(
+            mirrored.pacman_start(),
+            &(Point2::new(13, 7), Direction::Right)
+        );
+        assert_eq!(mirrored.ghosts()[0].scatter_point, Point2::new(1, 29));
+        assert_eq!(
+            mirrored.ghosts()[0].start_path[0],
+            (Point2::new(14, 19), Direction::Right)
+        );
+        assert_eq!(mirrored.ghost_home_pos().0, Point2::new(15, 15));
+        assert_eq!(mirrored.fruit_position(), Some(Point2::new(14, 13)));
+        assert_eq!(mirrored.mirrored().unwrap(), setup);
+
+        let rotated = setup.rotated_180().unwrap();
+        assert_eq!(
+            rotated.pacman_start(),
+            &(Point2::new(13, 23), Direction::Right)
+        );
+        assert_eq!(
+            rotated.ghost_respawn_path().last(),
+            Some(&(Point2::new(14, 11), Direction::Down))
+        );
+        assert_eq!(rotated.rotated_180().unwrap(), setup);
+    }
+
+    #[test]
+    fn cropped_setups() {
+        let setup = PacmanAgentSetup::default();
+        let cropped = setup.cropped(1, 1, 26, 29).unwrap();
+        assert_eq!(cropped.grid(), setup.grid());
+        assert_eq!(cropped.pacman_start(), setup.pacman_start());
+
+        // fruit can be moved, but only to a walkable cell
+        let moved = setup.clone().with_fruit_position(Some(Point2::new(14, 7)));
+        assert_eq!(moved.unwrap().fruit_position(), Some(Point2::new(14, 7)));
+        assert!(setup
+            .clone()
+            .with_fruit_position(Some(Point2::new(0, 0)))
+            .is_err());
+
+        // the ghost house is not in the bottom half of the maze
+        assert!(setup.cropped(1, 1, 26, 12).is_err());
+    }
 }
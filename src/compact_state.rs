@@ -0,0 +1,381 @@
+//! A fixed-size form of [`PacmanState`] that can be copied without allocating
+//!
+//! Search algorithms clone the game state for every node they visit. [`CompactPacmanState`]
+//! stores pellets as bitsets indexed by [`ComputedGrid`] node, and ghosts in a fixed-size array,
+//! so it is [`Copy`]. It is the same [`GameState`] as [`PacmanState`], with the same `step`, so
+//! both play exactly the same game.
+//!
+//! ```
+//! use mdrc_pacbot_util::agent_setup::PacmanAgentSetup;
+//! use mdrc_pacbot_util::compact_state::CompactPacmanState;
+//! use mdrc_pacbot_util::game_rules::GameRules;
+//! use mdrc_pacbot_util::game_state::PacmanState;
+//! use rand::rngs::StdRng;
+//! use rand::SeedableRng;
+//!
+//! let agent_setup = PacmanAgentSetup::default();
+//! let rules = GameRules::default();
+//! let grid = agent_setup.grid();
+//! let mut state = PacmanState::new(&agent_setup, &rules);
+//!
+//! let mut compact = CompactPacmanState::from_state(&state, grid).unwrap();
+//! assert_eq!(compact.to_state(grid), state);
+//!
+//! state.resume();
+//! compact.resume();
+//! let events = state.step(&agent_setup, &rules, &mut StdRng::seed_from_u64(0), false);
+//! let compact_events = compact.step(&agent_setup, &rules, &mut StdRng::seed_from_u64(0), false);
+//! assert_eq!(events, compact_events);
+//! assert_eq!(compact.to_state(grid), state);
+//! ```
+
+use crate::game_state::{Agent, GameState, Ghost, GhostList, GhostType, PacmanState, PelletSet};
+use crate::grid::{ComputedGrid, Direction, GridValue, MAX_GRID_SIZE};
+use anyhow::{anyhow, Error};
+use rapier2d::na::Point2;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Formatter};
+use std::ops::{Deref, DerefMut};
+
+/// The most walkable nodes a [`PelletBitset`] can hold, enough for any valid grid
+pub const MAX_NODES: usize = MAX_GRID_SIZE * MAX_GRID_SIZE;
+
+/// The most ghosts a [`GhostArray`] can hold
+pub const MAX_GHOSTS: usize = 4;
+
+/// A [`GameState`] that stores pellets as bitsets and ghosts in a fixed-size array
+///
+/// The grid may have at most [`MAX_NODES`] walkable nodes, and the agent setup at most
+/// [`MAX_GHOSTS`] ghosts; [`CompactPacmanState::new`] panics otherwise.
+pub type CompactPacmanState = GameState<PelletBitset, PelletBitset, GhostArray>;
+
+/// A set of walkable nodes, indexed as in [`ComputedGrid::walkable_nodes`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PelletBitset {
+    bits: [u64; MAX_NODES / 64],
+}
+
+impl PelletBitset {
+    /// Create an empty set
+    pub fn new() -> Self {
+        Self {
+            bits: [0; MAX_NODES / 64],
+        }
+    }
+
+    /// Test if the set contains a node
+    pub fn contains(&self, node: usize) -> bool {
+        node < MAX_NODES && self.bits[node / 64] & (1 << (node % 64)) != 0
+    }
+
+    /// Add a node to the set
+    ///
+    /// Panics if the node is not less than [`MAX_NODES`].
+    pub fn insert(&mut self, node: usize) {
+        self.bits[node / 64] |= 1 << (node % 64);
+    }
+
+    /// Remove a node from the set, returning whether it was there
+    pub fn remove(&mut self, node: usize) -> bool {
+        let contained = self.contains(node);
+        if contained {
+            self.bits[node / 64] &= !(1 << (node % 64));
+        }
+        contained
+    }
+
+    /// Get the number of nodes in the set
+    pub fn len(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// Test if the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|word| *word == 0)
+    }
+
+    /// Iterate over the nodes in the set, in increasing order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..MAX_NODES).filter(|node| self.contains(*node))
+    }
+}
+
+impl Default for PelletBitset {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PelletSet for PelletBitset {
+    fn refill(&mut self, grid: &ComputedGrid, value: GridValue) {
+        *self = Self::new();
+        for (node, p) in grid.walkable_nodes().iter().enumerate() {
+            if grid.at(p) == Some(value) {
+                self.insert(node);
+            }
+        }
+    }
+
+    fn has_pellet(&self, _location: Point2<u8>, node: usize) -> bool {
+        self.contains(node)
+    }
+
+    fn eat(&mut self, _location: Point2<u8>, node: usize) -> bool {
+        self.remove(node)
+    }
+
+    fn put_back(&mut self, _grid: &ComputedGrid, _location: Point2<u8>, node: usize) {
+        self.insert(node);
+    }
+
+    fn remaining(&self) -> usize {
+        self.len()
+    }
+}
+
+/// Up to [`MAX_GHOSTS`] ghosts, stored inline
+#[derive(Clone, Copy)]
+pub struct GhostArray {
+    ghosts: [Ghost; MAX_GHOSTS],
+    len: u8,
+}
+
+impl Default for GhostArray {
+    fn default() -> Self {
+        // fills the unused slots; only the first `len` ghosts are ever read
+        let unused = Ghost {
+            agent: Agent {
+                location: Point2::new(0, 0),
+                direction: Direction::Right,
+            },
+            color: GhostType::Red,
+            frightened_counter: 0,
+            respawn_timer: 0,
+            previous_location: Point2::new(0, 0),
+        };
+        Self {
+            ghosts: [unused; MAX_GHOSTS],
+            len: 0,
+        }
+    }
+}
+
+impl Deref for GhostArray {
+    type Target = [Ghost];
+
+    fn deref(&self) -> &[Ghost] {
+        &self.ghosts[..self.len as usize]
+    }
+}
+
+impl DerefMut for GhostArray {
+    fn deref_mut(&mut self) -> &mut [Ghost] {
+        &mut self.ghosts[..self.len as usize]
+    }
+}
+
+impl GhostList for GhostArray {
+    fn clear(&mut self) {
+        self.len = 0;
+    }
+
+    /// Panics if there are already [`MAX_GHOSTS`] ghosts
+    fn push(&mut self, ghost: Ghost) {
+        assert!(
+            (self.len as usize) < MAX_GHOSTS,
+            "A GhostArray holds at most {} ghosts",
+            MAX_GHOSTS
+        );
+        self.ghosts[self.len as usize] = ghost;
+        self.len += 1;
+    }
+}
+
+impl PartialEq for GhostArray {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+impl Eq for GhostArray {}
+
+impl Debug for GhostArray {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl CompactPacmanState {
+    /// Convert a [`PacmanState`] on the given grid
+    ///
+    /// Fails if the state doesn't fit, or if converting it back would not give the same state:
+    /// the pellets must match the grid's walkable nodes, and the super pellets must be on
+    /// walkable nodes, in the same order as [`ComputedGrid::walkable_nodes`].
+    pub fn from_state(state: &PacmanState, grid: &ComputedGrid) -> Result<Self, Error> {
+        let nodes = grid.walkable_nodes();
+        if nodes.len() > MAX_NODES {
+            return Err(anyhow!(
+                "The grid has {} walkable nodes, but at most {} are supported",
+                nodes.len(),
+                MAX_NODES
+            ));
+        }
+        if state.pellets().len() != nodes.len() {
+            return Err(anyhow!(
+                "There are {} pellet flags, but the grid has {} walkable nodes",
+                state.pellets().len(),
+                nodes.len()
+            ));
+        }
+        if state.ghosts.len() > MAX_GHOSTS {
+            return Err(anyhow!(
+                "There are {} ghosts, but at most {} are supported",
+                state.ghosts.len(),
+                MAX_GHOSTS
+            ));
+        }
+
+        let mut pellets = PelletBitset::new();
+        for (node, pellet) in state.pellets().iter().enumerate() {
+            if *pellet {
+                pellets.insert(node);
+            }
+        }
+
+        let mut power_pellets = PelletBitset::new();
+        let mut previous = None;
+        for p in state.power_pellets() {
+            let node = grid
+                .coords_to_node(p)
+                .ok_or_else(|| anyhow!("The super pellet at {} is not walkable", p))?;
+            if previous.is_some_and(|previous| previous >= node) {
+                return Err(anyhow!(
+                    "The super pellets are not in the order of the grid's walkable nodes"
+                ));
+            }
+            power_pellets.insert(node);
+            previous = Some(node);
+        }
+
+        let mut ghosts = GhostArray::default();
+        for ghost in &state.ghosts {
+            ghosts.push(*ghost);
+        }
+
+        Ok(state.with_storage(pellets, power_pellets, ghosts))
+    }
+
+    /// Convert back to a [`PacmanState`] on the given grid
+    pub fn to_state(&self, grid: &ComputedGrid) -> PacmanState {
+        let nodes = grid.walkable_nodes();
+        self.with_storage(
+            (0..nodes.len())
+                .map(|node| self.pellets().contains(node))
+                .collect(),
+            self.power_pellets()
+                .iter()
+                .map(|node| nodes[node])
+                .collect(),
+            self.ghosts.to_vec(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agent_setup::PacmanAgentSetup;
+    use crate::compact_state::{CompactPacmanState, PelletBitset, MAX_NODES};
+    use crate::game_rules::GameRules;
+    use crate::game_state::{play_seeded, PacmanState};
+    use crate::grid::facing_direction;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rapier2d::na::Point2;
+
+    #[test]
+    fn bitset() {
+        let mut set = PelletBitset::new();
+        assert!(set.is_empty());
+        for node in [0, 63, 64, MAX_NODES - 1] {
+            set.insert(node);
+        }
+        assert_eq!(set.len(), 4);
+        assert_eq!(
+            set.iter().collect::<Vec<_>>(),
+            vec![0, 63, 64, MAX_NODES - 1]
+        );
+        assert!(set.remove(63));
+        assert!(!set.remove(63));
+        assert!(!set.contains(63));
+        assert!(!set.contains(MAX_NODES));
+        assert_eq!(set.len(), 3);
+    }
+
+    #[test]
+    fn conversion() {
+        let agent_setup = PacmanAgentSetup::default();
+        let rules = GameRules::default();
+        let grid = agent_setup.grid();
+        let state = PacmanState::new(&agent_setup, &rules);
+
+        let compact = CompactPacmanState::from_state(&state, grid).unwrap();
+        assert_eq!(compact, CompactPacmanState::new(&agent_setup, &rules));
+        assert_eq!(compact.to_state(grid), state);
+        assert_eq!(
+            compact.pellets().len(),
+            state.pellets().iter().filter(|p| **p).count()
+        );
+        assert_eq!(compact.power_pellets().len(), state.power_pellets().len());
+        assert_eq!(compact.ghosts.len(), state.ghosts.len());
+
+        // states that would not convert back exactly are rejected
+        let mut unordered = state.clone();
+        let mut reversed = state.power_pellets().clone();
+        reversed.reverse();
+        unordered.set_pellets(grid, state.pellets().clone(), reversed);
+        assert!(CompactPacmanState::from_state(&unordered, grid).is_err());
+        let mut in_wall = state.clone();
+        in_wall.set_pellets(grid, state.pellets().clone(), vec![Point2::new(0, 0)]);
+        assert!(CompactPacmanState::from_state(&in_wall, grid).is_err());
+        let mut short = state.clone();
+        let mut pellets = state.pellets().clone();
+        pellets.pop();
+        short.set_pellets(grid, pellets, state.power_pellets().clone());
+        assert!(CompactPacmanState::from_state(&short, grid).is_err());
+        let mut crowded = state;
+        crowded.ghosts.push(crowded.ghosts[0]);
+        assert!(CompactPacmanState::from_state(&crowded, grid).is_err());
+    }
+
+    #[test]
+    fn steps_identically() {
+        let agent_setup = PacmanAgentSetup::default();
+        let rules = GameRules::default();
+        let grid = agent_setup.grid();
+        let mut compact = CompactPacmanState::new(&agent_setup, &rules);
+        compact.reset(&agent_setup, &rules, false);
+        let mut compact_rng = StdRng::seed_from_u64(3);
+
+        let state = play_seeded(&agent_setup, &rules, 3, |frame, state, location, rng| {
+            let direction = facing_direction(&state.pacman.location, &location);
+            state.update_pacman(location, direction);
+            compact.update_pacman(location, direction);
+            state.resume();
+            compact.resume();
+
+            let events = state.step(&agent_setup, &rules, rng, false);
+            let compact_events = compact.step(&agent_setup, &rules, &mut compact_rng, false);
+            assert_eq!(events, compact_events);
+            assert_eq!(compact.to_state(grid), *state);
+            assert_eq!(compact.zobrist_hash(), state.zobrist_hash());
+            assert_eq!(compact.status(&rules), state.status(&rules));
+            assert!(frame < 10_000);
+            !state.is_game_over(&rules)
+        });
+        assert!(state.is_game_over(&rules));
+        assert!(compact.is_game_over(&rules));
+    }
+}
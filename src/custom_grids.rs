@@ -0,0 +1,425 @@
+//! Grids that are loaded at runtime, and a registry that lists them alongside the [`StandardGrid`]s
+//!
+//! A map file starts with optional `key: value` header lines, followed by the [`Grid`] in the
+//! [text format](crate::grid::text_format):
+//!
+//! ```text
+//! name: Small Loop
+//! pacbot: 1 1 0
+//! soft_boundaries: -1 -1 8 7
+//! tunnel: 0 3 7 3
+//! IIIIIIII
+//! IooooooI
+//! IoIIIIoI
+//! ooIIIIoo
+//! IoIIIIoI
+//! IooooooI
+//! IIIIIIII
+//! ```
+//!
+//! - `name` is shown in the GUI; it defaults to the file name without its extension
+//! - `pacbot` is Pacbot's default x, y and angle; it defaults to the first walkable cell, facing right
+//! - `soft_boundaries` is the left, bottom, right and top of the area to show; it defaults to the
+//!   whole grid, with a border of one cell
+//! - `tunnel` connects two edge cells, and may be repeated
+
+use crate::grid::text_format::{grid_to_string, parse_grid};
+use crate::grid::{ComputedGrid, Grid, Tunnel};
+use crate::standard_grids::StandardGrid;
+use anyhow::{anyhow, Error};
+use rapier2d::na::{Isometry2, Point2, Vector2};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// The file extension used for map files
+pub const MAP_FILE_EXTENSION: &str = "map";
+
+/// A rectangle in world coordinates that a map is expected to stay within
+///
+/// Unlike walls, nothing stops Pacbot from leaving it; it is the area shown by the GUI.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SoftBoundaries {
+    /// The bottom left corner
+    pub left_bottom: Point2<f32>,
+    /// The top right corner
+    pub right_top: Point2<f32>,
+}
+
+impl SoftBoundaries {
+    /// The whole [`Grid`], with a border of one cell
+    pub fn around(grid: &Grid) -> Self {
+        Self {
+            left_bottom: Point2::new(-1.0, -1.0),
+            right_top: Point2::new(grid.width() as f32, grid.height() as f32),
+        }
+    }
+}
+
+/// A [`Grid`] that is not compiled in, along with the information a [`StandardGrid`] provides
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CustomGrid {
+    name: String,
+    grid: Grid,
+    tunnels: Vec<Tunnel>,
+    pacbot_isometry: Isometry2<f32>,
+    soft_boundaries: SoftBoundaries,
+}
+
+impl CustomGrid {
+    /// Create a new CustomGrid, checking that the grid and tunnels make a valid [`ComputedGrid`]
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::{Isometry2, Vector2};
+    /// use mdrc_pacbot_util::custom_grids::{CustomGrid, SoftBoundaries};
+    /// use mdrc_pacbot_util::grid::Grid;
+    /// use mdrc_pacbot_util::standard_grids::GRID_OUTER;
+    ///
+    /// let grid = Grid::from(GRID_OUTER);
+    /// let custom = CustomGrid::new(
+    ///     "Outer".to_string(),
+    ///     grid.clone(),
+    ///     vec![],
+    ///     Isometry2::new(Vector2::new(1.0, 1.0), 0.0),
+    ///     SoftBoundaries::around(&grid),
+    /// )
+    /// .unwrap();
+    ///
+    /// assert!(CustomGrid::new(
+    ///     "Walls".to_string(),
+    ///     Grid::new(5, 5),
+    ///     vec![],
+    ///     Isometry2::new(Vector2::new(1.0, 1.0), 0.0),
+    ///     SoftBoundaries::around(&grid),
+    /// )
+    /// .is_err());
+    /// ```
+    pub fn new(
+        name: String,
+        grid: Grid,
+        tunnels: Vec<Tunnel>,
+        pacbot_isometry: Isometry2<f32>,
+        soft_boundaries: SoftBoundaries,
+    ) -> Result<Self, Error> {
+        if name.trim().is_empty() {
+            return Err(anyhow!("Custom grid name is empty"));
+        }
+        ComputedGrid::with_tunnels(grid.clone(), tunnels.clone())?;
+        Ok(Self {
+            name,
+            grid,
+            tunnels,
+            pacbot_isometry,
+            soft_boundaries,
+        })
+    }
+
+    /// Parse a CustomGrid from the map file format, using `default_name` if there is no `name`
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::custom_grids::CustomGrid;
+    ///
+    /// let text = "pacbot: 1 1 0\nIIIIII\nIeeeeI\nIeIIeI\nIeIIeI\nIeeeeI\nIIIIII\n";
+    /// let custom = CustomGrid::parse(text, "Tiny").unwrap();
+    /// assert_eq!(custom.name(), "Tiny");
+    /// assert_eq!(custom.grid().width(), 6);
+    /// assert_eq!(CustomGrid::parse(&custom.to_text(), "Other").unwrap(), custom);
+    /// ```
+    pub fn parse(text: &str, default_name: &str) -> Result<Self, Error> {
+        let mut name = default_name.to_string();
+        let mut pacbot = None;
+        let mut soft_boundaries = None;
+        let mut tunnels = vec![];
+
+        let mut header_lines = 0;
+        for (i, line) in text.lines().enumerate() {
+            let Some((key, value)) = line.split_once(':') else {
+                break;
+            };
+            header_lines += 1;
+            let numbers: Option<Vec<f32>> =
+                value.split_whitespace().map(|n| n.parse().ok()).collect();
+            match (key.trim(), numbers) {
+                ("name", _) => name = value.trim().to_string(),
+                ("pacbot", Some(n)) if n.len() == 3 => {
+                    pacbot = Some(Isometry2::new(Vector2::new(n[0], n[1]), n[2]))
+                }
+                ("soft_boundaries", Some(n)) if n.len() == 4 => {
+                    soft_boundaries = Some(SoftBoundaries {
+                        left_bottom: Point2::new(n[0], n[1]),
+                        right_top: Point2::new(n[2], n[3]),
+                    })
+                }
+                ("tunnel", Some(n)) if n.len() == 4 && n.iter().all(|x| *x >= 0.0) => {
+                    tunnels.push((
+                        Point2::new(n[0] as u8, n[1] as u8),
+                        Point2::new(n[2] as u8, n[3] as u8),
+                    ))
+                }
+                (key, _) => return Err(anyhow!("line {}: invalid {:?} header", i + 1, key)),
+            }
+        }
+
+        let body: Vec<&str> = text.lines().skip(header_lines).collect();
+        let grid = parse_grid(&body.join("\n")).map_err(|mut e| {
+            e.line += header_lines;
+            anyhow!("{}", e)
+        })?;
+        let pacbot_isometry = match pacbot {
+            Some(isometry) => isometry,
+            None => {
+                let computed_grid = ComputedGrid::with_tunnels(grid.clone(), tunnels.clone())?;
+                let start = computed_grid.walkable_nodes()[0];
+                Isometry2::new(Vector2::new(start.x as f32, start.y as f32), 0.0)
+            }
+        };
+        let soft_boundaries = soft_boundaries.unwrap_or(SoftBoundaries::around(&grid));
+        Self::new(name, grid, tunnels, pacbot_isometry, soft_boundaries)
+    }
+
+    /// Write the CustomGrid in the map file format, including every header
+    pub fn to_text(&self) -> String {
+        let mut s = format!("name: {}\n", self.name);
+        let pacbot = &self.pacbot_isometry;
+        s += &format!(
+            "pacbot: {} {} {}\n",
+            pacbot.translation.x,
+            pacbot.translation.y,
+            pacbot.rotation.angle()
+        );
+        let boundaries = &self.soft_boundaries;
+        s += &format!(
+            "soft_boundaries: {} {} {} {}\n",
+            boundaries.left_bottom.x,
+            boundaries.left_bottom.y,
+            boundaries.right_top.x,
+            boundaries.right_top.y
+        );
+        for (a, b) in &self.tunnels {
+            s += &format!("tunnel: {} {} {} {}\n", a.x, a.y, b.x, b.y);
+        }
+        s + &grid_to_string(&self.grid)
+    }
+
+    /// Load a CustomGrid from a map file, named after the file if it has no `name` header
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        let default_name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Self::parse(&text, &default_name).map_err(|e| anyhow!("{}: {}", path.display(), e))
+    }
+
+    /// Save the CustomGrid to a map file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        fs::write(path, self.to_text())?;
+        Ok(())
+    }
+
+    /// Get the name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the [`Grid`]
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// Get the tunnels
+    pub fn tunnels(&self) -> &Vec<Tunnel> {
+        &self.tunnels
+    }
+
+    /// Get the default Pacbot [`Isometry2`]
+    pub fn pacbot_isometry(&self) -> &Isometry2<f32> {
+        &self.pacbot_isometry
+    }
+
+    /// Get the [`SoftBoundaries`]
+    pub fn soft_boundaries(&self) -> &SoftBoundaries {
+        &self.soft_boundaries
+    }
+}
+
+/// The grids available to choose from, starting with every built-in [`StandardGrid`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct GridRegistry {
+    grids: Vec<StandardGrid>,
+}
+
+impl Default for GridRegistry {
+    fn default() -> Self {
+        Self {
+            grids: StandardGrid::get_all(),
+        }
+    }
+}
+
+impl GridRegistry {
+    /// Get every registered grid, built-ins first
+    pub fn grids(&self) -> &Vec<StandardGrid> {
+        &self.grids
+    }
+
+    /// Find a registered grid by name
+    pub fn get(&self, name: &str) -> Option<&StandardGrid> {
+        self.grids.iter().find(|grid| grid.name() == name)
+    }
+
+    /// Add a custom grid, replacing any custom grid with the same name
+    ///
+    /// Built-in grids can't be replaced.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::custom_grids::{CustomGrid, GridRegistry};
+    ///
+    /// let mut registry = GridRegistry::default();
+    /// let custom = CustomGrid::parse("IIIIII\nIeeeeI\nIeIIeI\nIeIIeI\nIeeeeI\nIIIIII\n", "Tiny").unwrap();
+    /// let grid = registry.register(custom).unwrap();
+    ///
+    /// assert_eq!(registry.get("Tiny"), Some(&grid));
+    /// assert_eq!(grid.compute_grid().walkable_nodes().len(), 12);
+    /// ```
+    pub fn register(&mut self, custom_grid: CustomGrid) -> Result<StandardGrid, Error> {
+        let name = custom_grid.name().to_string();
+        let grid = StandardGrid::Custom(Box::new(custom_grid));
+        match self.grids.iter().position(|g| g.name() == name) {
+            Some(i) if matches!(self.grids[i], StandardGrid::Custom(_)) => {
+                self.grids[i] = grid.clone()
+            }
+            Some(_) => return Err(anyhow!("{:?} is the name of a built-in grid", name)),
+            None => self.grids.push(grid.clone()),
+        }
+        Ok(grid)
+    }
+
+    /// Register every map file in a directory, in order of file name
+    ///
+    /// Returns the grids that were registered; fails without registering anything if any map file
+    /// is invalid or has the name of a built-in grid.
+    pub fn load_dir(&mut self, dir: impl AsRef<Path>) -> Result<Vec<StandardGrid>, Error> {
+        let mut paths = vec![];
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext == MAP_FILE_EXTENSION)
+            {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        let custom_grids = paths
+            .iter()
+            .map(CustomGrid::load)
+            .collect::<Result<Vec<_>, Error>>()?;
+        // register into a copy, so that a name clash partway through leaves this unchanged
+        let mut registry = self.clone();
+        let grids = custom_grids
+            .into_iter()
+            .map(|custom_grid| registry.register(custom_grid))
+            .collect::<Result<Vec<_>, Error>>()?;
+        *self = registry;
+        Ok(grids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::standard_grids::tunnel_grid;
+
+    /// [`tunnel_grid`] as a [`CustomGrid`]
+    fn tunnel_custom_grid() -> CustomGrid {
+        let (grid, tunnel) = tunnel_grid();
+        CustomGrid::new(
+            "Tunnel".to_string(),
+            grid,
+            vec![tunnel],
+            Isometry2::new(Vector2::new(3.0, 5.0), 1.5),
+            SoftBoundaries {
+                left_bottom: Point2::new(-1.0, 0.0),
+                right_top: Point2::new(32.0, 10.5),
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let custom = tunnel_custom_grid();
+        let text = custom.to_text();
+        assert!(text.contains("tunnel: 0 5 31 5\n"));
+        assert_eq!(CustomGrid::parse(&text, "Other").unwrap(), custom);
+
+        let standard = StandardGrid::Custom(Box::new(custom.clone()));
+        assert_eq!(standard.name(), "Tunnel");
+        assert_eq!(
+            standard.get_default_pacbot_isometry(),
+            *custom.pacbot_isometry()
+        );
+        assert_eq!(standard.get_soft_boundaries(), *custom.soft_boundaries());
+        let computed = standard.compute_grid();
+        assert_eq!(
+            computed.dist(&Point2::new(1, 5), &Point2::new(30, 5)),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(CustomGrid::parse("pacbot: 1 1\nIII\nIeI\nIII\n", "Bad").is_err());
+        assert!(CustomGrid::parse("colour: blue\nIII\nIeI\nIII\n", "Bad").is_err());
+        // an error in the grid reports its line in the whole file
+        let error = CustomGrid::parse("name: Bad\nIII\nIxI\nIII\n", "Bad").unwrap_err();
+        assert!(error.to_string().starts_with("3:2"), "{}", error);
+        // no walkable cells
+        assert!(CustomGrid::parse("III\nIII\nIII\n", "Bad").is_err());
+    }
+
+    #[test]
+    fn registry() {
+        let dir = std::env::temp_dir().join(format!("pacbot-maps-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        tunnel_custom_grid().save(dir.join("b.map")).unwrap();
+        fs::write(
+            dir.join("a.map"),
+            "IIIIII\nIeeeeI\nIeIIeI\nIeIIeI\nIeeeeI\nIIIIII\n",
+        )
+        .unwrap();
+        fs::write(dir.join("notes.txt"), "not a map").unwrap();
+
+        let mut registry = GridRegistry::default();
+        let loaded = registry.load_dir(&dir).unwrap();
+        assert_eq!(
+            loaded.iter().map(|g| g.name()).collect::<Vec<_>>(),
+            vec!["a", "Tunnel"]
+        );
+        assert_eq!(registry.grids().len(), StandardGrid::get_all().len() + 2);
+
+        // loading again replaces the grids instead of adding them twice
+        registry.load_dir(&dir).unwrap();
+        assert_eq!(registry.grids().len(), StandardGrid::get_all().len() + 2);
+
+        fs::write(
+            dir.join("c.map"),
+            "name: Pacman\nIIIIII\nIeeeeI\nIeIIeI\nIeIIeI\nIeeeeI\nIIIIII\n",
+        )
+        .unwrap();
+        // the valid maps before the clash are not registered either
+        let mut failed = GridRegistry::default();
+        assert!(failed.load_dir(&dir).is_err());
+        assert_eq!(failed, GridRegistry::default());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
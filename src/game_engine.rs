@@ -0,0 +1,429 @@
+//! A headless game of Pacman, driven by one action per tick
+//!
+//! Unlike the GUI, which moves Pacman to wherever the physics simulation puts the robot,
+//! [`GameEngine`] moves Pacman one cell at a time on the grid. Nothing is drawn and no physics
+//! is simulated, so many games can be played quickly, e.g. to evaluate strategies.
+//!
+//! Engines made with [`GameEngine::with_history`] record every tick in a [`StateHistory`], so it
+//! can be undone and redone with [`GameEngine::undo`] and [`GameEngine::redo`]. Recording is off
+//! by default, so that playing many games doesn't allocate a history for each one.
+
+use crate::agent_setup::PacmanAgentSetup;
+use crate::game_rules::GameRules;
+use crate::game_state::{GameEvent, GameStatus, PacmanState};
+use crate::grid::Direction;
+use crate::history::StateHistory;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+/// What Pacman does during one tick
+///
+/// The values match the indices of [`ComputedGrid::valid_actions`].
+///
+/// [`ComputedGrid::valid_actions`]: crate::grid::ComputedGrid::valid_actions
+#[derive(
+    Clone, Copy, Debug, Eq, PartialEq, IntoPrimitive, TryFromPrimitive, Serialize, Deserialize,
+)]
+#[repr(u8)]
+pub enum PacmanAction {
+    /// Stay in place
+    Stay = 0,
+    /// Move one cell right
+    Right = 1,
+    /// Move one cell left
+    Left = 2,
+    /// Move one cell up
+    Up = 3,
+    /// Move one cell down
+    Down = 4,
+}
+
+impl PacmanAction {
+    /// All actions, in the same order as [`ComputedGrid::valid_actions`]
+    ///
+    /// [`ComputedGrid::valid_actions`]: crate::grid::ComputedGrid::valid_actions
+    pub const ALL: [PacmanAction; 5] = [
+        PacmanAction::Stay,
+        PacmanAction::Right,
+        PacmanAction::Left,
+        PacmanAction::Up,
+        PacmanAction::Down,
+    ];
+
+    /// Get the direction Pacman moves in, or `None` for [`PacmanAction::Stay`]
+    pub fn direction(self) -> Option<Direction> {
+        match self {
+            PacmanAction::Stay => None,
+            PacmanAction::Right => Some(Direction::Right),
+            PacmanAction::Left => Some(Direction::Left),
+            PacmanAction::Up => Some(Direction::Up),
+            PacmanAction::Down => Some(Direction::Down),
+        }
+    }
+}
+
+impl From<Direction> for PacmanAction {
+    fn from(direction: Direction) -> Self {
+        match direction {
+            Direction::Right => PacmanAction::Right,
+            Direction::Left => PacmanAction::Left,
+            Direction::Up => PacmanAction::Up,
+            Direction::Down => PacmanAction::Down,
+        }
+    }
+}
+
+/// The outcome of one tick of a [`GameEngine`]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct StepResult {
+    /// The increase in score
+    pub reward: usize,
+    /// What happened, in the order it happened
+    pub events: Vec<GameEvent>,
+    /// Whether the game is still going after the tick
+    pub status: GameStatus,
+}
+
+/// Plays a game of Pacman without the GUI or physics
+///
+/// # Examples
+///
+/// ```
+/// use mdrc_pacbot_util::agent_setup::PacmanAgentSetup;
+/// use mdrc_pacbot_util::game_engine::{GameEngine, PacmanAction};
+/// use mdrc_pacbot_util::game_rules::GameRules;
+/// use mdrc_pacbot_util::game_state::GameEvent;
+/// use rapier2d::na::Point2;
+///
+/// let mut engine = GameEngine::new(PacmanAgentSetup::default(), GameRules::default(), 0);
+/// assert_eq!(engine.state().pacman.location, Point2::new(14, 7));
+///
+/// // there is no pellet where Pacman starts, or next to it
+/// assert_eq!(engine.step(PacmanAction::Left).reward, 0);
+/// let result = engine.step(PacmanAction::Left);
+/// assert_eq!(engine.state().pacman.location, Point2::new(12, 7));
+/// assert_eq!(result.reward, 10);
+/// assert_eq!(
+///     result.events,
+///     vec![GameEvent::PelletEaten {
+///         location: Point2::new(12, 7),
+///         score: 10
+///     }]
+/// );
+///
+/// // walls block Pacman
+/// assert!(!engine.valid_actions().contains(&PacmanAction::Down));
+/// engine.step(PacmanAction::Down);
+/// assert_eq!(engine.state().pacman.location, Point2::new(12, 7));
+/// ```
+#[derive(Clone, Debug)]
+pub struct GameEngine<R: Rng = StdRng> {
+    /// Initial positions of Pacman, ghosts, etc.
+    agent_setup: PacmanAgentSetup,
+    /// Scoring and timing rules
+    rules: GameRules,
+    /// Current game state
+    state: PacmanState,
+    /// The ticks that can be undone and redone, if they are being recorded
+    history: Option<StateHistory>,
+    /// Decides the moves of frightened ghosts
+    rng: R,
+}
+
+impl GameEngine<StdRng> {
+    /// Create a new GameEngine whose randomness is decided by a seed
+    pub fn new(agent_setup: PacmanAgentSetup, rules: GameRules, seed: u64) -> Self {
+        Self::with_rng(agent_setup, rules, StdRng::seed_from_u64(seed))
+    }
+}
+
+impl<R: Rng> GameEngine<R> {
+    /// Create a new GameEngine that uses the given random number generator
+    pub fn with_rng(agent_setup: PacmanAgentSetup, rules: GameRules, rng: R) -> Self {
+        let mut state = PacmanState::new(&agent_setup, &rules);
+        state.reset(&agent_setup, &rules, false);
+        Self {
+            agent_setup,
+            rules,
+            state,
+            history: None,
+            rng,
+        }
+    }
+
+    /// Record ticks from now on, so that the most recent `max_len` can be undone
+    pub fn with_history(mut self, max_len: usize) -> Self {
+        self.history = Some(StateHistory::with_max_len(max_len));
+        self
+    }
+
+    /// Get the initial positions of Pacman, ghosts, etc.
+    pub fn agent_setup(&self) -> &PacmanAgentSetup {
+        &self.agent_setup
+    }
+
+    /// Get the scoring and timing rules
+    pub fn rules(&self) -> &GameRules {
+        &self.rules
+    }
+
+    /// Get the current game state
+    pub fn state(&self) -> &PacmanState {
+        &self.state
+    }
+
+    /// Get the ticks that can be undone and redone, if they are being recorded
+    pub fn history(&self) -> Option<&StateHistory> {
+        self.history.as_ref()
+    }
+
+    /// Start a new game, keeping the random number generator's state
+    ///
+    /// The history is cleared.
+    pub fn reset(&mut self) {
+        self.state.reset(&self.agent_setup, &self.rules, false);
+        if let Some(history) = &mut self.history {
+            history.clear();
+        }
+    }
+
+    /// Undo the most recent tick, returning whether there was one
+    ///
+    /// Without [`GameEngine::with_history`], there is never a tick to undo. The random number
+    /// generator is not rewound, so frightened ghosts may move differently if the tick is played
+    /// again.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::agent_setup::PacmanAgentSetup;
+    /// use mdrc_pacbot_util::game_engine::{GameEngine, PacmanAction};
+    /// use mdrc_pacbot_util::game_rules::GameRules;
+    ///
+    /// let mut engine =
+    ///     GameEngine::new(PacmanAgentSetup::default(), GameRules::default(), 0).with_history(100);
+    /// let start = engine.state().clone();
+    /// engine.step(PacmanAction::Left);
+    /// engine.step(PacmanAction::Left);
+    /// let score = engine.state().score;
+    ///
+    /// assert!(engine.undo());
+    /// assert!(engine.undo());
+    /// assert!(!engine.undo());
+    /// assert_eq!(*engine.state(), start);
+    ///
+    /// engine.redo();
+    /// engine.redo();
+    /// assert_eq!(engine.state().score, score);
+    /// ```
+    pub fn undo(&mut self) -> bool {
+        match &mut self.history {
+            Some(history) => history.undo(&mut self.state, self.agent_setup.grid()),
+            None => false,
+        }
+    }
+
+    /// Redo the most recently undone tick, returning whether there was one
+    ///
+    /// Any undone ticks are forgotten once [`GameEngine::step`] is called.
+    pub fn redo(&mut self) -> bool {
+        match &mut self.history {
+            Some(history) => history.redo(&mut self.state, self.agent_setup.grid()),
+            None => false,
+        }
+    }
+
+    /// Get whether the game is still going, and if not, how it ended
+    pub fn status(&self) -> GameStatus {
+        self.state.status(&self.rules)
+    }
+
+    /// Test if the game is over, because Pacman won or has no lives left
+    pub fn is_game_over(&self) -> bool {
+        self.state.is_game_over(&self.rules)
+    }
+
+    /// Get the actions that move Pacman from his current location, including staying still
+    pub fn valid_actions(&self) -> Vec<PacmanAction> {
+        let valid = self
+            .agent_setup
+            .grid()
+            .valid_actions(self.state.pacman.location)
+            .unwrap_or_default();
+        PacmanAction::ALL
+            .into_iter()
+            .filter(|action| valid[u8::from(*action) as usize])
+            .collect()
+    }
+
+    /// Move Pacman according to the action, then step the game forward one frame
+    ///
+    /// Actions that would move Pacman into a wall leave him where he is.
+    pub fn step(&mut self, action: PacmanAction) -> StepResult {
+        if self.is_game_over() {
+            return StepResult {
+                reward: 0,
+                events: vec![],
+                status: self.status(),
+            };
+        }
+
+        let mut next = None;
+        if let Some(direction) = action.direction() {
+            if self.valid_actions().contains(&action) {
+                let grid = self.agent_setup.grid();
+                next = grid
+                    .next(&self.state.pacman.location, &direction)
+                    .map(|p| (p, direction));
+            }
+        }
+
+        let (agent_setup, rules, rng) = (&self.agent_setup, &self.rules, &mut self.rng);
+        let mut tick = |state: &mut PacmanState| {
+            if let Some((p, direction)) = next {
+                state.update_pacman(p, direction);
+            }
+            // losing a life or clearing a level pauses the game, but there is nobody to resume it
+            state.resume();
+            let events = state.step(agent_setup, rules, rng, false);
+            state.pause();
+            events
+        };
+        let events = match &mut self.history {
+            Some(history) => history.record(&mut self.state, tick),
+            None => tick(&mut self.state),
+        };
+
+        StepResult {
+            reward: events.iter().map(GameEvent::score).sum(),
+            events,
+            status: self.status(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agent_setup::PacmanAgentSetup;
+    use crate::game_engine::{GameEngine, PacmanAction};
+    use crate::game_rules::GameRules;
+    use crate::game_state::{Fruit, GameEvent, GameStatus};
+    use crate::grid::Direction;
+    use rapier2d::na::Point2;
+
+    #[test]
+    fn actions() {
+        for action in PacmanAction::ALL {
+            assert_eq!(
+                action.direction().map(PacmanAction::from).unwrap_or(action),
+                action
+            );
+            assert_eq!(PacmanAction::try_from(u8::from(action)).unwrap(), action);
+        }
+
+        let engine = GameEngine::new(PacmanAgentSetup::default(), GameRules::default(), 0);
+        assert_eq!(
+            engine.valid_actions(),
+            vec![PacmanAction::Stay, PacmanAction::Right, PacmanAction::Left]
+        );
+    }
+
+    #[test]
+    fn game_over_after_losing_every_life() {
+        let rules = GameRules::default();
+        let mut engine = GameEngine::new(PacmanAgentSetup::default(), rules.clone(), 0);
+        let mut lives_lost = 0;
+        let mut ticks = 0;
+        // staying still, Pacman is eventually caught by the ghosts
+        while !engine.is_game_over() {
+            let result = engine.step(PacmanAction::Stay);
+            assert_eq!(result.reward, 0);
+            lives_lost += result
+                .events
+                .iter()
+                .filter(|e| matches!(e, GameEvent::LifeLost { .. }))
+                .count();
+            assert_eq!(result.status, engine.status());
+            ticks += 1;
+            assert!(ticks < 10_000);
+        }
+        assert_eq!(lives_lost, rules.starting_lives as usize);
+        assert_eq!(engine.status(), GameStatus::Lost);
+
+        let result = engine.step(PacmanAction::Left);
+        assert_eq!(result.status, GameStatus::Lost);
+        assert!(result.events.is_empty());
+
+        engine.reset();
+        assert!(!engine.is_game_over());
+        assert_eq!(engine.state().lives, rules.starting_lives);
+    }
+
+    #[test]
+    fn undo_every_tick() {
+        let mut engine = GameEngine::new(PacmanAgentSetup::default(), GameRules::default(), 0)
+            .with_history(usize::MAX);
+        let start = engine.state().clone();
+        let mut states = vec![];
+        while !engine.is_game_over() {
+            engine.step(PacmanAction::ALL[states.len() % 3]);
+            states.push(engine.state().clone());
+        }
+        assert_eq!(engine.history().unwrap().undo_len(), states.len());
+
+        states.pop();
+        while let Some(state) = states.pop() {
+            assert!(engine.undo());
+            assert_eq!(*engine.state(), state);
+        }
+        assert!(engine.undo());
+        assert_eq!(*engine.state(), start);
+        assert!(!engine.undo());
+
+        assert!(engine.redo());
+        engine.step(PacmanAction::Stay);
+        assert!(!engine.redo());
+        engine.reset();
+        assert_eq!(engine.history().unwrap().undo_len(), 0);
+
+        // without a history, nothing is recorded
+        let mut engine = GameEngine::new(PacmanAgentSetup::default(), GameRules::default(), 0);
+        engine.step(PacmanAction::Left);
+        assert!(engine.history().is_none());
+        assert!(!engine.undo());
+    }
+
+    #[test]
+    fn eating_events() {
+        let mut engine = GameEngine::new(PacmanAgentSetup::default(), GameRules::default(), 0);
+        let fruit = Point2::new(13, 13);
+        engine.state.fruit = Some(Fruit {
+            location: fruit,
+            remaining_time: 10,
+        });
+        engine
+            .state
+            .update_pacman(Point2::new(12, 13), Direction::Right);
+        let result = engine.step(PacmanAction::Right);
+        assert_eq!(
+            result.events,
+            vec![GameEvent::FruitEaten {
+                location: fruit,
+                score: engine.rules().fruit_score
+            }]
+        );
+        assert_eq!(result.reward, engine.rules().fruit_score);
+
+        // the power pellet at (1, 7), approached from the right
+        engine
+            .state
+            .update_pacman(Point2::new(2, 7), Direction::Left);
+        let result = engine.step(PacmanAction::Left);
+        assert!(result.events.contains(&GameEvent::PowerPelletEaten {
+            location: Point2::new(1, 7),
+            score: engine.rules().power_pellet_score
+        }));
+    }
+}
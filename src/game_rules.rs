@@ -0,0 +1,271 @@
+//! Rules that decide scoring and timing during a game of Pacman
+//!
+//! Rules can be saved to and loaded from JSON files. Any field missing from a file keeps its
+//! value from [`GameRules::official`], so a file only needs to list what it changes:
+//!
+//! ```
+//! use mdrc_pacbot_util::game_rules::GameRules;
+//!
+//! let rules = GameRules::from_json(r#"{ "starting_lives": 5, "fruit_score": 300 }"#).unwrap();
+//! assert_eq!(rules.starting_lives, 5);
+//! assert_eq!(rules.pellet_score, GameRules::official().pellet_score);
+//! ```
+
+use anyhow::{anyhow, Error};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// The file extension used for rules files
+pub const RULES_FILE_EXTENSION: &str = "json";
+
+/// How the score for eating a ghost grows with each ghost eaten during one frightened period
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum GhostScoreProgression {
+    /// The n-th ghost is worth n times the ghost score
+    Linear,
+    /// Each ghost is worth twice as much as the one before it
+    Doubling,
+}
+
+/// Timing rules that change from level to level
+///
+/// All times are in frames.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LevelRules {
+    /// Number of frames ghosts stay frightened after Pacman eats a power pellet
+    ///
+    /// If this is 0, power pellets are still worth points, but ghosts are not frightened.
+    pub frightened_length: u8,
+    /// The frames at which ghosts swap between chase and scatter mode
+    pub state_swap_times: Vec<u32>,
+}
+
+/// Scoring and timing rules for a game of Pacman
+///
+/// All times are in frames.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct GameRules {
+    /// Number of lives Pacman starts with
+    pub starting_lives: u8,
+    /// The number of levels Pacman must clear to win, or `None` to keep playing until he runs
+    /// out of lives
+    pub levels_to_win: Option<u32>,
+    /// Timing rules for each level, starting at level 1
+    ///
+    /// Levels past the end of the list use the last entry.
+    pub levels: Vec<LevelRules>,
+    /// Score for eating a pellet
+    pub pellet_score: usize,
+    /// Score for eating a power pellet
+    pub power_pellet_score: usize,
+    /// Score for the first ghost eaten during a frightened period
+    pub ghost_score: usize,
+    /// How the score for later ghosts in the same frightened period grows
+    pub ghost_score_progression: GhostScoreProgression,
+    /// Score for eating a fruit
+    pub fruit_score: usize,
+    /// The numbers of pellets and power pellets eaten at which a fruit appears
+    pub fruit_pellet_thresholds: Vec<u32>,
+    /// Number of frames a fruit stays on the board if it is not eaten
+    pub fruit_duration: u32,
+}
+
+impl GameRules {
+    /// The rules used by the official Pacbot game server, which ends the game after one level
+    pub fn official() -> Self {
+        Self {
+            starting_lives: 3,
+            levels_to_win: Some(1),
+            levels: vec![LevelRules {
+                frightened_length: 40,
+                state_swap_times: vec![35, 135, 170, 270, 295, 395, 420],
+            }],
+            pellet_score: 10,
+            power_pellet_score: 50,
+            ghost_score: 100,
+            ghost_score_progression: GhostScoreProgression::Linear,
+            fruit_score: 100,
+            fruit_pellet_thresholds: vec![70, 170],
+            fruit_duration: 60,
+        }
+    }
+
+    /// The rules of the original arcade game, at 5 frames per second
+    ///
+    /// Ghosts are frightened for less time in later levels, and there is no limit on the number of
+    /// levels.
+    pub fn classic() -> Self {
+        // seconds that ghosts are frightened in each level
+        let frightened_seconds = [6, 5, 4, 3, 2, 5, 2, 2, 1, 5, 2, 1, 1, 3, 1, 1, 0, 1, 0];
+        let levels = frightened_seconds
+            .iter()
+            .enumerate()
+            .map(|(i, seconds)| LevelRules {
+                frightened_length: seconds * 5,
+                state_swap_times: match i + 1 {
+                    1 => vec![35, 135, 170, 270, 295, 395, 420],
+                    2..=4 => vec![35, 135, 170, 270, 295, 5460, 5461],
+                    _ => vec![25, 125, 150, 250, 275, 5460, 5461],
+                },
+            })
+            .collect();
+        Self {
+            levels_to_win: None,
+            levels,
+            ghost_score: 200,
+            ghost_score_progression: GhostScoreProgression::Doubling,
+            fruit_duration: 48,
+            ..Self::official()
+        }
+    }
+
+    /// Check that a game can be played with these rules
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.starting_lives == 0 {
+            return Err(anyhow!("Pacman must start with at least one life"));
+        }
+        if self.levels_to_win == Some(0) {
+            return Err(anyhow!("Pacman must clear at least one level to win"));
+        }
+        if self.levels.is_empty() {
+            return Err(anyhow!("There must be rules for at least one level"));
+        }
+        if self.fruit_duration == 0 {
+            return Err(anyhow!(
+                "Fruit must stay on the board for at least one frame"
+            ));
+        }
+        Ok(())
+    }
+
+    /// Get the timing rules for a level, starting at 1
+    ///
+    /// If there are no level rules at all, the official level rules are used.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::game_rules::GameRules;
+    ///
+    /// let rules = GameRules::classic();
+    /// assert_eq!(rules.level(1).frightened_length, 30);
+    /// assert_eq!(rules.level(2).frightened_length, 25);
+    /// assert_eq!(rules.level(100).frightened_length, 0);
+    /// ```
+    pub fn level(&self, level: u32) -> &LevelRules {
+        static OFFICIAL_LEVEL: OnceLock<LevelRules> = OnceLock::new();
+
+        let index = (level.max(1) as usize - 1).min(self.levels.len().saturating_sub(1));
+        self.levels.get(index).unwrap_or_else(|| {
+            OFFICIAL_LEVEL.get_or_init(|| GameRules::official().levels.remove(0))
+        })
+    }
+
+    /// Get the score for eating the n-th ghost of a frightened period, starting at 1
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::game_rules::GameRules;
+    ///
+    /// assert_eq!(GameRules::official().ghost_score(3), 300);
+    /// assert_eq!(GameRules::classic().ghost_score(3), 800);
+    /// ```
+    pub fn ghost_score(&self, n: u8) -> usize {
+        let n = n.max(1) as usize;
+        match self.ghost_score_progression {
+            GhostScoreProgression::Linear => self.ghost_score.saturating_mul(n),
+            GhostScoreProgression::Doubling => self
+                .ghost_score
+                .saturating_mul(2usize.saturating_pow(n as u32 - 1)),
+        }
+    }
+
+    /// Parse and validate rules from JSON
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        let rules: Self = serde_json::from_str(json)?;
+        rules.validate()?;
+        Ok(rules)
+    }
+
+    /// Convert the rules to pretty-printed JSON
+    pub fn to_json(&self) -> Result<String, Error> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Load rules from a JSON file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let json = fs::read_to_string(path)?;
+        Self::from_json(&json).map_err(|e| anyhow!("{}: {}", path.display(), e))
+    }
+
+    /// Save the rules to a JSON file
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        fs::write(path, self.to_json()?)?;
+        Ok(())
+    }
+}
+
+impl Default for GameRules {
+    fn default() -> Self {
+        Self::official()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::game_rules::{GameRules, GhostScoreProgression};
+    use std::fs;
+
+    #[test]
+    fn presets_are_valid() {
+        GameRules::official().validate().unwrap();
+        GameRules::classic().validate().unwrap();
+        assert_eq!(GameRules::default(), GameRules::official());
+    }
+
+    #[test]
+    fn ghost_scores() {
+        let mut rules = GameRules::official();
+        let scores: Vec<usize> = (1..=4).map(|n| rules.ghost_score(n)).collect();
+        assert_eq!(scores, vec![100, 200, 300, 400]);
+
+        rules.ghost_score_progression = GhostScoreProgression::Doubling;
+        let scores: Vec<usize> = (1..=4).map(|n| rules.ghost_score(n)).collect();
+        assert_eq!(scores, vec![100, 200, 400, 800]);
+        assert_eq!(rules.ghost_score(200), usize::MAX);
+    }
+
+    #[test]
+    fn json() {
+        let rules = GameRules::classic();
+        assert_eq!(
+            GameRules::from_json(&rules.to_json().unwrap()).unwrap(),
+            rules
+        );
+        assert_eq!(GameRules::from_json("{}").unwrap(), GameRules::official());
+
+        // typos and unplayable rules are rejected
+        assert!(GameRules::from_json(r#"{ "startinglives": 5 }"#).is_err());
+        assert!(GameRules::from_json(r#"{ "levels": [] }"#).is_err());
+        assert!(GameRules::from_json(r#"{ "levels_to_win": 0 }"#).is_err());
+        assert!(GameRules::from_json(r#"{ "ghost_score_progression": "Tripling" }"#).is_err());
+    }
+
+    #[test]
+    fn files() {
+        let path = std::env::temp_dir().join(format!("pacbot-rules-{}.json", std::process::id()));
+        GameRules::classic().save(&path).unwrap();
+        assert_eq!(GameRules::load(&path).unwrap(), GameRules::classic());
+
+        fs::write(&path, "{ \"starting_lives\": 0 }").unwrap();
+        let error = GameRules::load(&path).unwrap_err();
+        assert!(error.to_string().starts_with(&path.display().to_string()));
+        fs::remove_file(&path).unwrap();
+    }
+}
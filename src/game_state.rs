@@ -1,13 +1,14 @@
 //! Structs to define the state of a game of Pacman
 use crate::agent_setup::PacmanAgentSetup;
-use crate::constants::{
-    FRIGHTENED_LENGTH, GHOST_SCORE, PELLET_SCORE, POWER_PELLET_SCORE, STARTING_LIVES,
-};
+use crate::game_rules::{GameRules, LevelRules};
+use crate::grid::pathfinding::CostMap;
 use crate::grid::{ComputedGrid, Direction, GridValue};
 use num_enum::{IntoPrimitive, TryFromPrimitive};
-use rand::rngs::ThreadRng;
+use rand::Rng;
 use rapier2d::na::Point2;
 use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+use std::ops::{Deref, DerefMut};
 
 /// Current ghost behavior - applies to all ghosts
 ///
@@ -55,7 +56,7 @@ pub struct Agent {
 pub struct Ghost {
     /// Location and direction
     pub agent: Agent,
-    /// Determines ghost behavior
+    /// Which ghost this is; determines behavior, unless the ghost's setup has a custom behavior
     pub color: GhostType,
     /// If frightened, the amount of time remaining as frightened
     ///
@@ -67,11 +68,255 @@ pub struct Ghost {
     pub previous_location: Point2<u8>,
 }
 
+/// A fruit on the board during a game of Pacman
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Fruit {
+    /// Where the fruit is
+    pub location: Point2<u8>,
+    /// The number of frames until the fruit disappears
+    pub remaining_time: u32,
+}
+
+/// Something that happened during one frame of a game of Pacman, returned by
+/// [`GameState::step`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum GameEvent {
+    /// Pacman ate a pellet
+    PelletEaten {
+        /// Where the pellet was
+        location: Point2<u8>,
+        /// The score gained
+        score: usize,
+    },
+    /// Pacman ate a power pellet, frightening the ghosts
+    PowerPelletEaten {
+        /// Where the power pellet was
+        location: Point2<u8>,
+        /// The score gained
+        score: usize,
+    },
+    /// A fruit appeared
+    FruitSpawned {
+        /// Where the fruit is
+        location: Point2<u8>,
+    },
+    /// Pacman ate the fruit
+    FruitEaten {
+        /// Where the fruit was
+        location: Point2<u8>,
+        /// The score gained
+        score: usize,
+    },
+    /// The fruit disappeared without being eaten
+    FruitExpired {
+        /// Where the fruit was
+        location: Point2<u8>,
+    },
+    /// Pacman ate a frightened ghost, sending it home
+    GhostEaten {
+        /// The ghost that was eaten
+        color: GhostType,
+        /// Where the ghost was eaten
+        location: Point2<u8>,
+        /// The score gained
+        score: usize,
+    },
+    /// A ghost caught Pacman
+    LifeLost {
+        /// Where Pacman was caught
+        location: Point2<u8>,
+        /// The number of lives left
+        lives: u8,
+    },
+    /// The ghosts changed mode
+    ModeChanged {
+        /// The mode before the change
+        from: GhostMode,
+        /// The mode after the change
+        to: GhostMode,
+    },
+    /// Pacman ate the last pellet of a level
+    LevelComplete {
+        /// The level that was cleared
+        level: u32,
+    },
+    /// The board was refilled for the next level
+    LevelStarted {
+        /// The new level
+        level: u32,
+    },
+    /// The game ended
+    GameOver {
+        /// Whether Pacman cleared the last level, rather than running out of lives
+        won: bool,
+    },
+}
+
+impl GameEvent {
+    /// Get the score gained because of this event
+    pub fn score(&self) -> usize {
+        match self {
+            GameEvent::PelletEaten { score, .. }
+            | GameEvent::PowerPelletEaten { score, .. }
+            | GameEvent::FruitEaten { score, .. }
+            | GameEvent::GhostEaten { score, .. } => *score,
+            _ => 0,
+        }
+    }
+}
+
+/// Whether a game of Pacman is still going, and if not, how it ended
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum GameStatus {
+    /// The level is being played
+    Playing,
+    /// The board has been cleared; the next step starts the next level
+    LevelComplete,
+    /// Pacman cleared the last level
+    Won,
+    /// Pacman ran out of lives
+    Lost,
+}
+
+/// The pellets or super pellets remaining on the board of a [`GameState`]
+///
+/// Pellets are identified by both their location and their index in
+/// [`ComputedGrid::walkable_nodes`], so that each implementation can use whichever suits it.
+pub trait PelletSet: Default {
+    /// Replace the contents with every walkable cell of the grid that has the given value
+    fn refill(&mut self, grid: &ComputedGrid, value: GridValue);
+    /// Test if there is a pellet at the given location
+    fn has_pellet(&self, location: Point2<u8>, node: usize) -> bool;
+    /// Remove the pellet at the given location, returning whether there was one
+    fn eat(&mut self, location: Point2<u8>, node: usize) -> bool;
+    /// Put back a pellet that was eaten
+    fn put_back(&mut self, grid: &ComputedGrid, location: Point2<u8>, node: usize);
+    /// Get the number of pellets remaining
+    fn remaining(&self) -> usize;
+}
+
+/// Whether there is a pellet at each walkable node
+impl PelletSet for Vec<bool> {
+    fn refill(&mut self, grid: &ComputedGrid, value: GridValue) {
+        self.clear();
+        self.extend(
+            grid.walkable_nodes()
+                .iter()
+                .map(|p| grid.at(p) == Some(value)),
+        );
+    }
+
+    fn has_pellet(&self, _location: Point2<u8>, node: usize) -> bool {
+        self.get(node).is_some_and(|pellet| *pellet)
+    }
+
+    fn eat(&mut self, _location: Point2<u8>, node: usize) -> bool {
+        match self.get_mut(node) {
+            Some(pellet) if *pellet => {
+                *pellet = false;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn put_back(&mut self, _grid: &ComputedGrid, _location: Point2<u8>, node: usize) {
+        if let Some(pellet) = self.get_mut(node) {
+            *pellet = true;
+        }
+    }
+
+    fn remaining(&self) -> usize {
+        self.iter().filter(|p| **p).count()
+    }
+}
+
+/// The locations of the pellets, in the order of [`ComputedGrid::walkable_nodes`]
+impl PelletSet for Vec<Point2<u8>> {
+    fn refill(&mut self, grid: &ComputedGrid, value: GridValue) {
+        self.clear();
+        self.extend(
+            grid.walkable_nodes()
+                .iter()
+                .filter(|p| grid.at(p) == Some(value)),
+        );
+    }
+
+    fn has_pellet(&self, location: Point2<u8>, _node: usize) -> bool {
+        self.contains(&location)
+    }
+
+    fn eat(&mut self, location: Point2<u8>, _node: usize) -> bool {
+        match self.iter().position(|p| *p == location) {
+            Some(i) => {
+                self.remove(i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn put_back(&mut self, grid: &ComputedGrid, location: Point2<u8>, node: usize) {
+        // keep the locations in node order
+        let index = self
+            .iter()
+            .position(|p| grid.coords_to_node(p).is_some_and(|other| other > node))
+            .unwrap_or(self.len());
+        self.insert(index, location);
+    }
+
+    fn remaining(&self) -> usize {
+        self.len()
+    }
+}
+
+/// The ghosts of a [`GameState`], in the same order as [`PacmanAgentSetup::ghosts`]
+pub trait GhostList: Default + Deref<Target = [Ghost]> + DerefMut {
+    /// Remove every ghost
+    fn clear(&mut self);
+    /// Add a ghost to the end of the list
+    fn push(&mut self, ghost: Ghost);
+}
+
+impl GhostList for Vec<Ghost> {
+    fn clear(&mut self) {
+        Vec::clear(self)
+    }
+
+    fn push(&mut self, ghost: Ghost) {
+        Vec::push(self, ghost)
+    }
+}
+
+/// Mix the bits of a 64-bit value, using the finalizer of SplitMix64
+fn mix(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Pack a location into the low 16 bits of a value to be hashed
+fn location_bits(p: Point2<u8>) -> u64 {
+    ((p.x as u64) << 8) | p.y as u64
+}
+
+/// The Zobrist key of a pellet or super pellet at a location
+fn pellet_key(location: Point2<u8>, power: bool) -> u64 {
+    mix(((power as u64) << 16) | location_bits(location))
+}
+
 /// Information that changes during a game of Pacman
 ///
+/// The state is generic over how the pellets (`P`), super pellets (`S`) and ghosts (`G`) are
+/// stored. [`PacmanState`] uses vectors, which suit any grid; [`CompactPacmanState`] uses
+/// fixed-size arrays, which are cheap to copy. Both play exactly the same game.
+///
 /// Note: frightened_counter is not present because its only effect is Pacman's speed after collecting a power pellet
-#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
-pub struct PacmanState {
+///
+/// [`CompactPacmanState`]: crate::compact_state::CompactPacmanState
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GameState<P, S, G> {
     /// Current ghost behavior - applies to all ghosts
     ///
     /// When paused, Pacman should not move
@@ -95,23 +340,34 @@ pub struct PacmanState {
     pub frightened_multiplier: u8,
     /// Lives remaining - starts at 3; at 0, the game is over
     pub lives: u8,
+    /// The current level, starting at 1
+    pub level: u32,
     /// Number of frames that have passed since the start of the game
     pub elapsed_time: u32,
 
     /// Pacman's location and direction
     pub pacman: Agent,
     /// Ghosts
-    pub ghosts: Vec<Ghost>,
+    pub ghosts: G,
 
     /// Pellets remaining
-    pub pellets: Vec<bool>,
+    pellets: P,
     /// Super pellets remaining
-    pub power_pellets: Vec<Point2<u8>>,
+    power_pellets: S,
+    /// Pellets and super pellets eaten so far; determines when fruit appears
+    pub pellets_eaten: u32,
+    /// Zobrist hash of the pellets and super pellets remaining, updated whenever they change
+    pellet_hash: u64,
+    /// The fruit, if one is on the board
+    pub fruit: Option<Fruit>,
 }
 
-impl PacmanState {
-    /// Create a new PacmanState from a PacmanAgentSetup
-    pub fn new(agent_setup: &PacmanAgentSetup) -> Self {
+/// A [`GameState`] that stores pellets and ghosts in vectors
+pub type PacmanState = GameState<Vec<bool>, Vec<Point2<u8>>, Vec<Ghost>>;
+
+impl<P: PelletSet, S: PelletSet, G: GhostList> GameState<P, S, G> {
+    /// Create a new GameState from a PacmanAgentSetup
+    pub fn new(agent_setup: &PacmanAgentSetup, rules: &GameRules) -> Self {
         let mut s = Self {
             mode: GhostMode::Scatter,
             old_mode: GhostMode::Chase,
@@ -123,23 +379,27 @@ impl PacmanState {
             frightened_counter: 0,
             frightened_multiplier: 1,
             lives: 0,
+            level: 1,
             elapsed_time: 0,
             pacman: Agent {
                 location: Default::default(),
                 direction: Direction::Right,
             },
-            ghosts: vec![],
-            pellets: vec![],
-            power_pellets: vec![],
+            ghosts: G::default(),
+            pellets: P::default(),
+            power_pellets: S::default(),
+            pellets_eaten: 0,
+            pellet_hash: 0,
+            fruit: None,
         };
 
-        s.reset(agent_setup, true);
+        s.reset(agent_setup, rules, true);
 
         s
     }
 
     /// Reset the game state to the initial state using the same or different PacmanAgentSetup
-    pub fn reset(&mut self, agent_setup: &PacmanAgentSetup, use_physics: bool) {
+    pub fn reset(&mut self, agent_setup: &PacmanAgentSetup, rules: &GameRules, use_physics: bool) {
         self.mode = GhostMode::Scatter;
         self.old_mode = GhostMode::Chase;
         self.just_swapped_state = false;
@@ -148,23 +408,166 @@ impl PacmanState {
         self.paused = true;
 
         self.score = 0;
-        self.lives = STARTING_LIVES;
+        self.lives = rules.starting_lives;
+        self.level = 1;
         self.elapsed_time = 0;
 
         self.respawn_agents(agent_setup, use_physics);
+        self.refill_pellets(agent_setup);
+    }
+
+    /// Put every pellet and power pellet back on the board, and remove the fruit
+    fn refill_pellets(&mut self, agent_setup: &PacmanAgentSetup) {
+        self.pellets.refill(agent_setup.grid(), GridValue::o);
+        self.power_pellets.refill(agent_setup.grid(), GridValue::O);
+        self.rehash_pellets(agent_setup.grid());
+        self.pellets_eaten = 0;
+        self.fruit = None;
+    }
+
+    /// Get the pellets remaining
+    pub fn pellets(&self) -> &P {
+        &self.pellets
+    }
+
+    /// Get the super pellets remaining
+    pub fn power_pellets(&self) -> &S {
+        &self.power_pellets
+    }
+
+    /// Replace the pellets and super pellets remaining
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::agent_setup::PacmanAgentSetup;
+    /// use mdrc_pacbot_util::game_rules::GameRules;
+    /// use mdrc_pacbot_util::game_state::PacmanState;
+    /// use rapier2d::na::Point2;
+    ///
+    /// let agent_setup = PacmanAgentSetup::default();
+    /// let grid = agent_setup.grid();
+    /// let mut state = PacmanState::new(&agent_setup, &GameRules::default());
+    ///
+    /// // leave only the power pellet at (1, 7)
+    /// let pellets = vec![false; grid.walkable_nodes().len()];
+    /// state.set_pellets(grid, pellets, vec![Point2::new(1, 7)]);
+    /// assert_eq!(state.power_pellets(), &vec![Point2::new(1, 7)]);
+    /// ```
+    pub fn set_pellets(&mut self, grid: &ComputedGrid, pellets: P, power_pellets: S) {
+        self.pellets = pellets;
+        self.power_pellets = power_pellets;
+        self.rehash_pellets(grid);
+    }
 
-        self.pellets = Vec::new();
-        self.power_pellets = Vec::new();
-        for p in agent_setup.grid().walkable_nodes() {
-            let grid_value = agent_setup.grid().at(p).unwrap();
-            self.pellets.push(grid_value == GridValue::o);
+    /// Remove the pellet or super pellet at a location, returning whether there was one
+    pub(crate) fn eat_pellet(&mut self, location: Point2<u8>, node: usize, power: bool) -> bool {
+        let eaten = if power {
+            self.power_pellets.eat(location, node)
+        } else {
+            self.pellets.eat(location, node)
+        };
+        if eaten {
+            self.pellet_hash ^= pellet_key(location, power);
+        }
+        eaten
+    }
 
-            if grid_value == GridValue::O {
-                self.power_pellets.push(p.to_owned());
+    /// Put back a pellet or super pellet that was eaten
+    pub(crate) fn put_back_pellet(
+        &mut self,
+        grid: &ComputedGrid,
+        location: Point2<u8>,
+        node: usize,
+        power: bool,
+    ) {
+        if power {
+            self.power_pellets.put_back(grid, location, node);
+        } else {
+            self.pellets.put_back(grid, location, node);
+        }
+        self.pellet_hash ^= pellet_key(location, power);
+    }
+
+    /// Recompute the hash of the remaining pellets from scratch
+    fn rehash_pellets(&mut self, grid: &ComputedGrid) {
+        self.pellet_hash = 0;
+        for (node, p) in grid.walkable_nodes().iter().enumerate() {
+            if self.pellets.has_pellet(*p, node) {
+                self.pellet_hash ^= pellet_key(*p, false);
+            }
+            if self.power_pellets.has_pellet(*p, node) {
+                self.pellet_hash ^= pellet_key(*p, true);
             }
         }
     }
 
+    /// Get a hash of everything that affects how the game plays out from here
+    ///
+    /// This covers the positions and directions of Pacman and the ghosts, the remaining pellets,
+    /// the fruit, the ghost mode, and the counters that drive the ghosts and the level. The
+    /// score, elapsed time and pause state are left out, so states that differ only in those
+    /// hash the same; states that are equal always do.
+    ///
+    /// The pellets are hashed Zobrist-style: each pellet has a key, and eating a pellet XORs its
+    /// key out of a running hash, so they cost nothing to hash here. Everything else is only a
+    /// handful of fields, and is hashed on demand.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::agent_setup::PacmanAgentSetup;
+    /// use mdrc_pacbot_util::game_rules::GameRules;
+    /// use mdrc_pacbot_util::game_state::PacmanState;
+    /// use rapier2d::na::Point2;
+    ///
+    /// let agent_setup = PacmanAgentSetup::default();
+    /// let mut state = PacmanState::new(&agent_setup, &GameRules::default());
+    /// let hash = state.zobrist_hash();
+    ///
+    /// state.score += 100;
+    /// assert_eq!(state.zobrist_hash(), hash);
+    /// state.pacman.location = Point2::new(12, 7);
+    /// assert_ne!(state.zobrist_hash(), hash);
+    /// ```
+    pub fn zobrist_hash(&self) -> u64 {
+        let mut hash = 0;
+        let mut add = |value: u64| hash = mix(hash ^ value);
+
+        let agent_bits =
+            |agent: &Agent| (location_bits(agent.location) << 8) | u8::from(agent.direction) as u64;
+        add(agent_bits(&self.pacman));
+        for ghost in self.ghosts.iter() {
+            add(agent_bits(&ghost.agent));
+            add(location_bits(ghost.previous_location));
+            add(((ghost.respawn_timer as u64) << 8) | ghost.frightened_counter as u64);
+        }
+        add(match self.fruit {
+            Some(fruit) => ((fruit.remaining_time as u64) << 16) | location_bits(fruit.location),
+            None => u64::MAX,
+        });
+        add(((u8::from(self.mode) as u64) << 16)
+            | ((u8::from(self.old_mode) as u64) << 8)
+            | self.just_swapped_state as u64);
+        add(((self.state_counter as u64) << 32) | self.start_counter as u64);
+        add(((self.frightened_counter as u64) << 16)
+            | ((self.frightened_multiplier as u64) << 8)
+            | self.lives as u64);
+        add(((self.level as u64) << 32) | self.pellets_eaten as u64);
+
+        hash ^ self.pellet_hash
+    }
+
+    /// Replace everything but the pellets with the contents of another state
+    pub(crate) fn restore_except_pellets(&mut self, other: &GameState<(), (), G>)
+    where
+        G: Clone,
+    {
+        let pellets = std::mem::take(&mut self.pellets);
+        let power_pellets = std::mem::take(&mut self.power_pellets);
+        *self = other.with_storage(pellets, power_pellets, other.ghosts.clone());
+    }
+
     /// Respawn the ghosts and Pacman, for when Pacman dies
     pub fn respawn_agents(&mut self, agent_setup: &PacmanAgentSetup, use_physics: bool) {
         if !use_physics {
@@ -173,7 +576,7 @@ impl PacmanState {
                 direction: agent_setup.pacman_start().1,
             };
         }
-        self.ghosts = Vec::new();
+        self.ghosts.clear();
         for ghost in agent_setup.ghosts() {
             self.ghosts.push(Ghost {
                 agent: Agent {
@@ -189,39 +592,123 @@ impl PacmanState {
     }
 
     /// Move forward one frame, using the current Pacman location
-    pub fn step(&mut self, agent_setup: &PacmanAgentSetup, rng: &mut ThreadRng, use_physics: bool) {
-        if self.is_game_over() || self.paused {
-            return;
+    ///
+    /// The only randomness in the game comes from `rng`, so the same seed and the same Pacman
+    /// locations always produce the same states.
+    ///
+    /// Returns what happened during the frame, in the order it happened.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::agent_setup::PacmanAgentSetup;
+    /// use mdrc_pacbot_util::game_rules::GameRules;
+    /// use mdrc_pacbot_util::game_state::{GameEvent, PacmanState};
+    /// use mdrc_pacbot_util::grid::Direction;
+    /// use rand::rngs::ThreadRng;
+    /// use rapier2d::na::Point2;
+    ///
+    /// let agent_setup = PacmanAgentSetup::default();
+    /// let rules = GameRules::default();
+    /// let mut state = PacmanState::new(&agent_setup, &rules);
+    /// state.resume();
+    ///
+    /// state.update_pacman(Point2::new(12, 7), Direction::Left);
+    /// let events = state.step(&agent_setup, &rules, &mut ThreadRng::default(), false);
+    /// assert_eq!(
+    ///     events,
+    ///     vec![GameEvent::PelletEaten {
+    ///         location: Point2::new(12, 7),
+    ///         score: rules.pellet_score
+    ///     }]
+    /// );
+    /// ```
+    pub fn step<R: Rng>(
+        &mut self,
+        agent_setup: &PacmanAgentSetup,
+        rules: &GameRules,
+        rng: &mut R,
+        use_physics: bool,
+    ) -> Vec<GameEvent> {
+        let mut events = vec![];
+        if self.is_game_over(rules) || self.paused {
+            return events;
+        }
+        if self.status(rules) == GameStatus::LevelComplete {
+            self.next_level(agent_setup, use_physics, &mut events);
+            return events;
         }
+        let level_rules = rules.level(self.level);
         if self.should_die(use_physics) {
-            self.die(agent_setup, use_physics);
+            self.die(agent_setup, rules, use_physics, &mut events);
         } else {
-            self.check_if_ghost_eaten(agent_setup);
-            self.update_ghosts(agent_setup, rng);
-            self.check_if_ghost_eaten(agent_setup);
+            self.check_if_ghost_eaten(agent_setup, rules, &mut events);
+            self.update_ghosts(agent_setup, level_rules, rng);
+            self.check_if_ghost_eaten(agent_setup, rules, &mut events);
             if self.mode == GhostMode::Frightened {
                 if self.frightened_counter == 1 {
-                    self.mode = self.old_mode;
+                    self.set_mode(self.old_mode, &mut events);
                     self.frightened_multiplier = 1;
-                } else if self.frightened_counter == FRIGHTENED_LENGTH {
+                } else if self.frightened_counter == level_rules.frightened_length {
                     self.just_swapped_state = false;
                 }
                 self.frightened_counter -= 1;
             } else {
-                if agent_setup.state_swap_times().contains(&self.state_counter) {
-                    self.mode = match self.mode {
+                if level_rules.state_swap_times.contains(&self.state_counter) {
+                    let mode = match self.mode {
                         GhostMode::Chase => GhostMode::Scatter,
                         _ => GhostMode::Chase,
-                    }
+                    };
+                    self.set_mode(mode, &mut events);
                 } else {
                     self.just_swapped_state = false;
                 }
                 self.state_counter += 1;
             }
             self.start_counter += 1;
+            self.update_fruit(&mut events);
         }
-        self.update_score(agent_setup.grid());
+        self.update_score(agent_setup, rules, &mut events);
         self.elapsed_time += 1;
+        match self.status(rules) {
+            GameStatus::Playing => {}
+            GameStatus::LevelComplete => {
+                events.push(GameEvent::LevelComplete { level: self.level })
+            }
+            GameStatus::Won => {
+                events.push(GameEvent::LevelComplete { level: self.level });
+                events.push(GameEvent::GameOver { won: true });
+            }
+            GameStatus::Lost => events.push(GameEvent::GameOver { won: false }),
+        }
+        events
+    }
+
+    /// Refill the board and send everyone back to the start for the next level
+    ///
+    /// Like losing a life, this pauses the game.
+    fn next_level(
+        &mut self,
+        agent_setup: &PacmanAgentSetup,
+        use_physics: bool,
+        events: &mut Vec<GameEvent>,
+    ) {
+        self.level += 1;
+        self.respawn_agents(agent_setup, use_physics);
+        self.refill_pellets(agent_setup);
+        self.state_counter = 0;
+        self.start_counter = 0;
+        self.old_mode = GhostMode::Chase;
+        self.set_mode(GhostMode::Scatter, events);
+        self.frightened_counter = 0;
+        self.frightened_multiplier = 1;
+        events.push(GameEvent::LevelStarted { level: self.level });
+        self.pause();
+    }
+
+    /// Get the current ghost behavior
+    pub fn mode(&self) -> GhostMode {
+        self.mode
     }
 
     /// Update Pacman's location and direction
@@ -232,6 +719,55 @@ impl PacmanState {
         };
     }
 
+    /// Returns a [`CostMap`] for planning Pacman's paths around the ghosts that can currently
+    /// kill him.
+    ///
+    /// Every step costs 1. Cells with a dangerous ghost are blocked, and cells within
+    /// `danger_radius` steps of one cost up to `danger_cost` more, falling off with distance.
+    /// Frightened ghosts are ignored.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::agent_setup::PacmanAgentSetup;
+    /// use mdrc_pacbot_util::game_rules::GameRules;
+    /// use mdrc_pacbot_util::game_state::PacmanState;
+    ///
+    /// let agent_setup = PacmanAgentSetup::default();
+    /// let state = PacmanState::new(&agent_setup, &GameRules::default());
+    /// let grid = agent_setup.grid();
+    ///
+    /// let costs = state.ghost_cost_map(grid, 3, 20.0);
+    /// let target = state.ghosts[0].agent.location;
+    /// assert_eq!(costs.get(&target), None);
+    /// assert!(grid.find_path(&state.pacman.location, &target, &costs).is_none());
+    /// ```
+    pub fn ghost_cost_map(
+        &self,
+        grid: &ComputedGrid,
+        danger_radius: u8,
+        danger_cost: f32,
+    ) -> CostMap {
+        let mut costs = CostMap::new(grid, 1.0);
+        let dangerous: Vec<Point2<u8>> = self
+            .ghosts
+            .iter()
+            .filter(|ghost| ghost.frightened_counter == 0)
+            .map(|ghost| ghost.agent.location)
+            .collect();
+        for p in grid.walkable_nodes() {
+            let closest = dangerous.iter().filter_map(|g| grid.dist(g, p)).min();
+            if let Some(d) = closest.filter(|&d| d <= danger_radius) {
+                let falloff = (danger_radius + 1 - d) as f32 / (danger_radius + 1) as f32;
+                costs.add_cost(p, danger_cost * falloff);
+            }
+        }
+        for ghost in &dangerous {
+            costs.block(ghost);
+        }
+        costs
+    }
+
     /// Pause the game
     pub fn pause(&mut self) {
         self.paused = true;
@@ -242,10 +778,46 @@ impl PacmanState {
         self.paused = false;
     }
 
-    /// Test if the game is over (if all pellets are eaten)
-    fn is_game_over(&self) -> bool {
-        // test if all pellets & super pellets are eaten
-        (!self.pellets.iter().any(|p| *p) && self.power_pellets.is_empty()) || self.lives == 0
+    /// Get whether the game is still going, and if not, how it ended
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::agent_setup::PacmanAgentSetup;
+    /// use mdrc_pacbot_util::game_rules::GameRules;
+    /// use mdrc_pacbot_util::game_state::{GameStatus, PacmanState};
+    ///
+    /// let agent_setup = PacmanAgentSetup::default();
+    /// let mut state = PacmanState::new(&agent_setup, &GameRules::official());
+    /// assert_eq!(state.status(&GameRules::official()), GameStatus::Playing);
+    ///
+    /// let grid = agent_setup.grid();
+    /// state.set_pellets(grid, vec![false; grid.walkable_nodes().len()], vec![]);
+    /// // the official rules only have one level
+    /// assert_eq!(state.status(&GameRules::official()), GameStatus::Won);
+    /// assert_eq!(state.status(&GameRules::classic()), GameStatus::LevelComplete);
+    ///
+    /// state.lives = 0;
+    /// assert_eq!(state.status(&GameRules::classic()), GameStatus::Lost);
+    /// ```
+    pub fn status(&self, rules: &GameRules) -> GameStatus {
+        if self.lives == 0 {
+            GameStatus::Lost
+        } else if self.pellets.remaining() > 0 || self.power_pellets.remaining() > 0 {
+            GameStatus::Playing
+        } else if rules
+            .levels_to_win
+            .is_some_and(|levels| self.level >= levels)
+        {
+            GameStatus::Won
+        } else {
+            GameStatus::LevelComplete
+        }
+    }
+
+    /// Test if the game is over, because Pacman won or has no lives left
+    pub fn is_game_over(&self, rules: &GameRules) -> bool {
+        matches!(self.status(rules), GameStatus::Won | GameStatus::Lost)
     }
 
     /// Should Pacman die this step?
@@ -266,43 +838,80 @@ impl PacmanState {
         })
     }
 
+    /// Change the ghost mode, recording the change if there is one
+    fn set_mode(&mut self, mode: GhostMode, events: &mut Vec<GameEvent>) {
+        if mode != self.mode {
+            events.push(GameEvent::ModeChanged {
+                from: self.mode,
+                to: mode,
+            });
+            self.mode = mode;
+        }
+    }
+
     /// Pacman dies
-    fn die(&mut self, agent_setup: &PacmanAgentSetup, use_physics: bool) {
-        self.lives -= 1;
+    fn die(
+        &mut self,
+        agent_setup: &PacmanAgentSetup,
+        rules: &GameRules,
+        use_physics: bool,
+        events: &mut Vec<GameEvent>,
+    ) {
+        self.lives = self.lives.saturating_sub(1);
+        events.push(GameEvent::LifeLost {
+            location: self.pacman.location,
+            lives: self.lives,
+        });
 
         self.respawn_agents(agent_setup, use_physics);
         self.state_counter = 0;
         self.start_counter = 0;
         self.old_mode = GhostMode::Chase;
-        self.mode = GhostMode::Scatter;
+        self.set_mode(GhostMode::Scatter, events);
         self.frightened_counter = 0;
         self.frightened_multiplier = 1;
+        self.fruit = None;
         self.pause();
-        self.update_score(agent_setup.grid());
+        self.update_score(agent_setup, rules, events);
     }
 
-    fn check_if_ghost_eaten(&mut self, agent_setup: &PacmanAgentSetup) {
-        for ghost in &mut self.ghosts {
+    fn check_if_ghost_eaten(
+        &mut self,
+        agent_setup: &PacmanAgentSetup,
+        rules: &GameRules,
+        events: &mut Vec<GameEvent>,
+    ) {
+        for ghost in self.ghosts.iter_mut() {
             if ghost.agent.location == self.pacman.location && ghost.frightened_counter > 0 {
                 ghost.send_home(agent_setup.ghost_home_pos());
-                self.score += GHOST_SCORE * self.frightened_multiplier as usize;
+                let score = rules.ghost_score(self.frightened_multiplier);
+                self.score += score;
                 self.frightened_multiplier += 1;
+                events.push(GameEvent::GhostEaten {
+                    color: ghost.color,
+                    location: self.pacman.location,
+                    score,
+                });
             }
         }
     }
 
-    fn update_ghosts(&mut self, agent_setup: &PacmanAgentSetup, rng: &mut ThreadRng) {
-        // find the red ghost location
+    fn update_ghosts<R: Rng>(
+        &mut self,
+        agent_setup: &PacmanAgentSetup,
+        level_rules: &LevelRules,
+        rng: &mut R,
+    ) {
+        // find the red ghost location; without one, the blue ghost aims as if it were on Pacman
         let red_ghost = self
             .ghosts
             .iter()
-            .filter(|ghost| ghost.color == GhostType::Red)
-            .collect::<Vec<&Ghost>>()[0] // this will fail if there is no red ghost
-            .agent
-            .location;
+            .find(|ghost| ghost.color == GhostType::Red)
+            .map_or(self.pacman.location, |ghost| ghost.agent.location);
         for i in 0..self.ghosts.len() {
             self.ghosts[i].step_ghost(
                 agent_setup,
+                level_rules,
                 &agent_setup.ghosts()[i],
                 self.mode,
                 self.start_counter,
@@ -314,46 +923,498 @@ impl PacmanState {
         }
     }
 
-    fn update_score(&mut self, grid: &ComputedGrid) {
+    /// Count down the time until the fruit disappears
+    fn update_fruit(&mut self, events: &mut Vec<GameEvent>) {
+        if let Some(fruit) = &mut self.fruit {
+            fruit.remaining_time = fruit.remaining_time.saturating_sub(1);
+            if fruit.remaining_time == 0 {
+                events.push(GameEvent::FruitExpired {
+                    location: fruit.location,
+                });
+                self.fruit = None;
+            }
+        }
+    }
+
+    /// Count an eaten pellet or super pellet, and spawn a fruit if it was the right one
+    fn pellet_eaten(
+        &mut self,
+        agent_setup: &PacmanAgentSetup,
+        rules: &GameRules,
+        events: &mut Vec<GameEvent>,
+    ) {
+        self.pellets_eaten += 1;
+        if rules.fruit_pellet_thresholds.contains(&self.pellets_eaten) {
+            if let Some(location) = agent_setup.fruit_position() {
+                self.fruit = Some(Fruit {
+                    location,
+                    remaining_time: rules.fruit_duration,
+                });
+                events.push(GameEvent::FruitSpawned { location });
+            }
+        }
+    }
+
+    fn update_score(
+        &mut self,
+        agent_setup: &PacmanAgentSetup,
+        rules: &GameRules,
+        events: &mut Vec<GameEvent>,
+    ) {
+        let location = self.pacman.location;
+        let node = agent_setup.grid().coords_to_node(&location);
+
         // test if eating pellet
-        if let Some(x) = grid.coords_to_node(&self.pacman.location) {
-            if self.pellets[x] {
-                self.pellets[x] = false;
-                self.score += PELLET_SCORE;
+        if let Some(x) = node {
+            if self.eat_pellet(location, x, false) {
+                self.score += rules.pellet_score;
+                events.push(GameEvent::PelletEaten {
+                    location,
+                    score: rules.pellet_score,
+                });
+                self.pellet_eaten(agent_setup, rules, events);
             }
         }
 
-        for i in 0..self.power_pellets.len() {
-            if self.power_pellets[i] == self.pacman.location {
-                self.power_pellets.remove(i);
-                self.score += POWER_PELLET_SCORE;
+        // test if eating fruit
+        if self.fruit.is_some_and(|fruit| fruit.location == location) {
+            self.fruit = None;
+            self.score += rules.fruit_score;
+            events.push(GameEvent::FruitEaten {
+                location,
+                score: rules.fruit_score,
+            });
+        }
+
+        // test if eating power pellet
+        if node.is_some_and(|x| self.eat_pellet(location, x, true)) {
+            self.score += rules.power_pellet_score;
+            events.push(GameEvent::PowerPelletEaten {
+                location,
+                score: rules.power_pellet_score,
+            });
+            self.pellet_eaten(agent_setup, rules, events);
+            let frightened_length = rules.level(self.level).frightened_length;
+            if frightened_length > 0 {
                 if self.mode != GhostMode::Frightened {
                     self.old_mode = self.mode;
-                    self.mode = GhostMode::Frightened;
+                    self.set_mode(GhostMode::Frightened, events);
                 }
-                self.frightened_counter = FRIGHTENED_LENGTH;
-                for ghost in &mut self.ghosts {
-                    ghost.frightened_counter = FRIGHTENED_LENGTH;
+                self.frightened_counter = frightened_length;
+                for ghost in self.ghosts.iter_mut() {
+                    ghost.frightened_counter = frightened_length;
                 }
                 self.just_swapped_state = true;
-                break;
             }
         }
     }
 }
 
+/// Hashes [`GameState::zobrist_hash`], so that states can be used as keys of a `HashMap`
+impl<P: PelletSet, S: PelletSet, G: GhostList> Hash for GameState<P, S, G> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.zobrist_hash());
+    }
+}
+
+impl<P, S, G> GameState<P, S, G> {
+    /// Copy the state into one that stores its pellets and ghosts differently
+    pub(crate) fn with_storage<P2, S2, G2>(
+        &self,
+        pellets: P2,
+        power_pellets: S2,
+        ghosts: G2,
+    ) -> GameState<P2, S2, G2> {
+        GameState {
+            mode: self.mode,
+            old_mode: self.old_mode,
+            just_swapped_state: self.just_swapped_state,
+            state_counter: self.state_counter,
+            start_counter: self.start_counter,
+            paused: self.paused,
+            score: self.score,
+            frightened_counter: self.frightened_counter,
+            frightened_multiplier: self.frightened_multiplier,
+            lives: self.lives,
+            level: self.level,
+            elapsed_time: self.elapsed_time,
+            pacman: self.pacman,
+            ghosts,
+            pellets,
+            power_pellets,
+            pellets_eaten: self.pellets_eaten,
+            pellet_hash: self.pellet_hash,
+            fruit: self.fruit,
+        }
+    }
+}
+
 impl Default for PacmanState {
     fn default() -> Self {
-        PacmanState::new(&PacmanAgentSetup::default())
+        PacmanState::new(&PacmanAgentSetup::default(), &GameRules::default())
     }
 }
 
+/// Play a game from the start, where Pacman follows a fixed route
+///
+/// Each frame, `on_frame` is given the frame number, the state, where Pacman should go next and
+/// the seeded rng, and makes the move. The game goes on while it returns `true`, and the final
+/// state is returned.
+#[cfg(test)]
+pub(crate) fn play_seeded(
+    agent_setup: &PacmanAgentSetup,
+    rules: &GameRules,
+    seed: u64,
+    mut on_frame: impl FnMut(usize, &mut PacmanState, Point2<u8>, &mut rand::rngs::StdRng) -> bool,
+) -> PacmanState {
+    use rand::SeedableRng;
+
+    let grid = agent_setup.grid();
+    let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+    let mut state = PacmanState::new(agent_setup, rules);
+    state.reset(agent_setup, rules, false);
+    for frame in 0.. {
+        // regularly jump to a power pellet, so that ghosts spend time frightened
+        let location = match state.power_pellets().first() {
+            Some(p) if frame % 60 == 30 => *p,
+            _ => {
+                let neighbors = grid.neighbors(&state.pacman.location);
+                neighbors[frame % neighbors.len()]
+            }
+        };
+        if !on_frame(frame, &mut state, location, &mut rng) {
+            break;
+        }
+    }
+    state
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::game_state::PacmanState;
+    use crate::agent_setup::PacmanAgentSetup;
+    use crate::game_rules::GameRules;
+    use crate::game_state::{Fruit, GameEvent, GameStatus, GhostMode, PacmanState};
+    use crate::grid::{facing_direction, Direction, GridValue};
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rapier2d::na::Point2;
+    use std::collections::HashSet;
 
     #[test]
     fn default_game_setup() {
         PacmanState::default();
     }
+
+    #[test]
+    fn ghost_cost_map() {
+        let agent_setup = PacmanAgentSetup::default();
+        let grid = agent_setup.grid();
+        let mut state = PacmanState::new(&agent_setup, &GameRules::default());
+        let ghost = Point2::new(6, 5);
+        for g in &mut state.ghosts {
+            g.agent.location = ghost;
+        }
+
+        let costs = state.ghost_cost_map(grid, 2, 30.0);
+        for p in grid.walkable_nodes() {
+            let expected = match grid.dist(&ghost, p).unwrap() {
+                0 => None,
+                1 => Some(21.0),
+                2 => Some(11.0),
+                _ => Some(1.0),
+            };
+            assert_eq!(costs.get(p), expected);
+        }
+
+        // frightened ghosts are not dangerous
+        for g in &mut state.ghosts {
+            g.frightened_counter = 10;
+        }
+        assert_eq!(state.ghost_cost_map(grid, 2, 30.0).get(&ghost), Some(1.0));
+    }
+
+    #[test]
+    fn fruit() {
+        let agent_setup = PacmanAgentSetup::default();
+        let rules = GameRules::default();
+        let grid = agent_setup.grid();
+        let fruit_position = Point2::new(13, 13);
+        assert_eq!(agent_setup.fruit_position(), Some(fruit_position));
+
+        let mut state = PacmanState::new(&agent_setup, &rules);
+        let mut events = vec![];
+        let pellets: Vec<Point2<u8>> = grid
+            .walkable_nodes()
+            .iter()
+            .filter(|p| grid.at(p) == Some(GridValue::o))
+            .copied()
+            .collect();
+
+        // the fruit appears once the 70th pellet is eaten
+        for p in &pellets[..69] {
+            state.pacman.location = *p;
+            state.update_score(&agent_setup, &rules, &mut events);
+        }
+        assert_eq!(state.fruit, None);
+        events.clear();
+        state.pacman.location = pellets[69];
+        state.update_score(&agent_setup, &rules, &mut events);
+        assert_eq!(
+            state.fruit,
+            Some(Fruit {
+                location: fruit_position,
+                remaining_time: rules.fruit_duration
+            })
+        );
+        assert_eq!(
+            events,
+            vec![
+                GameEvent::PelletEaten {
+                    location: pellets[69],
+                    score: rules.pellet_score
+                },
+                GameEvent::FruitSpawned {
+                    location: fruit_position
+                }
+            ]
+        );
+
+        // and disappears if it isn't eaten in time
+        events.clear();
+        for _ in 1..rules.fruit_duration {
+            state.update_fruit(&mut events);
+        }
+        assert!(state.fruit.is_some());
+        assert!(events.is_empty());
+        state.update_fruit(&mut events);
+        assert_eq!(state.fruit, None);
+        assert_eq!(
+            events,
+            vec![GameEvent::FruitExpired {
+                location: fruit_position
+            }]
+        );
+
+        // the second fruit is eaten
+        state.pellets_eaten = 169;
+        state.pacman.location = pellets[70];
+        state.update_score(&agent_setup, &rules, &mut events);
+        assert!(state.fruit.is_some());
+        let score = state.score;
+        events.clear();
+        state.pacman.location = fruit_position;
+        state.update_score(&agent_setup, &rules, &mut events);
+        assert_eq!(state.fruit, None);
+        assert_eq!(state.score, score + rules.fruit_score);
+        assert_eq!(
+            events,
+            vec![GameEvent::FruitEaten {
+                location: fruit_position,
+                score: rules.fruit_score
+            }]
+        );
+    }
+
+    #[test]
+    fn rules() {
+        let agent_setup = PacmanAgentSetup::default();
+        let rules = GameRules {
+            starting_lives: 5,
+            ..GameRules::classic()
+        };
+        let mut state = PacmanState::new(&agent_setup, &rules);
+        assert_eq!(state.lives, 5);
+
+        // eat every ghost during one frightened period
+        for ghost in &mut state.ghosts {
+            ghost.agent.location = state.pacman.location;
+            ghost.frightened_counter = rules.level(1).frightened_length;
+        }
+        let mut events = vec![];
+        state.check_if_ghost_eaten(&agent_setup, &rules, &mut events);
+        assert_eq!(state.score, 200 + 400 + 800 + 1600);
+        assert_eq!(
+            events.iter().map(GameEvent::score).collect::<Vec<_>>(),
+            vec![200, 400, 800, 1600]
+        );
+    }
+
+    #[test]
+    fn unvalidated_rules() {
+        let agent_setup = PacmanAgentSetup::default();
+        let rules = GameRules {
+            levels: vec![],
+            fruit_duration: 0,
+            ..GameRules::default()
+        };
+        assert!(rules.validate().is_err());
+        assert_eq!(rules.level(1), GameRules::official().level(1));
+
+        // rules that fail validation still play without panicking
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut state = PacmanState::new(&agent_setup, &rules);
+        state.reset(&agent_setup, &rules, false);
+        state.fruit = Some(Fruit {
+            location: Point2::new(13, 13),
+            remaining_time: rules.fruit_duration,
+        });
+        for _ in 0..100 {
+            state.resume();
+            state.step(&agent_setup, &rules, &mut rng, false);
+        }
+        assert_eq!(state.fruit, None);
+    }
+
+    #[test]
+    fn events_account_for_score() {
+        let agent_setup = PacmanAgentSetup::default();
+        let rules = GameRules::default();
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut state = PacmanState::new(&agent_setup, &rules);
+        state.reset(&agent_setup, &rules, false);
+
+        // eat the power pellet at (1, 7) and wait for the ghosts to catch Pacman
+        state.resume();
+        state.update_pacman(Point2::new(1, 7), Direction::Right);
+        let events = state.step(&agent_setup, &rules, &mut rng, false);
+        assert!(events.contains(&GameEvent::ModeChanged {
+            from: GhostMode::Scatter,
+            to: GhostMode::Frightened
+        }));
+
+        let mut score = state.score;
+        let mut lives = state.lives;
+        while !state.is_game_over(&rules) {
+            state.resume();
+            let events = state.step(&agent_setup, &rules, &mut rng, false);
+            score += events.iter().map(GameEvent::score).sum::<usize>();
+            assert_eq!(state.score, score);
+            for event in &events {
+                if let GameEvent::LifeLost { lives: left, .. } = event {
+                    lives -= 1;
+                    assert_eq!(*left, lives);
+                }
+            }
+            assert_eq!(state.lives, lives);
+        }
+        assert_eq!(lives, 0);
+    }
+
+    #[test]
+    fn levels() {
+        let agent_setup = PacmanAgentSetup::default();
+        let rules = GameRules {
+            levels_to_win: Some(2),
+            ..GameRules::classic()
+        };
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut state = PacmanState::new(&agent_setup, &rules);
+        state.reset(&agent_setup, &rules, false);
+
+        // leave one power pellet, and put Pacman on it
+        let last_pellet = Point2::new(12, 7);
+        let clear_board = |state: &mut PacmanState| {
+            let grid = agent_setup.grid();
+            let pellets = vec![false; grid.walkable_nodes().len()];
+            state.set_pellets(grid, pellets, vec![last_pellet]);
+            state.update_pacman(last_pellet, Direction::Left);
+            state.resume();
+        };
+
+        clear_board(&mut state);
+        let score = state.score;
+        let events = state.step(&agent_setup, &rules, &mut rng, false);
+        assert_eq!(state.status(&rules), GameStatus::LevelComplete);
+        assert_eq!(events.last(), Some(&GameEvent::LevelComplete { level: 1 }));
+
+        // the next step starts the next level, keeping the score and lives
+        let events = state.step(&agent_setup, &rules, &mut rng, false);
+        assert!(events.contains(&GameEvent::LevelStarted { level: 2 }));
+        assert_eq!(state.status(&rules), GameStatus::Playing);
+        assert_eq!(state.level, 2);
+        assert_eq!(state.score, score + rules.power_pellet_score);
+        assert_eq!(state.lives, rules.starting_lives);
+        assert_eq!(state.pacman.location, agent_setup.pacman_start().0);
+        assert_eq!(
+            state.pellets(),
+            PacmanState::new(&agent_setup, &rules).pellets()
+        );
+        assert!(state.paused);
+
+        // clearing the last level wins the game
+        clear_board(&mut state);
+        let events = state.step(&agent_setup, &rules, &mut rng, false);
+        assert_eq!(state.status(&rules), GameStatus::Won);
+        assert!(state.is_game_over(&rules));
+        assert_eq!(
+            events[events.len() - 2..],
+            [
+                GameEvent::LevelComplete { level: 2 },
+                GameEvent::GameOver { won: true }
+            ]
+        );
+        assert!(state.step(&agent_setup, &rules, &mut rng, false).is_empty());
+    }
+
+    /// Play a game where Pacman follows a fixed route, returning every state
+    fn play_states(seed: u64) -> Vec<PacmanState> {
+        let agent_setup = PacmanAgentSetup::default();
+        let rules = GameRules::default();
+        let mut states = vec![];
+        super::play_seeded(&agent_setup, &rules, seed, |_, state, location, rng| {
+            let direction = facing_direction(&state.pacman.location, &location);
+            state.update_pacman(location, direction);
+            state.resume();
+            state.step(&agent_setup, &rules, rng, false);
+            states.push(state.to_owned());
+            states.len() < 400
+        });
+        states
+    }
+
+    #[test]
+    fn deterministic_with_seed() {
+        let states = play_states(7);
+        assert!(states.iter().any(|s| s.mode == GhostMode::Frightened));
+        assert_eq!(states, play_states(7));
+        // frightened ghosts do use the rng
+        assert_ne!(states, play_states(8));
+    }
+
+    #[test]
+    fn zobrist_hash() {
+        let agent_setup = PacmanAgentSetup::default();
+        let rules = GameRules::default();
+        let grid = agent_setup.grid();
+
+        // the running pellet hash matches one computed from scratch
+        let states = play_states(7);
+        for state in &states {
+            let mut rehashed = state.clone();
+            rehashed.rehash_pellets(grid);
+            assert_eq!(rehashed, *state);
+        }
+        let hashes: HashSet<u64> = states.iter().map(PacmanState::zobrist_hash).collect();
+        let unique: HashSet<&PacmanState> = states.iter().collect();
+        assert_eq!(hashes.len(), unique.len());
+
+        // eating the same pellets in a different order gives the same state
+        let play = |route: [Point2<u8>; 3]| {
+            let mut state = PacmanState::new(&agent_setup, &rules);
+            state.reset(&agent_setup, &rules, false);
+            for p in route {
+                state.update_pacman(p, Direction::Right);
+                state.update_score(&agent_setup, &rules, &mut vec![]);
+            }
+            state
+        };
+        let a = play([Point2::new(12, 7), Point2::new(2, 7), Point2::new(14, 7)]);
+        let b = play([Point2::new(2, 7), Point2::new(12, 7), Point2::new(14, 7)]);
+        assert_eq!(a, b);
+        assert_eq!(a.zobrist_hash(), b.zobrist_hash());
+        assert_ne!(
+            a.zobrist_hash(),
+            PacmanState::new(&agent_setup, &rules).zobrist_hash()
+        );
+    }
 }
@@ -1,62 +1,252 @@
 //! Ghost behavior
+//!
+//! Where each ghost goes is decided by its [`GhostBehavior`]. By default, ghosts behave like the
+//! arcade ghost of their color, but any ghost can be given another behavior with
+//! [`PacmanAgentSetup::with_ghost_behavior`].
 
 use crate::agent_setup::{GhostSetup, PacmanAgentSetup};
+use crate::game_rules::LevelRules;
 use crate::game_state::{Agent, Ghost, GhostMode, GhostType};
 use crate::grid::{ComputedGrid, Direction};
-use rand::rngs::ThreadRng;
-use rand::Rng;
+use rand::{Rng, RngCore};
 use rapier2d::na::Point2;
 use rapier2d::parry::utils::Array1;
+use std::fmt::Debug;
+
+/// What a ghost knows when deciding where to go
+#[derive(Clone, Copy, Debug)]
+pub struct GhostView<'a> {
+    /// The grid
+    pub grid: &'a ComputedGrid,
+    /// The ghost's static information, such as its start path and scatter point
+    pub setup: &'a GhostSetup,
+    /// Pacman's location and direction
+    pub pacman: &'a Agent,
+    /// The red ghost's location at the start of the frame
+    pub red_ghost_location: Point2<u8>,
+}
+
+/// Decides where a ghost goes
+///
+/// Chasing and scattering ghosts pick whichever legal move is closest to their target, so a
+/// behavior only needs to choose targets. As in the original game, targets may be anywhere,
+/// including off the board, so they use signed coordinates. Leaving the ghost house and returning
+/// to it after being eaten follow fixed paths instead.
+///
+/// # Examples
+///
+/// ```
+/// use mdrc_pacbot_util::agent_setup::PacmanAgentSetup;
+/// use mdrc_pacbot_util::game_state::{Ghost, GhostType};
+/// use mdrc_pacbot_util::ghost::{GhostBehavior, GhostView};
+/// use rapier2d::na::Point2;
+/// use std::sync::Arc;
+///
+/// /// A red ghost that guards the bottom left corner instead of chasing Pacman
+/// #[derive(Debug)]
+/// struct Guard;
+///
+/// impl GhostBehavior for Guard {
+///     fn chase_target(&self, _ghost: &Ghost, _view: &GhostView) -> Point2<i32> {
+///         Point2::new(1, 1)
+///     }
+/// }
+///
+/// let agent_setup = PacmanAgentSetup::default().with_ghost_behavior(GhostType::Red, Arc::new(Guard));
+/// let red = &agent_setup.ghosts()[0];
+/// assert_eq!(red.color, GhostType::Red);
+/// assert!(red.custom_behavior.is_some());
+/// ```
+pub trait GhostBehavior: Debug + Send + Sync {
+    /// Get the cell the ghost heads towards while chasing Pacman
+    fn chase_target(&self, ghost: &Ghost, view: &GhostView) -> Point2<i32>;
+
+    /// Get the cell the ghost heads towards while scattering
+    ///
+    /// By default, this is the ghost's scatter point.
+    fn scatter_target(&self, _ghost: &Ghost, view: &GhostView) -> Point2<i32> {
+        view.setup.scatter_point.cast()
+    }
+
+    /// Choose where a frightened ghost moves, from its legal moves, which are never empty
+    ///
+    /// By default, the move is chosen at random.
+    fn frightened_move(
+        &self,
+        _ghost: &Ghost,
+        _view: &GhostView,
+        moves: &[Point2<u8>],
+        rng: &mut dyn RngCore,
+    ) -> Point2<u8> {
+        moves[rng.gen_range(0..moves.len())]
+    }
+
+    /// Get where the ghost moves while leaving the ghost house, `start_counter` frames after the
+    /// start of a life or level, or `None` once it has left
+    ///
+    /// By default, the ghost follows its start path, one cell per frame.
+    fn exit_house_move(
+        &self,
+        _ghost: &Ghost,
+        view: &GhostView,
+        start_counter: u32,
+    ) -> Option<Point2<u8>> {
+        view.setup
+            .start_path
+            .get(start_counter as usize)
+            .map(|(p, _)| *p)
+    }
+}
+
+/// Get Euclidean distance between two points
+fn distance(a: &Point2<i32>, b: &Point2<i32>) -> f32 {
+    let dx = (a.x as f32) - (b.x as f32);
+    let dy = (a.y as f32) - (b.y as f32);
+    (dx * dx + dy * dy).sqrt()
+}
+
+/// The arcade red ghost, which chases Pacman directly
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RedGhost;
+
+impl GhostBehavior for RedGhost {
+    fn chase_target(&self, _ghost: &Ghost, view: &GhostView) -> Point2<i32> {
+        view.pacman.location.cast()
+    }
+}
+
+/// The arcade pink ghost, which aims for 4 tiles ahead of Pacman
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PinkGhost;
+
+impl GhostBehavior for PinkGhost {
+    /// Return the space 4 tiles ahead of Pacman in the direction Pacman is currently facing.
+    ///
+    /// If Pacman is facing up, then we replicate a bug in the original game and return the
+    /// space 4 tiles above and 4 tiles to the left of Pacman.
+    fn chase_target(&self, _ghost: &Ghost, view: &GhostView) -> Point2<i32> {
+        let p: Point2<i32> = view.pacman.location.cast();
+
+        match view.pacman.direction {
+            Direction::Up => Point2::new(p.x - 4, p.y + 4),
+            Direction::Down => Point2::new(p.x, p.y - 4),
+            Direction::Left => Point2::new(p.x - 4, p.y),
+            Direction::Right => Point2::new(p.x + 4, p.y),
+        }
+    }
+}
+
+/// The arcade orange ghost, which chases Pacman
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OrangeGhost;
+
+impl GhostBehavior for OrangeGhost {
+    fn chase_target(&self, ghost: &Ghost, view: &GhostView) -> Point2<i32> {
+        if distance(&ghost.agent.location.cast(), &view.pacman.location.cast()) > 8.0 {
+            return view.pacman.location.cast();
+        }
+        RedGhost.chase_target(ghost, view)
+    }
+}
+
+/// The arcade blue ghost, which aims for the point opposite the red ghost, across the space
+/// 2 tiles ahead of Pacman
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BlueGhost;
+
+impl GhostBehavior for BlueGhost {
+    fn chase_target(&self, _ghost: &Ghost, view: &GhostView) -> Point2<i32> {
+        let pacman_location: Point2<i32> = view.pacman.location.cast();
+        let target = match view.pacman.direction {
+            Direction::Right => Point2::new(pacman_location.x + 2, pacman_location.y),
+            Direction::Left => Point2::new(pacman_location.x - 2, pacman_location.y),
+            Direction::Up => Point2::new(pacman_location.x - 2, pacman_location.y + 2),
+            Direction::Down => Point2::new(pacman_location.x, pacman_location.y - 2),
+        };
+
+        let red_ghost_location: Point2<i32> = view.red_ghost_location.cast();
+        Point2::new(
+            target.x + (target.x - red_ghost_location.x),
+            target.y + (target.y - red_ghost_location.y),
+        )
+    }
+}
+
+impl GhostType {
+    /// Get the behavior of the arcade ghost of this color
+    pub fn behavior(self) -> &'static dyn GhostBehavior {
+        match self {
+            GhostType::Red => &RedGhost,
+            GhostType::Pink => &PinkGhost,
+            GhostType::Orange => &OrangeGhost,
+            GhostType::Blue => &BlueGhost,
+        }
+    }
+}
 
 impl Ghost {
     /// Have the ghost take one step
+    ///
+    /// Where it goes is decided by the [`GhostBehavior`] of `ghost_setup`; frightened ghosts may
+    /// use `rng`
     #[allow(clippy::too_many_arguments)]
-    pub fn step_ghost(
+    pub fn step_ghost<R: Rng>(
         &mut self,
         agent_setup: &PacmanAgentSetup,
+        level_rules: &LevelRules,
         ghost_setup: &GhostSetup,
         mode: GhostMode,
         start_counter: u32,
         state_counter: u32,
         pacman: &Agent,
         red_ghost_location: &Point2<u8>,
-        rng: &mut ThreadRng,
+        rng: &mut R,
     ) {
         if self.frightened_counter > 0 {
             self.frightened_counter -= 1;
         }
 
-        let mut destination;
-        let mut literal = false;
+        let behavior = ghost_setup.behavior();
+        let view = GhostView {
+            grid: agent_setup.grid(),
+            setup: ghost_setup,
+            pacman,
+            red_ghost_location: *red_ghost_location,
+        };
+        let destination;
 
-        if (start_counter as usize) < ghost_setup.start_path.len() {
-            destination = ghost_setup.start_path[start_counter as usize].0;
-            literal = true;
+        if let Some(exit_house_move) = behavior.exit_house_move(self, &view, start_counter) {
+            destination = exit_house_move;
         } else if let Some(next_respawn_path_move) = self.get_respawn_path_move(agent_setup) {
             destination = next_respawn_path_move.to_owned();
-            literal = true;
             self.respawn_timer += 1;
-        } else if let Some(next_swapped_state_move) =
-            self.get_swapped_state_move(agent_setup, state_counter)
-        {
-            destination = next_swapped_state_move.to_owned();
-        } else if self.frightened_counter > 0 {
-            destination = self.get_frightened_move(rng, agent_setup.grid());
-            self.frightened_counter -= 1;
-        } else if mode == GhostMode::Chase {
-            destination =
-                self.get_next_chase_move(&pacman.location, pacman.direction, red_ghost_location);
         } else {
-            destination = self.get_next_scatter_move(ghost_setup);
-        }
-
-        if !literal {
-            destination =
-                self.get_move_based_on(&self.agent.location, &destination, agent_setup.grid());
+            let target = if let Some(next_swapped_state_move) =
+                self.get_swapped_state_move(level_rules, state_counter)
+            {
+                next_swapped_state_move.cast()
+            } else if self.frightened_counter > 0 {
+                let moves = agent_setup.grid().ghost_moves(
+                    &self.agent.location,
+                    &self.previous_location,
+                    false,
+                );
+                let frightened_move = behavior.frightened_move(self, &view, &moves, rng);
+                self.frightened_counter -= 1;
+                frightened_move.cast()
+            } else if mode == GhostMode::Chase {
+                behavior.chase_target(self, &view)
+            } else {
+                behavior.scatter_target(self, &view)
+            };
+            destination = self.get_move_based_on(&self.agent.location, &target, agent_setup.grid());
         }
 
         let current_position = self.agent.location.to_owned();
-        let direction = Self::direction(&current_position, &destination);
+        let direction = agent_setup
+            .grid()
+            .adjacent_direction(&current_position, &destination)
+            .unwrap_or_else(|| Self::direction(&current_position, &destination));
 
         self.previous_location = current_position;
         self.agent.location = destination;
@@ -75,25 +265,18 @@ impl Ghost {
         }
     }
 
-    /// Get Euclidean distance between two points
-    fn distance(a: &Point2<u8>, b: &Point2<u8>) -> f32 {
-        let dx = (a.x as f32) - (b.x as f32);
-        let dy = (a.y as f32) - (b.y as f32);
-        (dx * dx + dy * dy).sqrt()
-    }
-
+    /// Get the legal move closest to the target, which may be off the board
     fn get_move_based_on(
         &self,
         start: &Point2<u8>,
-        p: &Point2<u8>,
+        p: &Point2<i32>,
         grid: &ComputedGrid,
     ) -> Point2<u8> {
-        grid.neighbors(start)
+        grid.ghost_moves(start, &self.previous_location, false)
             .iter()
-            .filter(|p| **p != self.previous_location)
             .min_by(|n1, n2| {
-                let d1 = Self::distance(n1, p);
-                let d2 = Self::distance(n2, p);
+                let d1 = distance(&n1.cast(), p);
+                let d2 = distance(&n2.cast(), p);
                 d1.total_cmp(&d2)
             })
             .unwrap()
@@ -102,10 +285,10 @@ impl Ghost {
 
     fn get_swapped_state_move(
         &self,
-        agent_setup: &PacmanAgentSetup,
+        level_rules: &LevelRules,
         elapsed_time: u32,
     ) -> Option<&Point2<u8>> {
-        if agent_setup.state_swap_times().contains(&elapsed_time) {
+        if level_rules.state_swap_times.contains(&elapsed_time) {
             return Some(&self.previous_location);
         }
         None
@@ -117,91 +300,243 @@ impl Ghost {
         Some(p.unwrap().0)
     }
 
-    /// Frightened behavior - return a random legal move
-    fn get_frightened_move(&self, rng: &mut ThreadRng, grid: &ComputedGrid) -> Point2<u8> {
-        let moves = grid.neighbors(&self.agent.location);
-        let index = rng.gen_range(0..moves.len());
-        moves[index].to_owned()
-    }
+    /// Teleport the ghost back to the home position, after it is eaten
+    pub fn send_home(&mut self, ghost_home_pos: &(Point2<u8>, Direction)) {
+        self.agent.location = ghost_home_pos.0;
+        self.agent.direction = ghost_home_pos.1;
 
-    fn get_next_scatter_move(&self, ghost_setup: &GhostSetup) -> Point2<u8> {
-        ghost_setup.scatter_point.to_owned()
+        self.previous_location = ghost_home_pos.0;
+        self.respawn_timer = 0;
+        self.frightened_counter = 0;
     }
+}
 
-    fn get_next_chase_move(
-        &self,
-        pacman_location: &Point2<u8>,
-        pacman_direction: Direction,
-        red_ghost_location: &Point2<u8>,
-    ) -> Point2<u8> {
-        match self.color {
-            GhostType::Blue => {
-                self.get_next_blue_chase_move(red_ghost_location, pacman_location, pacman_direction)
-            }
-            GhostType::Red => self.get_next_red_chase_move(pacman_location),
-            GhostType::Pink => self.get_next_pink_chase_move(pacman_location, pacman_direction),
-            GhostType::Orange => self.get_next_orange_chase_move(pacman_location),
-        }
-    }
+#[cfg(test)]
+mod tests {
+    use crate::agent_setup::PacmanAgentSetup;
+    use crate::game_rules::GameRules;
+    use crate::game_state::{Agent, Ghost, GhostType, PacmanState};
+    use crate::ghost::{GhostBehavior, GhostMode, GhostView};
+    use crate::grid::Direction;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rapier2d::na::Point2;
+    use std::sync::Arc;
 
-    fn get_next_blue_chase_move(
-        &self,
-        red_ghost_location: &Point2<u8>,
-        pacman_location: &Point2<u8>,
-        pacman_direction: Direction,
-    ) -> Point2<u8> {
-        let target = match pacman_direction {
-            Direction::Right => Point2::new(pacman_location.x + 2, pacman_location.y),
-            Direction::Left => Point2::new(pacman_location.x - 2, pacman_location.y),
-            Direction::Up => Point2::new(pacman_location.x - 2, pacman_location.y + 2),
-            Direction::Down => Point2::new(pacman_location.x, pacman_location.y - 2),
+    #[test]
+    fn arcade_chase_targets() {
+        let agent_setup = PacmanAgentSetup::default();
+        let state = PacmanState::new(&agent_setup, &GameRules::default());
+        let pacman = Agent {
+            location: Point2::new(14, 7),
+            direction: Direction::Right,
         };
+        let targets: Vec<Point2<i32>> = agent_setup
+            .ghosts()
+            .iter()
+            .zip(&state.ghosts)
+            .map(|(setup, ghost)| {
+                let view = GhostView {
+                    grid: agent_setup.grid(),
+                    setup,
+                    pacman: &pacman,
+                    red_ghost_location: Point2::new(12, 5),
+                };
+                setup.behavior().chase_target(ghost, &view)
+            })
+            .collect();
+        assert_eq!(
+            targets,
+            vec![
+                // red: Pacman
+                Point2::new(14, 7),
+                // pink: 4 ahead of Pacman
+                Point2::new(18, 7),
+                // orange: far from Pacman, so Pacman
+                Point2::new(14, 7),
+                // blue: the red ghost reflected across 2 ahead of Pacman
+                Point2::new(20, 9),
+            ]
+        );
+    }
 
-        let x = target.x as i32 + (target.x as i32 - red_ghost_location.x as i32);
-        let y = target.y as i32 + (target.y as i32 - red_ghost_location.y as i32);
+    const DIRECTIONS: [Direction; 4] = [
+        Direction::Up,
+        Direction::Down,
+        Direction::Left,
+        Direction::Right,
+    ];
 
-        Point2::new(x as u8, y as u8)
+    #[test]
+    fn off_board_targets() {
+        let agent_setup = PacmanAgentSetup::default();
+        let state = PacmanState::new(&agent_setup, &GameRules::default());
+        let pacman = Agent {
+            location: Point2::new(1, 1),
+            direction: Direction::Left,
+        };
+        let target = |color: GhostType, pacman: &Agent, red_ghost_location| {
+            let index = state.ghosts.iter().position(|g| g.color == color).unwrap();
+            let view = GhostView {
+                grid: agent_setup.grid(),
+                setup: &agent_setup.ghosts()[index],
+                pacman,
+                red_ghost_location,
+            };
+            color.behavior().chase_target(&state.ghosts[index], &view)
+        };
+
+        assert_eq!(
+            target(GhostType::Pink, &pacman, Point2::new(12, 5)),
+            Point2::new(-3, 1)
+        );
+        assert_eq!(
+            target(GhostType::Blue, &pacman, Point2::new(12, 5)),
+            Point2::new(-14, -3)
+        );
+        let pacman = Agent {
+            location: Point2::new(26, 29),
+            direction: Direction::Up,
+        };
+        assert_eq!(
+            target(GhostType::Pink, &pacman, Point2::new(12, 5)),
+            Point2::new(22, 33)
+        );
     }
 
-    fn get_next_red_chase_move(&self, pacman_location: &Point2<u8>) -> Point2<u8> {
-        pacman_location.to_owned()
+    #[test]
+    fn chase_targets_from_every_position() {
+        let agent_setup = PacmanAgentSetup::default();
+        let grid = agent_setup.grid();
+        let state = PacmanState::new(&agent_setup, &GameRules::default());
+        let cells: Vec<Point2<u8>> = (0..grid.width() as u8)
+            .flat_map(|x| (0..grid.height() as u8).map(move |y| Point2::new(x, y)))
+            .collect();
+
+        for &location in &cells {
+            for direction in DIRECTIONS {
+                let pacman = Agent {
+                    location,
+                    direction,
+                };
+                for (setup, ghost) in agent_setup.ghosts().iter().zip(&state.ghosts) {
+                    // only the blue ghost depends on where the red ghost is
+                    let red_ghost_locations = if ghost.color == GhostType::Blue {
+                        &cells[..]
+                    } else {
+                        &cells[..1]
+                    };
+                    for &red_ghost_location in red_ghost_locations {
+                        let view = GhostView {
+                            grid,
+                            setup,
+                            pacman: &pacman,
+                            red_ghost_location,
+                        };
+                        let target = setup.behavior().chase_target(ghost, &view);
+                        // the furthest a target can be from Pacman is the blue ghost's
+                        // reflection of a red ghost on the far side of the board
+                        let limit = 2 * grid.width().max(grid.height()) as i32 + 4;
+                        assert!((target.x - location.x as i32).abs() <= limit);
+                        assert!((target.y - location.y as i32).abs() <= limit);
+                    }
+                }
+            }
+        }
     }
 
-    /// Return the move closest to the space 4 tiles ahead of Pacman in the direction
-    /// Pacman is currently facing.
-    ///
-    /// If Pacman is facing up, then we replicate a bug in
-    /// the original game and return the move closest to the space 4 tiles above and
-    /// 4 tiles to the left of Pacman.
-    fn get_next_pink_chase_move(
-        &self,
-        pacman_location: &Point2<u8>,
-        pacman_direction: Direction,
-    ) -> Point2<u8> {
-        let p = pacman_location;
+    #[test]
+    fn step_from_every_position() {
+        let agent_setup = PacmanAgentSetup::default();
+        let rules = GameRules::default();
+        let level_rules = rules.level(1);
+        let grid = agent_setup.grid();
+        let mut rng = StdRng::seed_from_u64(0);
+        let state = PacmanState::new(&agent_setup, &rules);
+        // every ghost starts out of the house, where it can go left or right
+        let start = Point2::new(14, 19);
+        let red_ghost_location = Point2::new(12, 5);
 
-        match pacman_direction {
-            Direction::Up => Point2::new(p.x - 4, p.y + 4),
-            Direction::Down => Point2::new(p.x, p.y - 4),
-            Direction::Left => Point2::new(p.x - 4, p.y),
-            Direction::Right => Point2::new(p.x + 4, p.y),
+        for &location in grid.walkable_nodes() {
+            for direction in DIRECTIONS {
+                let pacman = Agent {
+                    location,
+                    direction,
+                };
+                for mode in [GhostMode::Chase, GhostMode::Scatter] {
+                    for (setup, ghost) in agent_setup.ghosts().iter().zip(&state.ghosts) {
+                        let mut ghost = *ghost;
+                        ghost.agent.location = start;
+                        ghost.previous_location = start;
+                        let moves = grid.ghost_moves(
+                            &ghost.agent.location,
+                            &ghost.previous_location,
+                            false,
+                        );
+                        ghost.step_ghost(
+                            &agent_setup,
+                            level_rules,
+                            setup,
+                            mode,
+                            u32::MAX,
+                            0,
+                            &pacman,
+                            &red_ghost_location,
+                            &mut rng,
+                        );
+                        assert_eq!(moves.len(), 2);
+                        assert!(moves.contains(&ghost.agent.location));
+                    }
+                }
+            }
         }
     }
 
-    fn get_next_orange_chase_move(&self, pacman_location: &Point2<u8>) -> Point2<u8> {
-        if Self::distance(&self.agent.location, pacman_location) > 8.0 {
-            return pacman_location.to_owned();
+    /// Ghosts that never leave the house
+    #[derive(Debug)]
+    struct StayHome;
+
+    impl GhostBehavior for StayHome {
+        fn chase_target(&self, ghost: &Ghost, _view: &GhostView) -> Point2<i32> {
+            ghost.agent.location.cast()
+        }
+
+        fn exit_house_move(
+            &self,
+            ghost: &Ghost,
+            _view: &GhostView,
+            _start_counter: u32,
+        ) -> Option<Point2<u8>> {
+            Some(ghost.agent.location)
         }
-        self.get_next_red_chase_move(pacman_location)
     }
 
-    /// Teleport the ghost back to the home position, after it is eaten
-    pub fn send_home(&mut self, ghost_home_pos: &(Point2<u8>, Direction)) {
-        self.agent.location = ghost_home_pos.0;
-        self.agent.direction = ghost_home_pos.1;
+    #[test]
+    fn custom_behavior() {
+        let mut agent_setup = PacmanAgentSetup::default();
+        let behavior: Arc<dyn GhostBehavior> = Arc::new(StayHome);
+        for color in [GhostType::Red, GhostType::Pink, GhostType::Orange] {
+            agent_setup = agent_setup.with_ghost_behavior(color, behavior.clone());
+        }
+        assert_ne!(agent_setup, PacmanAgentSetup::default());
+        let rules = GameRules::default();
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut state = PacmanState::new(&agent_setup, &rules);
+        state.reset(&agent_setup, &rules, false);
+        state.update_pacman(state.pacman.location, Direction::Right);
+        let start = state.ghosts.clone();
 
-        self.previous_location = ghost_home_pos.0;
-        self.respawn_timer = 0;
-        self.frightened_counter = 0;
+        for _ in 0..50 {
+            state.resume();
+            state.step(&agent_setup, &rules, &mut rng, false);
+        }
+        // only the blue ghost left the house
+        for (ghost, start) in state.ghosts.iter().zip(&start) {
+            if ghost.color == GhostType::Blue {
+                assert_ne!(ghost.agent.location, start.agent.location);
+            } else {
+                assert_eq!(ghost.agent.location, start.agent.location);
+            }
+        }
     }
 }
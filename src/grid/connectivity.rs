@@ -0,0 +1,322 @@
+//! Connected components, articulation points and dead ends of a [`ComputedGrid`].
+//!
+//! An articulation point is a walkable cell whose removal would split its component in two, like
+//! the entrance to a dead end. A cell is in a dead end if it is not on any loop and not on a path
+//! between two loops; these are the cells that are removed by repeatedly removing cells with only
+//! one walkable neighbor, and once Pacman enters one the only way out is back the way it came.
+
+use crate::grid::{ComputedGrid, GridValue};
+use rapier2d::na::Point2;
+use serde::{Deserialize, Serialize};
+
+/// Connectivity information about the walkable cells of a [`ComputedGrid`].
+///
+/// Vectors indexed by node are in the same order as [`ComputedGrid::walkable_nodes`].
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Connectivity {
+    /// the walkable cells in each connected component
+    components: Vec<Vec<Point2<u8>>>,
+    /// the component of each node
+    node_components: Vec<usize>,
+    articulation_points: Vec<Point2<u8>>,
+    /// whether each node is an articulation point
+    node_articulation: Vec<bool>,
+    /// whether each node is in a dead end
+    node_dead_end: Vec<bool>,
+}
+
+impl Connectivity {
+    /// Computes the [`Connectivity`] of a [`ComputedGrid`] whose neighbors are already known.
+    pub(crate) fn new(grid: &ComputedGrid) -> Self {
+        let node_count = grid.walkable_nodes.len();
+        let adjacency: Vec<Vec<usize>> = grid
+            .walkable_nodes
+            .iter()
+            .map(|p| {
+                grid.neighbors(p)
+                    .iter()
+                    .map(|n| grid.coords_to_node[n])
+                    .collect()
+            })
+            .collect();
+
+        let mut connectivity = Self {
+            node_components: vec![usize::MAX; node_count],
+            node_articulation: vec![false; node_count],
+            node_dead_end: vec![false; node_count],
+            ..Default::default()
+        };
+
+        // iterative depth first search that finds components and articulation points together
+        let mut discovery = vec![usize::MAX; node_count];
+        let mut low = vec![0; node_count];
+        let mut time = 0;
+        for root in 0..node_count {
+            if discovery[root] != usize::MAX {
+                continue;
+            }
+            let component = connectivity.components.len();
+            connectivity.components.push(vec![]);
+            let mut root_children = 0;
+            // (node, parent, index of the next neighbor to visit)
+            let mut stack = vec![(root, usize::MAX, 0)];
+            discovery[root] = time;
+            low[root] = time;
+            time += 1;
+            while let Some(&mut (node, parent, ref mut next)) = stack.last_mut() {
+                if *next == 0 {
+                    connectivity.node_components[node] = component;
+                    connectivity.components[component].push(grid.walkable_nodes[node]);
+                }
+                if let Some(&neighbor) = adjacency[node].get(*next) {
+                    *next += 1;
+                    if discovery[neighbor] == usize::MAX {
+                        discovery[neighbor] = time;
+                        low[neighbor] = time;
+                        time += 1;
+                        if node == root {
+                            root_children += 1;
+                        }
+                        stack.push((neighbor, node, 0));
+                    } else if neighbor != parent {
+                        low[node] = low[node].min(discovery[neighbor]);
+                    }
+                } else {
+                    stack.pop();
+                    if parent != usize::MAX {
+                        low[parent] = low[parent].min(low[node]);
+                        if parent != root && low[node] >= discovery[parent] {
+                            connectivity.node_articulation[parent] = true;
+                        }
+                    }
+                }
+            }
+            if root_children > 1 {
+                connectivity.node_articulation[root] = true;
+            }
+        }
+        connectivity.articulation_points = (0..node_count)
+            .filter(|&i| connectivity.node_articulation[i])
+            .map(|i| grid.walkable_nodes[i])
+            .collect();
+
+        // peel off cells with one remaining neighbor until only loops and paths between them remain
+        let mut degree: Vec<usize> = adjacency.iter().map(|a| a.len()).collect();
+        let mut leaves: Vec<usize> = (0..node_count).filter(|&i| degree[i] <= 1).collect();
+        while let Some(leaf) = leaves.pop() {
+            if connectivity.node_dead_end[leaf] {
+                continue;
+            }
+            connectivity.node_dead_end[leaf] = true;
+            for &neighbor in &adjacency[leaf] {
+                if !connectivity.node_dead_end[neighbor] {
+                    degree[neighbor] -= 1;
+                    if degree[neighbor] == 1 {
+                        leaves.push(neighbor);
+                    }
+                }
+            }
+        }
+
+        connectivity
+    }
+
+    /// Returns the walkable cells in each connected component.
+    pub fn components(&self) -> &Vec<Vec<Point2<u8>>> {
+        &self.components
+    }
+
+    /// Returns the walkable cells whose removal would disconnect part of their component.
+    pub fn articulation_points(&self) -> &Vec<Point2<u8>> {
+        &self.articulation_points
+    }
+}
+
+impl ComputedGrid {
+    /// Returns the [`Connectivity`] of the walkable cells in the grid.
+    pub fn connectivity(&self) -> &Connectivity {
+        &self.connectivity
+    }
+
+    /// Returns the index of the connected component that contains the given position, or `None`
+    /// if it is not walkable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// assert_eq!(grid.connectivity().components().len(), 1);
+    /// assert_eq!(grid.component(&Point2::new(1, 1)), Some(0));
+    /// assert_eq!(grid.component(&Point2::new(0, 0)), None);
+    /// ```
+    pub fn component(&self, p: &Point2<u8>) -> Option<usize> {
+        self.coords_to_node
+            .get(p)
+            .map(|&i| self.connectivity.node_components[i])
+    }
+
+    /// Returns whether there is a path between two walkable positions.
+    ///
+    /// Positions that are not walkable are never connected.
+    pub fn connected(&self, p1: &Point2<u8>, p2: &Point2<u8>) -> bool {
+        match (self.component(p1), self.component(p2)) {
+            (Some(c1), Some(c2)) => c1 == c2,
+            _ => false,
+        }
+    }
+
+    /// Returns whether removing the given position would split the maze.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Outer.compute_grid();
+    /// assert!(!grid.is_articulation_point(&Point2::new(1, 1)));
+    /// ```
+    pub fn is_articulation_point(&self, p: &Point2<u8>) -> bool {
+        self.coords_to_node
+            .get(p)
+            .is_some_and(|&i| self.connectivity.node_articulation[i])
+    }
+
+    /// Returns whether the given position is a dead end, with exactly one walkable neighbor.
+    pub fn is_dead_end(&self, p: &Point2<u8>) -> bool {
+        self.coords_to_node
+            .get(p)
+            .is_some_and(|_| self.neighbors(p).len() == 1)
+    }
+
+    /// Returns whether the given position is in a dead end branch of the maze, which can only be
+    /// left the way it was entered.
+    ///
+    /// This includes dead ends themselves and every cell leading to them that is not on a loop.
+    pub fn in_dead_end(&self, p: &Point2<u8>) -> bool {
+        self.coords_to_node
+            .get(p)
+            .is_some_and(|&i| self.connectivity.node_dead_end[i])
+    }
+
+    /// Returns the positions of all pellets and power pellets that cannot be reached from the
+    /// given position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// assert!(grid.unreachable_pellets(&Point2::new(1, 1)).is_empty());
+    /// ```
+    pub fn unreachable_pellets(&self, from: &Point2<u8>) -> Vec<Point2<u8>> {
+        self.walkable_nodes
+            .iter()
+            .filter(|p| {
+                matches!(
+                    self.grid[p.x as usize][p.y as usize],
+                    GridValue::o | GridValue::O
+                )
+            })
+            .filter(|p| !self.connected(from, p))
+            .copied()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::{ComputedGrid, Grid, GridValue};
+    use crate::standard_grids::StandardGrid;
+    use rapier2d::na::Point2;
+
+    /// A loop with a two cell dead end on its right, and a separate pellet in the corner
+    fn branching_grid() -> ComputedGrid {
+        let mut grid = Grid::new(12, 9);
+        for x in 1..=5 {
+            grid[x][1] = GridValue::o;
+            grid[x][4] = GridValue::o;
+        }
+        for y in 1..=4 {
+            grid[1][y] = GridValue::o;
+            grid[5][y] = GridValue::o;
+        }
+        for x in 6..=7 {
+            grid[x][4] = GridValue::o;
+        }
+        grid[10][7] = GridValue::O;
+        ComputedGrid::try_from(grid).unwrap()
+    }
+
+    #[test]
+    fn standard_grid_components() {
+        for standard_grid in [
+            StandardGrid::Pacman,
+            StandardGrid::Outer,
+            StandardGrid::Blank,
+        ] {
+            let grid = standard_grid.compute_grid();
+            let start = grid.walkable_nodes()[0];
+            assert_eq!(grid.connectivity().components().len(), 1);
+            assert!(grid.unreachable_pellets(&start).is_empty());
+        }
+
+        // the playground is a collection of separate test shapes
+        let grid = StandardGrid::Playground.compute_grid();
+        assert_eq!(grid.connectivity().components().len(), 8);
+        assert!(!grid.connected(&Point2::new(1, 1), &Point2::new(1, 4)));
+    }
+
+    #[test]
+    fn components() {
+        let grid = branching_grid();
+        assert_eq!(grid.connectivity().components().len(), 2);
+        assert!(grid.connected(&Point2::new(1, 1), &Point2::new(7, 4)));
+        assert!(!grid.connected(&Point2::new(1, 1), &Point2::new(10, 7)));
+        assert!(!grid.connected(&Point2::new(1, 1), &Point2::new(0, 0)));
+        assert_eq!(
+            grid.unreachable_pellets(&Point2::new(1, 1)),
+            vec![Point2::new(10, 7)]
+        );
+        assert_eq!(grid.unreachable_pellets(&Point2::new(10, 7)).len(), 16);
+    }
+
+    #[test]
+    fn articulation_points() {
+        let grid = branching_grid();
+        assert_eq!(
+            grid.connectivity().articulation_points(),
+            &vec![Point2::new(5, 4), Point2::new(6, 4)]
+        );
+        assert!(grid.is_articulation_point(&Point2::new(6, 4)));
+        assert!(!grid.is_articulation_point(&Point2::new(7, 4)));
+        assert!(!grid.is_articulation_point(&Point2::new(1, 1)));
+    }
+
+    #[test]
+    fn dead_ends() {
+        let grid = branching_grid();
+        assert!(grid.is_dead_end(&Point2::new(7, 4)));
+        assert!(!grid.is_dead_end(&Point2::new(6, 4)));
+        assert!(!grid.is_dead_end(&Point2::new(10, 7)));
+
+        assert!(grid.in_dead_end(&Point2::new(7, 4)));
+        assert!(grid.in_dead_end(&Point2::new(6, 4)));
+        assert!(grid.in_dead_end(&Point2::new(10, 7)));
+        assert!(!grid.in_dead_end(&Point2::new(5, 4)));
+        assert!(!grid.in_dead_end(&Point2::new(1, 1)));
+
+        let grid = StandardGrid::Pacman.compute_grid();
+        for p in grid.walkable_nodes() {
+            assert!(!grid.in_dead_end(p));
+        }
+    }
+}
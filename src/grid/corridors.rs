@@ -0,0 +1,376 @@
+//! A compressed graph of the junctions in a [`ComputedGrid`] and the corridors between them.
+//!
+//! A junction is any walkable cell that does not have exactly two walkable neighbors - an
+//! intersection, a dead end, or an isolated cell. A corridor is the chain of cells with exactly two
+//! neighbors that connects two junctions. Loops that contain no junction at all, like the path in
+//! [`GRID_OUTER`](crate::standard_grids::GRID_OUTER), get one of their cells promoted to a junction
+//! so that every walkable cell belongs to the graph.
+
+use crate::grid::{ComputedGrid, Direction, GridValue};
+use rapier2d::na::Point2;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// A chain of cells between two junctions of a [`CorridorGraph`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Corridor {
+    /// The indices of the junctions at either end of the corridor
+    ///
+    /// These are the same for a loop that starts and ends at one junction
+    pub ends: [usize; 2],
+    /// The direction to move from each junction in `ends` to enter the corridor
+    pub end_directions: [Direction; 2],
+    /// The cells strictly between the two junctions, in order from `ends[0]` to `ends[1]`
+    pub cells: Vec<Point2<u8>>,
+    /// The number of steps from one end of the corridor to the other
+    pub length: usize,
+    /// The number of pellets in `cells` in the original [`Grid`](crate::grid::Grid)
+    pub pellet_count: u32,
+    /// The number of power pellets in `cells` in the original [`Grid`](crate::grid::Grid)
+    pub power_pellet_count: u32,
+    /// Whether either end of the corridor is a dead end
+    pub dead_end: bool,
+}
+
+impl Corridor {
+    /// Returns the junction at the other end of the corridor, or `None` if the given junction is
+    /// not an end of this corridor.
+    pub fn other_end(&self, junction: usize) -> Option<usize> {
+        if self.ends[0] == junction {
+            Some(self.ends[1])
+        } else if self.ends[1] == junction {
+            Some(self.ends[0])
+        } else {
+            None
+        }
+    }
+}
+
+/// Where a walkable cell is in a [`CorridorGraph`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum CorridorLocation {
+    /// The cell is the junction with this index
+    Junction(usize),
+    /// The cell is inside a corridor
+    Corridor {
+        /// The index of the corridor
+        corridor: usize,
+        /// The number of steps from the corridor's first end to the cell
+        ///
+        /// This is between 1 and the corridor's length minus 1
+        offset: usize,
+    },
+}
+
+/// A compressed graph of junctions and the corridors between them.
+///
+/// # Examples
+///
+/// ```
+/// use rapier2d::na::Point2;
+/// use mdrc_pacbot_util::grid::corridors::CorridorLocation;
+/// use mdrc_pacbot_util::grid::ComputedGrid;
+/// use mdrc_pacbot_util::standard_grids::StandardGrid;
+///
+/// let grid = StandardGrid::Pacman.compute_grid();
+/// let graph = grid.corridor_graph();
+///
+/// // the bottom row meets a corridor going up here
+/// let junction = graph.junction_at(&Point2::new(12, 1)).unwrap();
+/// assert_eq!(graph.corridors_at(junction).len(), 3);
+///
+/// // the bottom left corner is partway along a corridor that ends at that junction
+/// match grid.corridor_location(&Point2::new(1, 1)) {
+///     Some(CorridorLocation::Corridor { corridor, .. }) => {
+///         let corridor = &graph.corridors()[corridor];
+///         assert!(corridor.ends.contains(&junction));
+///         assert_eq!(corridor.length, 16);
+///     }
+///     _ => panic!("expected a corridor"),
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CorridorGraph {
+    junctions: Vec<Point2<u8>>,
+    corridors: Vec<Corridor>,
+    /// for each junction, the indices of the corridors that touch it
+    junction_corridors: Vec<Vec<usize>>,
+    locations: HashMap<Point2<u8>, CorridorLocation>,
+}
+
+impl CorridorGraph {
+    /// Builds the [`CorridorGraph`] for a [`ComputedGrid`].
+    pub(crate) fn new(grid: &ComputedGrid) -> Self {
+        let mut graph = Self::default();
+
+        for p in grid.walkable_nodes() {
+            if grid.neighbors(p).len() != 2 {
+                graph.add_junction(*p);
+            }
+        }
+
+        // whether a corridor has been traced from each junction in each direction
+        let mut traced: Vec<[bool; 4]> = vec![];
+        let mut next_junction = 0;
+        loop {
+            traced.resize(graph.junctions.len(), [false; 4]);
+            while next_junction < graph.junctions.len() {
+                let junction = next_junction;
+                next_junction += 1;
+                let p = graph.junctions[junction];
+                for n in grid.neighbors(&p) {
+                    let direction = grid.adjacent_direction(&p, &n).unwrap();
+                    if !traced[junction][u8::from(direction) as usize] {
+                        graph.trace_corridor(grid, junction, direction, &mut traced);
+                    }
+                }
+            }
+
+            // any cell left over is part of a loop with no junctions
+            match grid
+                .walkable_nodes()
+                .iter()
+                .find(|p| !graph.locations.contains_key(p))
+            {
+                Some(p) => graph.add_junction(*p),
+                None => break,
+            }
+        }
+
+        graph
+    }
+
+    fn add_junction(&mut self, p: Point2<u8>) {
+        self.locations
+            .insert(p, CorridorLocation::Junction(self.junctions.len()));
+        self.junctions.push(p);
+        self.junction_corridors.push(vec![]);
+    }
+
+    fn trace_corridor(
+        &mut self,
+        grid: &ComputedGrid,
+        start: usize,
+        direction: Direction,
+        traced: &mut [[bool; 4]],
+    ) {
+        let corridor_index = self.corridors.len();
+        let mut cells = vec![];
+        let mut pellet_count = 0;
+        let mut power_pellet_count = 0;
+
+        let mut previous = self.junctions[start];
+        let mut current = grid.next(&previous, &direction).unwrap();
+        let end = loop {
+            if let Some(CorridorLocation::Junction(end)) = self.locations.get(&current) {
+                break *end;
+            }
+            self.locations.insert(
+                current,
+                CorridorLocation::Corridor {
+                    corridor: corridor_index,
+                    offset: cells.len() + 1,
+                },
+            );
+            cells.push(current);
+            match grid.at(&current) {
+                Some(GridValue::o) => pellet_count += 1,
+                Some(GridValue::O) => power_pellet_count += 1,
+                _ => {}
+            }
+            let next = grid
+                .neighbors(&current)
+                .into_iter()
+                .find(|n| *n != previous)
+                .unwrap();
+            previous = current;
+            current = next;
+        };
+        let end_direction = grid.adjacent_direction(&current, &previous).unwrap();
+
+        traced[start][u8::from(direction) as usize] = true;
+        traced[end][u8::from(end_direction) as usize] = true;
+
+        let dead_end = [start, end]
+            .iter()
+            .any(|j| grid.neighbors(&self.junctions[*j]).len() == 1);
+        self.junction_corridors[start].push(corridor_index);
+        if end != start {
+            self.junction_corridors[end].push(corridor_index);
+        }
+        self.corridors.push(Corridor {
+            ends: [start, end],
+            end_directions: [direction, end_direction],
+            length: cells.len() + 1,
+            cells,
+            pellet_count,
+            power_pellet_count,
+            dead_end,
+        });
+    }
+
+    /// Returns the positions of the junctions, indexed by junction.
+    pub fn junctions(&self) -> &Vec<Point2<u8>> {
+        &self.junctions
+    }
+
+    /// Returns the corridors, indexed by corridor.
+    pub fn corridors(&self) -> &Vec<Corridor> {
+        &self.corridors
+    }
+
+    /// Returns the indices of the corridors that touch the given junction.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the junction index is out of bounds.
+    pub fn corridors_at(&self, junction: usize) -> &Vec<usize> {
+        &self.junction_corridors[junction]
+    }
+
+    /// Returns the index of the junction at the given position, if there is one.
+    pub fn junction_at(&self, p: &Point2<u8>) -> Option<usize> {
+        match self.locations.get(p)? {
+            CorridorLocation::Junction(junction) => Some(*junction),
+            CorridorLocation::Corridor { .. } => None,
+        }
+    }
+
+    /// Returns where the given position is in the graph, or `None` if it is not walkable.
+    pub fn location(&self, p: &Point2<u8>) -> Option<CorridorLocation> {
+        self.locations.get(p).copied()
+    }
+}
+
+impl ComputedGrid {
+    /// Returns the [`CorridorGraph`] of junctions and corridors in the grid.
+    pub fn corridor_graph(&self) -> &CorridorGraph {
+        &self.corridor_graph
+    }
+
+    /// Returns the junction or corridor that contains the given position, or `None` if it is not
+    /// walkable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::corridors::CorridorLocation;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// assert!(matches!(
+    ///     grid.corridor_location(&Point2::new(12, 1)),
+    ///     Some(CorridorLocation::Junction(_))
+    /// ));
+    /// assert_eq!(grid.corridor_location(&Point2::new(0, 0)), None);
+    /// ```
+    pub fn corridor_location(&self, p: &Point2<u8>) -> Option<CorridorLocation> {
+        self.corridor_graph.location(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Grid;
+    use crate::standard_grids::{StandardGrid, GRID_BLANK};
+
+    #[test]
+    fn every_cell_has_a_location() {
+        for standard_grid in StandardGrid::get_all() {
+            let grid = standard_grid.compute_grid();
+            let graph = grid.corridor_graph();
+            for p in grid.walkable_nodes() {
+                match graph.location(p).unwrap() {
+                    CorridorLocation::Junction(j) => assert_eq!(graph.junctions()[j], *p),
+                    CorridorLocation::Corridor { corridor, offset } => {
+                        assert_eq!(graph.corridors()[corridor].cells[offset - 1], *p)
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn corridors_are_consistent() {
+        let grid = StandardGrid::Pacman.compute_grid();
+        let graph = grid.corridor_graph();
+        for (i, corridor) in graph.corridors().iter().enumerate() {
+            let start = graph.junctions()[corridor.ends[0]];
+            let end = graph.junctions()[corridor.ends[1]];
+            let mut path = vec![start];
+            path.extend(&corridor.cells);
+            path.push(end);
+            assert_eq!(path.len(), corridor.length + 1);
+            for step in path.windows(2) {
+                assert!(grid.neighbors(&step[0]).contains(&step[1]));
+            }
+            assert_eq!(
+                grid.next(&start, &corridor.end_directions[0]),
+                Some(path[1])
+            );
+            assert_eq!(
+                grid.next(&end, &corridor.end_directions[1]),
+                Some(path[path.len() - 2])
+            );
+            assert!(graph.corridors_at(corridor.ends[0]).contains(&i));
+            assert!(graph.corridors_at(corridor.ends[1]).contains(&i));
+        }
+
+        // each direction out of a junction starts exactly one corridor
+        for (j, p) in graph.junctions().iter().enumerate() {
+            let ends: usize = graph
+                .corridors_at(j)
+                .iter()
+                .map(|c| {
+                    graph.corridors()[*c]
+                        .ends
+                        .iter()
+                        .filter(|e| **e == j)
+                        .count()
+                })
+                .sum();
+            assert_eq!(ends, grid.neighbors(p).len());
+        }
+    }
+
+    #[test]
+    fn dead_end_corridor() {
+        let mut grid = Grid::from(GRID_BLANK);
+        for column in grid.columns_mut().take(5).skip(1) {
+            column[1] = GridValue::o;
+        }
+        let grid = ComputedGrid::try_from(grid).unwrap();
+        let graph = grid.corridor_graph();
+
+        assert_eq!(graph.junctions().len(), 2);
+        assert_eq!(graph.corridors().len(), 1);
+        let corridor = &graph.corridors()[0];
+        assert!(corridor.dead_end);
+        assert_eq!(corridor.length, 3);
+        assert_eq!(corridor.pellet_count, 2);
+        assert_eq!(
+            grid.corridor_location(&Point2::new(3, 1)),
+            Some(CorridorLocation::Corridor {
+                corridor: 0,
+                offset: 2
+            })
+        );
+    }
+
+    #[test]
+    fn loop_without_junctions() {
+        let grid = StandardGrid::Outer.compute_grid();
+        let graph = grid.corridor_graph();
+
+        assert_eq!(graph.junctions().len(), 1);
+        assert_eq!(graph.corridors().len(), 1);
+        let corridor = &graph.corridors()[0];
+        assert_eq!(corridor.ends[0], corridor.ends[1]);
+        assert_eq!(corridor.length, grid.walkable_nodes().len());
+        assert!(!corridor.dead_end);
+        assert_eq!(graph.corridors_at(0), &vec![0]);
+    }
+}
@@ -0,0 +1,242 @@
+//! Precomputed lookup tables for distances to walls in continuous space.
+//!
+//! Building these is slow, so they are not part of [`ComputedGrid`] itself; create them once with
+//! [`ComputedGrid::distance_field`] or [`ComputedGrid::ray_table`] and keep them for as long as the
+//! grid is in use. Both are sampled over the area from `(-1, -1)` to `(width, height)` in world
+//! coordinates, which contains every wall surface of the grid.
+
+use crate::grid::ComputedGrid;
+use rapier2d::na::Point2;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+
+/// Signed distances to the nearest wall, sampled on a fine grid.
+///
+/// See [`ComputedGrid::signed_distance_to_wall`] for the exact values.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DistanceField {
+    /// samples per grid unit
+    resolution: usize,
+    /// the number of samples in each direction
+    columns: usize,
+    rows: usize,
+    /// indexed by column, then row
+    values: Vec<f32>,
+}
+
+impl DistanceField {
+    /// Returns the number of samples per grid unit.
+    pub fn resolution(&self) -> usize {
+        self.resolution
+    }
+
+    /// Returns the approximate signed distance from a point in world coordinates to the nearest
+    /// wall, interpolated between the nearest samples.
+    ///
+    /// Points outside the sampled area use the nearest sample on its edge.
+    pub fn get(&self, p: &Point2<f32>) -> f32 {
+        let scale = self.resolution as f32;
+        let x = ((p.x + 1.0) * scale).clamp(0.0, (self.columns - 1) as f32);
+        let y = ((p.y + 1.0) * scale).clamp(0.0, (self.rows - 1) as f32);
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(self.columns - 1), (y0 + 1).min(self.rows - 1));
+        let (fx, fy) = (x - x0 as f32, y - y0 as f32);
+
+        let value = |x: usize, y: usize| self.values[x * self.rows + y];
+        let bottom = value(x0, y0) * (1.0 - fx) + value(x1, y0) * fx;
+        let top = value(x0, y1) * (1.0 - fx) + value(x1, y1) * fx;
+        bottom * (1.0 - fy) + top * fy
+    }
+}
+
+/// Options for [`ComputedGrid::ray_table`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RayTableOptions {
+    /// The number of ray origins per grid unit, in each direction
+    pub resolution: usize,
+    /// The number of evenly spaced angles that rays are cast at
+    pub angles: usize,
+    /// Rays longer than this are truncated to this distance
+    pub max_range: f32,
+}
+
+impl Default for RayTableOptions {
+    fn default() -> Self {
+        Self {
+            resolution: 4,
+            angles: 90,
+            max_range: 3.0,
+        }
+    }
+}
+
+/// Precomputed ray lengths from sampled origins at sampled angles.
+///
+/// See [`ComputedGrid::cast_ray`] for the exact values.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RayTable {
+    options: RayTableOptions,
+    /// the number of ray origins in each direction
+    columns: usize,
+    rows: usize,
+    /// indexed by column, then row, then angle
+    lengths: Vec<f32>,
+}
+
+impl RayTable {
+    /// Returns the options the table was built with.
+    pub fn options(&self) -> &RayTableOptions {
+        &self.options
+    }
+
+    /// Returns the approximate distance a ray travels from a point in world coordinates before it
+    /// hits a wall, using the nearest sampled origin and angle.
+    ///
+    /// The angle is in radians, counter-clockwise from the `+x` direction. Rays from outside the
+    /// sampled area have length 0.
+    pub fn cast(&self, origin: &Point2<f32>, angle: f32) -> f32 {
+        let scale = self.options.resolution as f32;
+        let x = ((origin.x + 1.0) * scale).round();
+        let y = ((origin.y + 1.0) * scale).round();
+        if x < 0.0 || y < 0.0 || x >= self.columns as f32 || y >= self.rows as f32 {
+            return 0.0;
+        }
+        let angles = self.options.angles;
+        let a = (angle.rem_euclid(2.0 * PI) / (2.0 * PI) * angles as f32).round() as usize % angles;
+        self.lengths[((x as usize) * self.rows + y as usize) * angles + a]
+    }
+}
+
+impl ComputedGrid {
+    /// Samples [`ComputedGrid::signed_distance_to_wall`] with the given number of samples per grid
+    /// unit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// let field = grid.distance_field(4);
+    /// assert_eq!(field.get(&Point2::new(1.0, 1.0)), 1.0);
+    /// assert_eq!(field.get(&Point2::new(0.75, 1.0)), 0.75);
+    /// assert_eq!(field.get(&Point2::new(2.5, 2.5)), -0.5);
+    /// ```
+    pub fn distance_field(&self, resolution: usize) -> DistanceField {
+        let resolution = resolution.max(1);
+        let columns = (self.width() + 1) * resolution + 1;
+        let rows = (self.height() + 1) * resolution + 1;
+        let scale = resolution as f32;
+        let values = (0..columns * rows)
+            .into_par_iter()
+            .map(|i| {
+                let p = Point2::new(
+                    (i / rows) as f32 / scale - 1.0,
+                    (i % rows) as f32 / scale - 1.0,
+                );
+                self.signed_distance_to_wall(&p)
+            })
+            .collect();
+        DistanceField {
+            resolution,
+            columns,
+            rows,
+            values,
+        }
+    }
+
+    /// Casts rays from evenly spaced origins at evenly spaced angles and stores their lengths.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::f32::consts::PI;
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::distance_field::RayTableOptions;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// let table = grid.ray_table(&RayTableOptions {
+    ///     resolution: 2,
+    ///     angles: 4,
+    ///     max_range: 5.0,
+    /// });
+    /// assert_eq!(table.cast(&Point2::new(1.0, 1.0), PI), 1.0);
+    /// assert_eq!(table.cast(&Point2::new(1.0, 1.0), 0.0), 5.0);
+    /// ```
+    pub fn ray_table(&self, options: &RayTableOptions) -> RayTable {
+        let options = RayTableOptions {
+            resolution: options.resolution.max(1),
+            angles: options.angles.max(1),
+            max_range: options.max_range,
+        };
+        let columns = (self.width() + 1) * options.resolution + 1;
+        let rows = (self.height() + 1) * options.resolution + 1;
+        let scale = options.resolution as f32;
+        let angles = options.angles;
+        let lengths = (0..columns * rows * angles)
+            .into_par_iter()
+            .map(|i| {
+                let (origin, a) = (i / angles, i % angles);
+                let p = Point2::new(
+                    (origin / rows) as f32 / scale - 1.0,
+                    (origin % rows) as f32 / scale - 1.0,
+                );
+                self.cast_ray(&p, a as f32 / angles as f32 * 2.0 * PI, options.max_range)
+            })
+            .collect();
+        RayTable {
+            options,
+            columns,
+            rows,
+            lengths,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::standard_grids::StandardGrid;
+
+    #[test]
+    fn distance_field_matches_exact_distances() {
+        let grid = StandardGrid::Pacman.compute_grid();
+        let field = grid.distance_field(4);
+        for x in 0..grid.width() * 3 {
+            for y in 0..grid.height() * 3 {
+                let p = Point2::new(x as f32 / 3.0, y as f32 / 3.0);
+                let exact = grid.signed_distance_to_wall(&p);
+                // interpolation is exact on samples, and close in between
+                assert!((field.get(&p) - exact).abs() < 0.15, "{:?}", p);
+            }
+        }
+    }
+
+    #[test]
+    fn ray_table_matches_exact_rays() {
+        let grid = StandardGrid::Pacman.compute_grid();
+        let options = RayTableOptions {
+            resolution: 2,
+            angles: 8,
+            max_range: 4.0,
+        };
+        let table = grid.ray_table(&options);
+        for x in 0..=grid.width() * 2 {
+            for y in 0..=grid.height() * 2 {
+                let p = Point2::new(x as f32 / 2.0, y as f32 / 2.0);
+                for a in 0..8 {
+                    let angle = a as f32 * PI / 4.0;
+                    assert_eq!(table.cast(&p, angle), grid.cast_ray(&p, angle, 4.0));
+                    // nearby angles use the same bin, including across 0
+                    assert_eq!(table.cast(&p, angle - 0.1), table.cast(&p, angle));
+                }
+            }
+        }
+        assert_eq!(table.cast(&Point2::new(-5.0, 1.0), 0.0), 0.0);
+    }
+}
@@ -0,0 +1,451 @@
+//! Random maze generation for [`Grid`]s.
+//!
+//! Paths are carved along a lattice of rows and columns that are at least 3 cells apart, so every
+//! wall is at least 2 cells thick and no 2x2 square is ever open. A random spanning tree of the
+//! lattice keeps the walkable area connected, and extra paths are then added to create loops.
+
+use crate::grid::{Grid, GridValue, MAX_GRID_SIZE};
+use anyhow::{anyhow, Error};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+/// Options for [`generate_maze`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct MazeOptions {
+    /// The width of the [`Grid`]
+    pub width: usize,
+    /// The height of the [`Grid`]
+    pub height: usize,
+    /// The seed for the random number generator; the same options always produce the same maze
+    pub seed: u64,
+    /// Whether the maze should be mirrored left to right, like the official Pacbot [`Grid`]
+    pub symmetric: bool,
+    /// Whether to put a ghost house in the center of the maze
+    pub ghost_house: bool,
+    /// The chance, from 0 to 1, that each lattice edge not needed for connectivity is opened anyway
+    pub loop_chance: f64,
+    /// Whether to open extra paths until no lattice point is a dead end
+    pub remove_dead_ends: bool,
+    /// Whether to put power pellets at the four corners of the lattice
+    pub power_pellets: bool,
+}
+
+impl Default for MazeOptions {
+    fn default() -> Self {
+        Self {
+            width: 28,
+            height: 31,
+            seed: 0,
+            symmetric: true,
+            ghost_house: true,
+            loop_chance: 0.2,
+            remove_dead_ends: true,
+            power_pellets: true,
+        }
+    }
+}
+
+/// A pair of lattice points, as indices into the lattice columns and rows
+type LatticeEdge = ((usize, usize), (usize, usize));
+
+/// Generates a random maze that passes [`validate_grid`](crate::grid::validation::validate_grid)
+/// and whose walkable cells are all connected.
+///
+/// Walkable cells are filled with pellets, and the ghost house, if any, is made of ghost chamber
+/// cells with a door at the top.
+///
+/// # Examples
+///
+/// ```
+/// use mdrc_pacbot_util::grid::generator::{generate_maze, MazeOptions};
+/// use mdrc_pacbot_util::grid::ComputedGrid;
+///
+/// let grid = generate_maze(&MazeOptions {
+///     seed: 42,
+///     ..Default::default()
+/// })
+/// .unwrap();
+///
+/// assert_eq!(grid.width(), 28);
+/// assert_eq!(grid.height(), 31);
+/// let computed_grid = ComputedGrid::try_from(grid).unwrap();
+/// ```
+pub fn generate_maze(options: &MazeOptions) -> Result<Grid, Error> {
+    let (width, height) = (options.width, options.height);
+    if !(3..=MAX_GRID_SIZE).contains(&width) || !(3..=MAX_GRID_SIZE).contains(&height) {
+        return Err(anyhow!(
+            "Maze size {}x{} is not between 3x3 and {}x{}",
+            width,
+            height,
+            MAX_GRID_SIZE,
+            MAX_GRID_SIZE
+        ));
+    }
+    if options.symmetric && width % 2 == 0 && width < 6 {
+        return Err(anyhow!(
+            "Symmetric maze width {} is too small; even widths must be at least 6",
+            width
+        ));
+    }
+    if !(0.0..=1.0).contains(&options.loop_chance) {
+        return Err(anyhow!(
+            "Loop chance {} is not between 0 and 1",
+            options.loop_chance
+        ));
+    }
+
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    let columns = if options.symmetric {
+        symmetric_lattice_positions(width)
+    } else {
+        lattice_positions(1, width - 2)
+    };
+    let rows = lattice_positions(1, height - 2);
+
+    let mirror = |(x, y): (usize, usize)| {
+        if options.symmetric {
+            (columns.len() - 1 - x, y)
+        } else {
+            (x, y)
+        }
+    };
+
+    // lattice indices of the ghost house's outer corners
+    let house = if options.ghost_house {
+        Some(ghost_house_bounds(&columns, &rows)?)
+    } else {
+        None
+    };
+    let inside_house = |(x, y): (usize, usize)| {
+        house.is_some_and(|((x0, y0), (x1, y1))| x0 < x && x < x1 && y0 < y && y < y1)
+    };
+
+    // every lattice edge, grouped with its mirror image so both are opened together
+    let mut orbits: Vec<Vec<LatticeEdge>> = vec![];
+    for x in 0..columns.len() {
+        for y in 0..rows.len() {
+            for (nx, ny) in [(x + 1, y), (x, y + 1)] {
+                if nx >= columns.len() || ny >= rows.len() {
+                    continue;
+                }
+                let edge = ((x, y), (nx, ny));
+                // edges into the ghost house are never opened
+                if inside_house(edge.0) || inside_house(edge.1) {
+                    continue;
+                }
+                let (a, b) = (mirror(edge.0), mirror(edge.1));
+                let mirrored = if a <= b { (a, b) } else { (b, a) };
+                match mirrored.cmp(&edge) {
+                    // this orbit was added when its mirror image was reached
+                    std::cmp::Ordering::Less => {}
+                    std::cmp::Ordering::Equal => orbits.push(vec![edge]),
+                    std::cmp::Ordering::Greater => orbits.push(vec![edge, mirrored]),
+                }
+            }
+        }
+    }
+
+    let node_index = |(x, y): (usize, usize)| x * rows.len() + y;
+    let mut components = UnionFind::new(columns.len() * rows.len());
+    let mut open = vec![false; orbits.len()];
+    let open_orbit = |i: usize, open: &mut Vec<bool>, components: &mut UnionFind| {
+        open[i] = true;
+        for (a, b) in &orbits[i] {
+            components.union(node_index(*a), node_index(*b));
+        }
+    };
+
+    // the path around the ghost house is always open
+    if let Some(((x0, y0), (x1, y1))) = house {
+        for (i, orbit) in orbits.iter().enumerate() {
+            let ((ax, ay), (bx, by)) = orbit[0];
+            let on_column = (ax == bx) && (ax == x0 || ax == x1) && y0 <= ay && by <= y1;
+            let on_row = (ay == by) && (ay == y0 || ay == y1) && x0 <= ax && bx <= x1;
+            if on_column || on_row {
+                open_orbit(i, &mut open, &mut components);
+            }
+        }
+    }
+
+    // random spanning tree, with each orbit treated as one edge
+    let mut order: Vec<usize> = (0..orbits.len()).collect();
+    order.shuffle(&mut rng);
+    for &i in &order {
+        if open[i] {
+            continue;
+        }
+        let joins_components = orbits[i]
+            .iter()
+            .any(|(a, b)| components.find(node_index(*a)) != components.find(node_index(*b)));
+        if joins_components || rng.gen_bool(options.loop_chance) {
+            open_orbit(i, &mut open, &mut components);
+        }
+    }
+
+    if options.remove_dead_ends {
+        let degree = |node: (usize, usize), open: &Vec<bool>| {
+            (0..orbits.len())
+                .filter(|i| open[*i])
+                .flat_map(|i| orbits[i].iter())
+                .filter(|(a, b)| *a == node || *b == node)
+                .count()
+        };
+        for x in 0..columns.len() {
+            for y in 0..rows.len() {
+                if inside_house((x, y)) || degree((x, y), &open) != 1 {
+                    continue;
+                }
+                let mut candidates: Vec<usize> = (0..orbits.len())
+                    .filter(|i| !open[*i])
+                    .filter(|i| orbits[*i].iter().any(|(a, b)| *a == (x, y) || *b == (x, y)))
+                    .collect();
+                candidates.shuffle(&mut rng);
+                if let Some(&i) = candidates.first() {
+                    open_orbit(i, &mut open, &mut components);
+                }
+            }
+        }
+    }
+
+    // carve the open edges into the grid
+    let mut grid = Grid::new(width, height);
+    for (i, orbit) in orbits.iter().enumerate() {
+        if !open[i] {
+            continue;
+        }
+        for ((ax, ay), (bx, by)) in orbit {
+            for x in columns[*ax]..=columns[*bx] {
+                for y in rows[*ay]..=rows[*by] {
+                    grid[x][y] = GridValue::o;
+                }
+            }
+        }
+    }
+
+    if let Some(((x0, y0), (x1, y1))) = house {
+        let (left, right) = (columns[x0], columns[x1]);
+        let (bottom, top) = (rows[y0], rows[y1]);
+        // walls one cell thick surround the ghost chambers
+        for column in grid.columns_mut().take(right - 1).skip(left + 2) {
+            for cell in column.iter_mut().take(top - 1).skip(bottom + 2) {
+                *cell = GridValue::n;
+            }
+        }
+        // the door is in the middle of the top wall
+        let center = left + right;
+        for x in [center / 2, center.div_ceil(2)] {
+            grid[x][top - 1] = GridValue::n;
+        }
+    }
+
+    if options.power_pellets {
+        for x in [columns[0], columns[columns.len() - 1]] {
+            for y in [rows[0], rows[rows.len() - 1]] {
+                if grid[x][y].walkable() {
+                    grid[x][y] = GridValue::O;
+                }
+            }
+        }
+    }
+
+    Ok(grid)
+}
+
+/// Returns evenly spaced positions from `first` to `last` that are at least 3 apart
+fn lattice_positions(first: usize, last: usize) -> Vec<usize> {
+    let span = last - first;
+    let count = span / 3 + 1;
+    if count == 1 {
+        return vec![first];
+    }
+    (0..count).map(|i| first + i * span / (count - 1)).collect()
+}
+
+/// Returns lattice positions for a [`Grid`] of the given width that are mirrored left to right
+fn symmetric_lattice_positions(width: usize) -> Vec<usize> {
+    // the last position on the left is either the center, or far enough from its mirror image
+    let last = if width % 2 == 1 {
+        (width - 1) / 2
+    } else {
+        (width - 4) / 2
+    };
+    let mut positions = lattice_positions(1, last.max(1));
+    let mirrored: Vec<usize> = positions
+        .iter()
+        .rev()
+        .map(|x| width - 1 - x)
+        .filter(|x| *x > last)
+        .collect();
+    positions.extend(mirrored);
+    positions
+}
+
+/// Finds the lattice indices of the corners of a ghost house in the middle of the lattice
+fn ghost_house_bounds(columns: &[usize], rows: &[usize]) -> Result<LatticeEdge, Error> {
+    // the ghost chambers need to be at least 3 cells wide and 2 cells tall
+    let expand = |positions: &[usize], min_size: usize| {
+        let n = positions.len();
+        let (mut low, mut high) = ((n - 1) / 2, n / 2);
+        while positions[high] - positions[low] < min_size + 3 {
+            if low == 0 || high == n - 1 {
+                return None;
+            }
+            low -= 1;
+            high += 1;
+        }
+        Some((low, high))
+    };
+    let (x0, x1) =
+        expand(columns, 3).ok_or_else(|| anyhow!("Maze is too narrow for a ghost house"))?;
+    let (y0, y1) = expand(rows, 2).ok_or_else(|| anyhow!("Maze is too short for a ghost house"))?;
+    Ok(((x0, y0), (x1, y1)))
+}
+
+/// Disjoint sets of lattice points, for tracking which points are connected
+struct UnionFind {
+    parents: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(size: usize) -> Self {
+        Self {
+            parents: (0..size).collect(),
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            self.parents[i] = self.parents[self.parents[i]];
+            i = self.parents[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        self.parents[a] = b;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::validation::validate_grid;
+    use crate::grid::ComputedGrid;
+
+    fn check_maze(options: &MazeOptions) -> ComputedGrid {
+        let grid = generate_maze(options).unwrap();
+        assert_eq!(validate_grid(&grid, &[]), Ok(()), "{:?}", options);
+        let grid = ComputedGrid::try_from(grid).unwrap();
+
+        // every walkable cell is reachable from every other
+        let start = grid.walkable_nodes()[0];
+        for p in grid.walkable_nodes() {
+            assert!(grid.dist(&start, p).is_some(), "{:?}", options);
+        }
+        grid
+    }
+
+    #[test]
+    fn generated_mazes_are_valid() {
+        for seed in 0..4 {
+            for (width, height) in [(28, 31), (32, 32), (20, 17), (15, 24)] {
+                for symmetric in [false, true] {
+                    for ghost_house in [false, true] {
+                        check_maze(&MazeOptions {
+                            width,
+                            height,
+                            seed,
+                            symmetric,
+                            ghost_house,
+                            ..Default::default()
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn generated_mazes_are_reproducible() {
+        let options = MazeOptions {
+            seed: 7,
+            ..Default::default()
+        };
+        assert_eq!(
+            generate_maze(&options).unwrap(),
+            generate_maze(&options).unwrap()
+        );
+
+        let other = MazeOptions {
+            seed: 8,
+            ..Default::default()
+        };
+        assert_ne!(
+            generate_maze(&options).unwrap(),
+            generate_maze(&other).unwrap()
+        );
+    }
+
+    #[test]
+    fn generated_mazes_are_symmetric() {
+        for width in [27, 28, 29, 30] {
+            let grid = generate_maze(&MazeOptions {
+                width,
+                seed: 3,
+                ..Default::default()
+            })
+            .unwrap();
+            for x in 0..width {
+                assert_eq!(grid[x], grid[width - 1 - x]);
+            }
+        }
+    }
+
+    #[test]
+    fn generated_ghost_house() {
+        let grid = check_maze(&MazeOptions::default());
+        let chambers = grid
+            .grid()
+            .columns()
+            .flatten()
+            .filter(|v| **v == GridValue::n)
+            .count();
+        // at least a 3x2 chamber, plus the door
+        assert!(chambers > 3 * 2);
+    }
+
+    #[test]
+    fn generated_maze_without_dead_ends() {
+        let grid = check_maze(&MazeOptions {
+            seed: 5,
+            ghost_house: false,
+            loop_chance: 0.0,
+            ..Default::default()
+        });
+        for p in grid.walkable_nodes() {
+            assert!(grid.neighbors(p).len() >= 2);
+        }
+    }
+
+    #[test]
+    fn invalid_options() {
+        assert!(generate_maze(&MazeOptions {
+            width: MAX_GRID_SIZE + 1,
+            ..Default::default()
+        })
+        .is_err());
+        assert!(generate_maze(&MazeOptions {
+            width: 5,
+            height: 5,
+            ..Default::default()
+        })
+        .is_err());
+        assert!(generate_maze(&MazeOptions {
+            loop_chance: 2.0,
+            ..Default::default()
+        })
+        .is_err());
+    }
+}
@@ -0,0 +1,284 @@
+//! Movement rules for ghosts, which unlike Pacman can move through the ghost house.
+//!
+//! The ghost house is made of ghost chamber ([`GridValue::n`]) cells. Its doors are the chamber
+//! cells next to a cell that Pacman can walk on. Outside the house, ghosts may not reverse
+//! direction unless they are at a dead end, and may only go back through a door when they are
+//! returning home after being eaten. Inside the house, they can move freely.
+
+use crate::grid::validation::GridValidationError;
+use crate::grid::{ComputedGrid, Direction, GridValue};
+use rapier2d::na::Point2;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+/// Neighbors and distances for ghost movement in a [`ComputedGrid`].
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct GhostGraph {
+    /// cells that ghosts can move through, including the ghost house
+    nodes: Vec<Point2<u8>>,
+    coords_to_node: HashMap<Point2<u8>, usize>,
+    /// the neighbors of each node, in the same order as [`ComputedGrid::neighbors`]
+    neighbors: Vec<Vec<usize>>,
+    distance_matrix: Vec<Vec<Option<u8>>>,
+    doors: Vec<Point2<u8>>,
+}
+
+impl GhostGraph {
+    /// Computes the [`GhostGraph`] of a [`ComputedGrid`].
+    pub(crate) fn new(grid: &ComputedGrid) -> Result<Self, GridValidationError> {
+        let ghost_walkable = |p: &Point2<u8>| grid.at(p).is_some_and(|v| v.ghost_walkable());
+        let nodes: Vec<Point2<u8>> = (0..grid.height())
+            .flat_map(|y| (0..grid.width()).map(move |x| Point2::new(x as u8, y as u8)))
+            .filter(ghost_walkable)
+            .collect();
+        let coords_to_node: HashMap<Point2<u8>, usize> =
+            nodes.iter().enumerate().map(|(i, p)| (*p, i)).collect();
+
+        let neighbors: Vec<Vec<usize>> = nodes
+            .iter()
+            .map(|p| {
+                [
+                    Direction::Right,
+                    Direction::Up,
+                    Direction::Left,
+                    Direction::Down,
+                ]
+                .iter()
+                .filter_map(|d| grid.next(p, d))
+                .filter_map(|n| coords_to_node.get(&n).copied())
+                .collect()
+            })
+            .collect();
+
+        let doors = nodes
+            .iter()
+            .enumerate()
+            .filter(|(i, p)| {
+                grid.at(p) == Some(GridValue::n)
+                    && neighbors[*i]
+                        .iter()
+                        .any(|&n| grid.at(&nodes[n]).is_some_and(|v| v.walkable()))
+            })
+            .map(|(_, p)| *p)
+            .collect();
+
+        let distance_matrix = (0..nodes.len())
+            .map(|start| {
+                let mut distances = vec![None; nodes.len()];
+                distances[start] = Some(0);
+                let mut queue = VecDeque::from([start]);
+                while let Some(node) = queue.pop_front() {
+                    let dist: u8 = distances[node].unwrap();
+                    for &n in &neighbors[node] {
+                        if distances[n].is_none() {
+                            let dist =
+                                dist.checked_add(1)
+                                    .ok_or(GridValidationError::PathTooLong {
+                                        from: nodes[start],
+                                        to: nodes[n],
+                                    })?;
+                            distances[n] = Some(dist);
+                            queue.push_back(n);
+                        }
+                    }
+                }
+                Ok(distances)
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            nodes,
+            coords_to_node,
+            neighbors,
+            distance_matrix,
+            doors,
+        })
+    }
+
+    /// Returns every cell that ghosts can move through.
+    pub fn nodes(&self) -> &Vec<Point2<u8>> {
+        &self.nodes
+    }
+
+    /// Returns the ghost house cells that connect to the rest of the maze.
+    pub fn doors(&self) -> &Vec<Point2<u8>> {
+        &self.doors
+    }
+}
+
+impl ComputedGrid {
+    /// Returns the [`GhostGraph`] of cells that ghosts can move through.
+    pub fn ghost_graph(&self) -> &GhostGraph {
+        &self.ghost_graph
+    }
+
+    /// Returns whether the given position is inside the ghost house.
+    pub fn in_ghost_house(&self, p: &Point2<u8>) -> bool {
+        self.at(p) == Some(GridValue::n)
+    }
+
+    /// Returns the cells a ghost can move to from the given position, ignoring the rules about
+    /// reversing and doors.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// let door = Point2::new(13, 18);
+    /// assert!(grid.ghost_neighbors(&Point2::new(13, 19)).contains(&door));
+    /// assert!(!grid.neighbors(&Point2::new(13, 19)).contains(&door));
+    /// ```
+    pub fn ghost_neighbors(&self, p: &Point2<u8>) -> Vec<Point2<u8>> {
+        match self.ghost_graph.coords_to_node.get(p) {
+            Some(&i) => self.ghost_graph.neighbors[i]
+                .iter()
+                .map(|&n| self.ghost_graph.nodes[n])
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Returns the number of steps a ghost needs to move between two positions, including through
+    /// the ghost house, or `None` if there is no path.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// let (home, outside) = (Point2::new(12, 15), Point2::new(13, 19));
+    /// assert_eq!(grid.ghost_dist(&home, &outside), Some(5));
+    /// assert_eq!(grid.dist(&home, &outside), None);
+    /// ```
+    pub fn ghost_dist(&self, p1: &Point2<u8>, p2: &Point2<u8>) -> Option<u8> {
+        let i1 = self.ghost_graph.coords_to_node.get(p1)?;
+        let i2 = self.ghost_graph.coords_to_node.get(p2)?;
+        self.ghost_graph.distance_matrix[*i1][*i2]
+    }
+
+    /// Returns the cells a ghost at `p` may move to next, after coming from `previous`.
+    ///
+    /// Outside the ghost house, ghosts can't reverse unless there is nowhere else to go, and can
+    /// only enter the ghost house if `entering_house` is set. Inside the house, any move is
+    /// allowed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// let (p, previous) = (Point2::new(13, 19), Point2::new(12, 19));
+    /// assert_eq!(grid.ghost_moves(&p, &previous, false), vec![Point2::new(14, 19)]);
+    /// assert_eq!(
+    ///     grid.ghost_moves(&p, &previous, true),
+    ///     vec![Point2::new(14, 19), Point2::new(13, 18)]
+    /// );
+    /// ```
+    pub fn ghost_moves(
+        &self,
+        p: &Point2<u8>,
+        previous: &Point2<u8>,
+        entering_house: bool,
+    ) -> Vec<Point2<u8>> {
+        let neighbors = self.ghost_neighbors(p);
+        if self.in_ghost_house(p) {
+            return neighbors;
+        }
+        let allowed: Vec<Point2<u8>> = neighbors
+            .into_iter()
+            .filter(|n| entering_house || !self.in_ghost_house(n))
+            .collect();
+        let forward: Vec<Point2<u8>> = allowed.iter().filter(|n| *n != previous).copied().collect();
+        if forward.is_empty() {
+            allowed
+        } else {
+            forward
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::grid::generator::{generate_maze, MazeOptions};
+    use crate::grid::ComputedGrid;
+    use crate::standard_grids::StandardGrid;
+    use rapier2d::na::Point2;
+
+    #[test]
+    fn pacman_ghost_house() {
+        let grid = StandardGrid::Pacman.compute_grid();
+        assert_eq!(
+            grid.ghost_graph().doors(),
+            &vec![Point2::new(13, 18), Point2::new(14, 18)]
+        );
+        assert_eq!(
+            grid.ghost_graph().nodes().len(),
+            grid.walkable_nodes().len() + 20
+        );
+
+        // walkable distances are unchanged, since the house is a dead end for Pacman's purposes
+        for p1 in grid.walkable_nodes().iter().step_by(7) {
+            for p2 in grid.walkable_nodes().iter().step_by(5) {
+                assert_eq!(grid.ghost_dist(p1, p2), grid.dist(p1, p2));
+            }
+        }
+    }
+
+    #[test]
+    fn ghost_moves_in_house() {
+        let grid = StandardGrid::Pacman.compute_grid();
+        let inside = Point2::new(15, 16);
+        assert!(grid.in_ghost_house(&inside));
+        // ghosts can reverse inside the house
+        assert!(grid
+            .ghost_moves(&inside, &Point2::new(15, 15), false)
+            .contains(&Point2::new(15, 15)));
+        // and leave it through the door
+        assert_eq!(
+            grid.ghost_moves(&Point2::new(13, 18), &Point2::new(13, 17), false),
+            vec![
+                Point2::new(14, 18),
+                Point2::new(13, 19),
+                Point2::new(13, 17)
+            ]
+        );
+    }
+
+    #[test]
+    fn ghosts_reverse_at_dead_ends() {
+        let grid = StandardGrid::Playground.compute_grid();
+        // the end of the bottom corridor
+        let end = Point2::new(30, 1);
+        assert_eq!(
+            grid.ghost_moves(&end, &Point2::new(29, 1), false),
+            vec![Point2::new(29, 1)]
+        );
+    }
+
+    #[test]
+    fn generated_ghost_houses_are_reachable() {
+        for seed in 0..5 {
+            let grid = generate_maze(&MazeOptions {
+                seed,
+                ..Default::default()
+            })
+            .unwrap();
+            let grid = ComputedGrid::try_from(grid).unwrap();
+            assert!(!grid.ghost_graph().doors().is_empty());
+            let outside = grid.walkable_nodes()[0];
+            for p in grid.ghost_graph().nodes() {
+                assert!(grid.ghost_dist(p, &outside).is_some());
+            }
+        }
+    }
+}
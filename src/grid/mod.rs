@@ -0,0 +1,1236 @@
+//! Logical grid structs and utilities.
+
+pub mod connectivity;
+pub mod corridors;
+pub mod distance_field;
+pub mod generator;
+pub mod ghost_house;
+pub mod pathfinding;
+pub mod text_format;
+pub mod transform;
+pub mod validation;
+pub mod walls;
+
+use crate::grid::connectivity::Connectivity;
+use crate::grid::corridors::CorridorGraph;
+use crate::grid::ghost_house::GhostGraph;
+use crate::grid::validation::{validate_grid, GridValidationError};
+use anyhow::Error;
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use rapier2d::math::Real;
+use rapier2d::na::Point2;
+use rapier2d::prelude::Rotation;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::ops::{Index, IndexMut};
+
+/// Enum for direction values.
+#[derive(
+    Clone, Copy, Debug, Eq, PartialEq, IntoPrimitive, TryFromPrimitive, Serialize, Deserialize,
+)]
+#[repr(u8)]
+pub enum Direction {
+    /// Right, or +x
+    Right = 0,
+    /// Left, or -x
+    Left = 1,
+    /// Up, or +y
+    Up = 2,
+    /// Down, or -y
+    Down = 3,
+}
+
+impl Direction {
+    /// Get the associated rotation
+    pub fn get_rotation(&self) -> Rotation<f32> {
+        match self {
+            Direction::Right => Rotation::new(0.0),
+            Direction::Left => Rotation::new(std::f32::consts::PI),
+            Direction::Up => Rotation::new(std::f32::consts::PI / 2.0),
+            Direction::Down => Rotation::new(-std::f32::consts::PI / 2.0),
+        }
+    }
+}
+
+/// Enum for [`Grid`] cell values.
+#[derive(
+    Clone, Copy, Debug, Eq, PartialEq, IntoPrimitive, TryFromPrimitive, Serialize, Deserialize,
+)]
+#[repr(u8)]
+#[allow(non_camel_case_types)]
+pub enum GridValue {
+    /// Wall
+    I = 1,
+    /// Normal pellet
+    o = 2,
+    /// Empty space
+    e = 3,
+    /// Power pellet
+    O = 4,
+    /// Ghost chambers
+    n = 5,
+    /// Cherry position
+    c = 6,
+}
+
+impl GridValue {
+    /// Returns whether this [`GridValue`] is walkable.
+    ///
+    /// A [`GridValue`] is walkable if it is not a ghost chamber or wall.
+    pub fn walkable(self) -> bool {
+        self != GridValue::I && self != GridValue::n
+    }
+
+    /// Returns whether ghosts can move through this [`GridValue`].
+    ///
+    /// Unlike [`GridValue::walkable`], this includes ghost chambers.
+    pub fn ghost_walkable(self) -> bool {
+        self != GridValue::I
+    }
+}
+
+/// The largest width or height of a [`Grid`].
+pub const MAX_GRID_SIZE: usize = 32;
+
+/// A 2D grid of [`GridValue`]s.
+///
+/// The grid is indexed by `grid[x][y]`, where `x` is visually horizontal and `y` is vertical.
+/// `grid[x]` is the column of cells with that `x` coordinate.
+///
+/// # Examples
+///
+/// ```
+/// use mdrc_pacbot_util::grid::{Grid, GridValue};
+///
+/// let mut grid = Grid::new(5, 4);
+/// grid[1][2] = GridValue::o;
+///
+/// assert_eq!(grid.width(), 5);
+/// assert_eq!(grid.height(), 4);
+/// assert_eq!(grid[1][2], GridValue::o);
+/// assert_eq!(grid[0][0], GridValue::I);
+/// ```
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Grid {
+    width: usize,
+    height: usize,
+    /// cells in column-major order, so that each column is contiguous
+    cells: Vec<GridValue>,
+}
+
+impl Grid {
+    /// Creates a [`Grid`] of the given size that is entirely walls.
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![GridValue::I; width * height],
+        }
+    }
+
+    /// Returns the number of columns in the [`Grid`].
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the number of rows in the [`Grid`].
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns an iterator over the columns of the [`Grid`], from left to right.
+    pub fn columns(&self) -> impl Iterator<Item = &[GridValue]> {
+        // chunks panics on a size of 0, even if there are no cells
+        self.cells.chunks(self.height.max(1))
+    }
+
+    /// Returns an iterator over the mutable columns of the [`Grid`], from left to right.
+    pub fn columns_mut(&mut self) -> impl Iterator<Item = &mut [GridValue]> {
+        self.cells.chunks_mut(self.height.max(1))
+    }
+}
+
+impl Index<usize> for Grid {
+    type Output = [GridValue];
+
+    fn index(&self, x: usize) -> &Self::Output {
+        assert!(x < self.width, "column {} is out of bounds", x);
+        &self.cells[x * self.height..(x + 1) * self.height]
+    }
+}
+
+impl IndexMut<usize> for Grid {
+    fn index_mut(&mut self, x: usize) -> &mut Self::Output {
+        assert!(x < self.width, "column {} is out of bounds", x);
+        &mut self.cells[x * self.height..(x + 1) * self.height]
+    }
+}
+
+impl<const W: usize, const H: usize> From<[[GridValue; H]; W]> for Grid {
+    fn from(columns: [[GridValue; H]; W]) -> Self {
+        Self {
+            width: W,
+            height: H,
+            cells: columns.iter().flatten().copied().collect(),
+        }
+    }
+}
+
+/// A pair of cells on the edges of a [`Grid`] that are adjacent to each other.
+///
+/// Leaving the [`Grid`] through one end of a tunnel arrives at the other end. The direction
+/// used to leave the [`Grid`] is determined by the edge the cell is on.
+pub type Tunnel = (Point2<u8>, Point2<u8>);
+
+/// Returns the direction that leaves the [`Grid`] from the given edge cell, or `None` if the cell
+/// is not on exactly one edge.
+fn tunnel_exit_direction(grid: &Grid, p: &Point2<u8>) -> Option<Direction> {
+    if p.x as usize >= grid.width || p.y as usize >= grid.height {
+        return None;
+    }
+    let on_left = p.x == 0;
+    let on_right = p.x as usize == grid.width - 1;
+    let on_bottom = p.y == 0;
+    let on_top = p.y as usize == grid.height - 1;
+    match (on_left, on_right, on_bottom, on_top) {
+        (true, false, false, false) => Some(Direction::Left),
+        (false, true, false, false) => Some(Direction::Right),
+        (false, false, true, false) => Some(Direction::Down),
+        (false, false, false, true) => Some(Direction::Up),
+        _ => None,
+    }
+}
+
+/// A rectangle representing a wall.
+///
+/// The rectangle is defined by the top left corner and the bottom right corner.
+/// Note that [`Wall`] does not follow the same grid conventions as [`Grid`].
+/// The coordinates are intended to be +0.5, and may be negative.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Wall {
+    /// The bottom left corner of the [`Wall`].
+    pub left_bottom: Point2<Real>,
+    /// The top right corner of the [`Wall`].
+    pub right_top: Point2<Real>,
+}
+
+/// A [`Grid`] with precomputed data for faster pathfinding.
+///
+/// This struct is created by [`ComputedGrid::try_from`].
+///
+/// # Examples
+///
+/// ```
+/// use mdrc_pacbot_util::grid::ComputedGrid;
+/// use mdrc_pacbot_util::standard_grids::StandardGrid;
+///
+/// let grid = StandardGrid::Blank.compute_grid();
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ComputedGrid {
+    grid: Grid,
+
+    pellet_count: u32,
+    power_pellets: Vec<Point2<u8>>,
+
+    walkable_nodes: Vec<Point2<u8>>,
+    coords_to_node: HashMap<Point2<u8>, usize>,
+
+    /// pairs of edge cells that are adjacent to each other
+    tunnels: Vec<Tunnel>,
+
+    /// walkable, right, left, up, down
+    valid_actions: Vec<[bool; 5]>,
+    /// note that all walkable nodes might not be reachable from each other
+    distance_matrix: Vec<Vec<Option<u8>>>,
+    /// the first direction to move along a shortest path between two nodes
+    next_hop: Vec<Vec<Option<Direction>>>,
+    /// junctions and the corridors between them
+    corridor_graph: CorridorGraph,
+    /// connected components, articulation points and dead ends
+    connectivity: Connectivity,
+    /// neighbors and distances for ghosts, which can also move through the ghost house
+    ghost_graph: GhostGraph,
+
+    /// non-overlapping rectangles that cover the walls
+    walls: Vec<Wall>,
+}
+
+impl TryFrom<Grid> for ComputedGrid {
+    type Error = Error;
+
+    fn try_from(grid: Grid) -> Result<Self, Self::Error> {
+        Self::with_tunnels(grid, vec![])
+    }
+}
+
+impl ComputedGrid {
+    /// Creates a [`ComputedGrid`] where the given pairs of edge cells are connected by tunnels.
+    ///
+    /// Tunnel ends are allowed on the edges of the [`Grid`], which must otherwise be walls.
+    /// Moving off the [`Grid`] from one end of a tunnel arrives at the other end.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::{ComputedGrid, Grid, GridValue};
+    /// use mdrc_pacbot_util::standard_grids::GRID_BLANK;
+    ///
+    /// let mut grid = Grid::from(GRID_BLANK);
+    /// for column in grid.columns_mut() {
+    ///     column[5] = GridValue::e;
+    /// }
+    /// let left = Point2::new(0, 5);
+    /// let right = Point2::new(grid.width() as u8 - 1, 5);
+    ///
+    /// assert!(ComputedGrid::try_from(grid.clone()).is_err());
+    ///
+    /// let grid = ComputedGrid::with_tunnels(grid, vec![(left, right)]).unwrap();
+    /// assert_eq!(grid.dist(&left, &right), Some(1));
+    /// ```
+    pub fn with_tunnels(grid: Grid, tunnels: Vec<Tunnel>) -> Result<Self, Error> {
+        validate_grid(&grid, &tunnels)?;
+        let (width, height) = (grid.width, grid.height);
+
+        let mut pellet_count = 0;
+        let mut power_pellets = vec![];
+
+        let mut walkable_nodes = vec![];
+        let mut coords_to_node: HashMap<Point2<u8>, usize> = HashMap::new();
+
+        let mut distance_matrix = vec![];
+
+        // iterate through all grid positions; only tunnel ends may be walkable on the edges
+        for (y, x) in (0..height).flat_map(|y| (0..width).map(move |x| (y, x))) {
+            let pos = Point2::new(x as u8, y as u8);
+            let tile = grid[x][y];
+            if tile == GridValue::o {
+                // count pellets
+                pellet_count += 1;
+            } else if tile == GridValue::O {
+                // remember super pellets
+                power_pellets.push(pos);
+            }
+            if tile.walkable() {
+                // remember walkable nodes
+                let node_index = walkable_nodes.len();
+                walkable_nodes.push(pos);
+                coords_to_node.insert(pos, node_index);
+            }
+        }
+
+        // initialize distance matrix
+        for _ in 0..walkable_nodes.len() {
+            distance_matrix.push(vec![None; walkable_nodes.len()]);
+        }
+
+        // initialize ComputedGrid
+        let mut s = ComputedGrid {
+            grid,
+            pellet_count,
+            power_pellets,
+            walkable_nodes,
+            coords_to_node,
+            tunnels,
+            valid_actions: vec![],
+            distance_matrix,
+            next_hop: vec![],
+            corridor_graph: CorridorGraph::default(),
+            connectivity: Connectivity::default(),
+            ghost_graph: GhostGraph::default(),
+            walls: Vec::new(),
+        };
+
+        // quick lookup for whether a node is walkable in a given direction
+        s.valid_actions = s
+            .walkable_nodes
+            .iter()
+            .map(|p| {
+                let walkable = |direction| {
+                    s.next(p, &direction)
+                        .and_then(|n| s.at(&n))
+                        .is_some_and(|v| v.walkable())
+                };
+                [
+                    true,
+                    walkable(Direction::Right),
+                    walkable(Direction::Left),
+                    walkable(Direction::Up),
+                    walkable(Direction::Down),
+                ]
+            })
+            .collect();
+
+        // compute distance matrix with BFS
+        for (i, &start) in s.walkable_nodes.iter().enumerate() {
+            let mut visited = vec![false; s.walkable_nodes.len()];
+            let mut queue = VecDeque::from([(start, 0)]);
+            while let Some((pos, dist)) = queue.pop_front() {
+                // only walkable nodes are added to the queue
+                let node_index = *s.coords_to_node.get(&pos).unwrap();
+                if visited[node_index] {
+                    continue;
+                }
+                visited[node_index] = true;
+                let dist = u8::try_from(dist).map_err(|_| GridValidationError::PathTooLong {
+                    from: start,
+                    to: pos,
+                })?;
+                s.distance_matrix[i][node_index] = Some(dist);
+                for neighbor in s.neighbors(&pos) {
+                    queue.push_back((neighbor, usize::from(dist) + 1));
+                }
+            }
+        }
+
+        // compute next hops from the distance matrix; ties go to the first neighbor
+        s.next_hop = s
+            .walkable_nodes
+            .iter()
+            .enumerate()
+            .map(|(i, p)| {
+                let neighbors: Vec<(Direction, usize)> = s
+                    .neighbors(p)
+                    .iter()
+                    .map(|n| {
+                        (
+                            s.adjacent_direction(p, n).unwrap(),
+                            *s.coords_to_node.get(n).unwrap(),
+                        )
+                    })
+                    .collect();
+                (0..s.walkable_nodes.len())
+                    .map(|j| {
+                        let dist = s.distance_matrix[i][j].filter(|&d| d > 0)?;
+                        neighbors
+                            .iter()
+                            .find(|(_, n)| s.distance_matrix[*n][j] == Some(dist - 1))
+                            .map(|(direction, _)| *direction)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        s.corridor_graph = CorridorGraph::new(&s);
+        s.connectivity = Connectivity::new(&s);
+        s.ghost_graph = GhostGraph::new(&s)?;
+
+        s.walls = s.compute_walls();
+
+        Ok(s)
+    }
+
+    /// Returns the underlying [`Grid`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Blank.compute_grid();
+    ///
+    /// assert_eq!(grid.grid()[0][0], mdrc_pacbot_util::grid::GridValue::I);
+    /// ```
+    pub fn grid(&self) -> &Grid {
+        &self.grid
+    }
+
+    /// Returns the width of the underlying [`Grid`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// assert_eq!(grid.width(), 28);
+    /// ```
+    pub fn width(&self) -> usize {
+        self.grid.width
+    }
+
+    /// Returns the height of the underlying [`Grid`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// assert_eq!(grid.height(), 31);
+    /// ```
+    pub fn height(&self) -> usize {
+        self.grid.height
+    }
+
+    /// Returns the number of pellets in the grid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Blank.compute_grid();
+    /// assert_eq!(grid.pellet_count(), 0);
+    /// ```
+    pub fn pellet_count(&self) -> u32 {
+        self.pellet_count
+    }
+
+    /// Returns the positions of all power pellets in the grid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Blank.compute_grid();
+    /// assert!(grid.power_pellets().is_empty());
+    /// ```
+    pub fn power_pellets(&self) -> &Vec<Point2<u8>> {
+        &self.power_pellets
+    }
+
+    /// Returns the positions of all walkable nodes in the grid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Blank.compute_grid();
+    /// assert_eq!(grid.walkable_nodes()[0], Point2::new(1, 1));
+    /// ```
+    pub fn walkable_nodes(&self) -> &Vec<Point2<u8>> {
+        &self.walkable_nodes
+    }
+
+    /// Returns the index of the given position in the walkable_nodes vector, or `None` if the
+    /// position is not walkable.
+    ///     
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Blank.compute_grid();
+    /// assert_eq!(grid.coords_to_node(&Point2::new(1, 1)), Some(0));
+    /// assert_eq!(grid.coords_to_node(&Point2::new(0, 0)), None);
+    /// ```
+    pub fn coords_to_node(&self, p: &Point2<u8>) -> Option<usize> {
+        self.coords_to_node.get(p).copied()
+    }
+
+    /// Returns the valid actions for the given position.
+    ///
+    /// The five values represent:
+    /// - whether the node is walkable
+    /// - whether the node to the right is walkable
+    /// - whether the node to the left is walkable
+    /// - whether the node above is walkable
+    /// - whether the node below is walkable
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Blank.compute_grid();
+    /// assert_eq!(grid.valid_actions(Point2::new(1, 1)), Some([true, false, false, false, false]));
+    /// ```
+    pub fn valid_actions(&self, p: Point2<u8>) -> Option<[bool; 5]> {
+        let node_index = self.coords_to_node.get(&p)?;
+        Some(self.valid_actions[*node_index])
+    }
+
+    /// Returns the [`GridValue`] at the given position, or `None` if the position is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Blank.compute_grid();
+    /// assert_eq!(grid.at(&Point2::new(0, 0)), Some(mdrc_pacbot_util::grid::GridValue::I));
+    /// assert_eq!(grid.at(&Point2::new(1, 1)), Some(mdrc_pacbot_util::grid::GridValue::e));
+    /// assert_eq!(grid.at(&Point2::new(32, 32)), None);
+    /// ```
+    pub fn at(&self, p: &Point2<u8>) -> Option<GridValue> {
+        if p.x >= self.grid.width as u8 || p.y >= self.grid.height as u8 {
+            return None;
+        }
+        Some(self.grid[p.x as usize][p.y as usize])
+    }
+
+    /// Returns the [`Point2`] in the given direction from the given position, or `None` if the
+    /// position is out of bounds.
+    ///
+    /// Moving off the edge of the grid from one end of a tunnel returns the other end.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::{ComputedGrid, Direction};
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Blank.compute_grid();
+    /// assert_eq!(grid.next(&Point2::new(1, 1), &Direction::Right), Some(Point2::new(2, 1)));
+    /// assert_eq!(grid.next(&Point2::new(1, 1), &Direction::Left), Some(Point2::new(0, 1)));
+    /// assert_eq!(grid.next(&Point2::new(1, 1), &Direction::Up), Some(Point2::new(1, 2)));
+    /// assert_eq!(grid.next(&Point2::new(1, 1), &Direction::Down), Some(Point2::new(1, 0)));
+    /// ```
+    pub fn next(&self, p: &Point2<u8>, direction: &Direction) -> Option<Point2<u8>> {
+        if let Some(other_end) = self.tunnel_other_end(p, direction) {
+            return Some(other_end);
+        }
+        match direction {
+            Direction::Right => {
+                if p.x == self.grid.width as u8 - 1 {
+                    return None;
+                }
+                Some(Point2::new(p.x + 1, p.y))
+            }
+            Direction::Left => {
+                if p.x == 0 {
+                    return None;
+                }
+                Some(Point2::new(p.x - 1, p.y))
+            }
+            Direction::Up => {
+                if p.y == self.grid.height as u8 - 1 {
+                    return None;
+                }
+                Some(Point2::new(p.x, p.y + 1))
+            }
+            Direction::Down => {
+                if p.y == 0 {
+                    return None;
+                }
+                Some(Point2::new(p.x, p.y - 1))
+            }
+        }
+    }
+
+    /// Returns the distance between two points, or `None` if the points are not both walkable.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// assert_eq!(grid.dist(&Point2::new(1, 1), &Point2::new(1, 1)), Some(0));
+    /// assert_eq!(grid.dist(&Point2::new(1, 1), &Point2::new(1, 2)), Some(1));
+    /// ```
+    pub fn dist(&self, p1: &Point2<u8>, p2: &Point2<u8>) -> Option<u8> {
+        let p1 = self.coords_to_node.get(p1)?;
+        let p2 = self.coords_to_node.get(p2)?;
+        self.distance_matrix[*p1][*p2]
+    }
+
+    /// Returns the first direction to move along a shortest path from one position to another.
+    ///
+    /// Returns `None` if the positions are equal, either position is not walkable, or there is no
+    /// path between them. When there are several shortest paths, the result is always the same;
+    /// use [`ComputedGrid::optimal_first_moves`] to get every option.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::{ComputedGrid, Direction};
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// assert_eq!(grid.next_hop(&Point2::new(1, 1), &Point2::new(6, 1)), Some(Direction::Right));
+    /// assert_eq!(grid.next_hop(&Point2::new(1, 1), &Point2::new(1, 1)), None);
+    /// ```
+    pub fn next_hop(&self, from: &Point2<u8>, to: &Point2<u8>) -> Option<Direction> {
+        let from = self.coords_to_node.get(from)?;
+        let to = self.coords_to_node.get(to)?;
+        self.next_hop[*from][*to]
+    }
+
+    /// Returns the directions along a shortest path from one position to another.
+    ///
+    /// The path is empty if the positions are equal, and `None` if either position is not
+    /// walkable or there is no path between them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::{ComputedGrid, Direction};
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// let directions = grid.shortest_path_directions(&Point2::new(1, 1), &Point2::new(3, 1)).unwrap();
+    /// assert_eq!(directions, vec![Direction::Right, Direction::Right]);
+    /// ```
+    pub fn shortest_path_directions(
+        &self,
+        from: &Point2<u8>,
+        to: &Point2<u8>,
+    ) -> Option<Vec<Direction>> {
+        let dist = self.dist(from, to)?;
+        let mut directions = Vec::with_capacity(dist as usize);
+        let mut p = *from;
+        while p != *to {
+            let direction = self.next_hop(&p, to)?;
+            directions.push(direction);
+            p = self.next(&p, &direction)?;
+        }
+        Some(directions)
+    }
+
+    /// Returns the positions along a shortest path from one position to another, including both
+    /// the start and the end.
+    ///
+    /// Returns `None` if either position is not walkable or there is no path between them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// let path = grid.shortest_path(&Point2::new(1, 1), &Point2::new(1, 3)).unwrap();
+    /// assert_eq!(path, vec![Point2::new(1, 1), Point2::new(1, 2), Point2::new(1, 3)]);
+    /// ```
+    pub fn shortest_path(&self, from: &Point2<u8>, to: &Point2<u8>) -> Option<Vec<Point2<u8>>> {
+        let mut path = vec![*from];
+        for direction in self.shortest_path_directions(from, to)? {
+            path.push(self.next(path.last().unwrap(), &direction)?);
+        }
+        Some(path)
+    }
+
+    /// Returns every direction that starts a shortest path from one position to another.
+    ///
+    /// The result is empty if the positions are equal, either position is not walkable, or there
+    /// is no path between them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::{ComputedGrid, Direction};
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Outer.compute_grid();
+    /// let moves = grid.optimal_first_moves(&Point2::new(1, 1), &Point2::new(30, 30));
+    /// assert_eq!(moves, vec![Direction::Right, Direction::Up]);
+    /// ```
+    pub fn optimal_first_moves(&self, from: &Point2<u8>, to: &Point2<u8>) -> Vec<Direction> {
+        let Some(dist) = self.dist(from, to).filter(|&d| d > 0) else {
+            return vec![];
+        };
+        [
+            Direction::Right,
+            Direction::Up,
+            Direction::Left,
+            Direction::Down,
+        ]
+        .into_iter()
+        .filter(|direction| {
+            self.next(from, direction)
+                .and_then(|n| self.dist(&n, to))
+                .is_some_and(|d| d + 1 == dist)
+        })
+        .collect()
+    }
+
+    /// Returns all the walkable neighbors of the given position.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// assert!(grid.neighbors(&Point2::new(1, 1)).contains(&Point2::new(1, 2)));
+    /// assert!(grid.neighbors(&Point2::new(1, 1)).contains(&Point2::new(2, 1)));
+    /// ```
+    pub fn neighbors(&self, p: &Point2<u8>) -> Vec<Point2<u8>> {
+        let mut neighbors = vec![];
+        for direction in [
+            Direction::Right,
+            Direction::Up,
+            Direction::Left,
+            Direction::Down,
+        ] {
+            if let Some(neighbor) = self.next(p, &direction) {
+                if let Some(grid_value) = self.at(&neighbor) {
+                    if grid_value.walkable() {
+                        neighbors.push(neighbor);
+                    }
+                }
+            }
+        }
+        neighbors
+    }
+
+    /// Returns the direction that moves from one position to an adjacent position, or `None` if
+    /// the positions are not adjacent.
+    ///
+    /// Unlike [`facing_direction`], this takes tunnels into account.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::{ComputedGrid, Direction};
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// assert_eq!(grid.adjacent_direction(&Point2::new(1, 1), &Point2::new(1, 2)), Some(Direction::Up));
+    /// assert_eq!(grid.adjacent_direction(&Point2::new(1, 1), &Point2::new(1, 3)), None);
+    /// ```
+    pub fn adjacent_direction(&self, from: &Point2<u8>, to: &Point2<u8>) -> Option<Direction> {
+        [
+            Direction::Right,
+            Direction::Left,
+            Direction::Up,
+            Direction::Down,
+        ]
+        .into_iter()
+        .find(|direction| self.next(from, direction) == Some(*to))
+    }
+
+    /// Returns the tunnels in the grid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// assert!(grid.tunnels().is_empty());
+    /// ```
+    pub fn tunnels(&self) -> &Vec<Tunnel> {
+        &self.tunnels
+    }
+
+    /// If moving in the given direction from the given position goes through a tunnel, returns
+    /// the other end of the tunnel
+    fn tunnel_other_end(&self, p: &Point2<u8>, direction: &Direction) -> Option<Point2<u8>> {
+        if tunnel_exit_direction(&self.grid, p) != Some(*direction) {
+            return None;
+        }
+        self.tunnels.iter().find_map(|&(a, b)| {
+            if a == *p {
+                Some(b)
+            } else if b == *p {
+                Some(a)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Returns the [`Wall`]s in the grid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// let walls = grid.walls();
+    /// ```
+    pub fn walls(&self) -> &Vec<Wall> {
+        &self.walls
+    }
+
+    /// Return the walkable node from the nodes surrounding this point
+    pub fn node_nearest(&self, x: f32, y: f32) -> Option<Point2<u8>> {
+        for node in [
+            Point2::new(x.floor() as u8, y.floor() as u8),
+            Point2::new(x.ceil() as u8, y.floor() as u8),
+            Point2::new(x.floor() as u8, y.ceil() as u8),
+            Point2::new(x.ceil() as u8, y.ceil() as u8),
+        ] {
+            if let Some(x) = self.at(&node) {
+                if x.walkable() {
+                    return Some(node);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Find the direction from the start point to the end point
+pub fn facing_direction(start: &Point2<u8>, end: &Point2<u8>) -> Direction {
+    if start.x < end.x {
+        Direction::Right
+    } else if start.x > end.x {
+        Direction::Left
+    } else if start.y < end.y {
+        Direction::Up
+    } else if start.y > end.y {
+        Direction::Down
+    } else {
+        // start == end
+        Direction::Right
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::GridValue::{e as EMPTY, o as PELLET, I as WALL, O as POWER_PELLET};
+    use crate::standard_grids::*;
+
+    #[test]
+    fn compute_small_grid() {
+        let mut grid = Grid::new(5, 3);
+        for column in grid.columns_mut().take(4).skip(1) {
+            column[1] = PELLET;
+        }
+        let computed_grid = ComputedGrid::try_from(grid).unwrap();
+
+        assert_eq!(computed_grid.width(), 5);
+        assert_eq!(computed_grid.height(), 3);
+        assert_eq!(computed_grid.walkable_nodes().len(), 3);
+        assert_eq!(
+            computed_grid.dist(&Point2::new(1, 1), &Point2::new(3, 1)),
+            Some(2)
+        );
+        assert_eq!(computed_grid.at(&Point2::new(5, 1)), None);
+        assert_eq!(
+            computed_grid.next(&Point2::new(4, 1), &Direction::Right),
+            None
+        );
+        assert_eq!(computed_grid.next(&Point2::new(1, 2), &Direction::Up), None);
+    }
+
+    #[test]
+    fn reject_long_paths() {
+        // a corridor that winds back and forth across the whole grid
+        let mut grid = Grid::new(MAX_GRID_SIZE, MAX_GRID_SIZE);
+        for (i, y) in (1..MAX_GRID_SIZE - 1).step_by(3).enumerate() {
+            for column in grid.columns_mut().take(MAX_GRID_SIZE - 1).skip(1) {
+                column[y] = EMPTY;
+            }
+            if y + 3 < MAX_GRID_SIZE - 1 {
+                let x = if i % 2 == 0 { MAX_GRID_SIZE - 2 } else { 1 };
+                grid[x][y + 1] = EMPTY;
+                grid[x][y + 2] = EMPTY;
+            }
+        }
+
+        let error = ComputedGrid::try_from(grid.clone()).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref(),
+            Some(GridValidationError::PathTooLong { .. })
+        ));
+
+        // cutting the corridor short makes every path short enough
+        for column in grid.columns_mut() {
+            column[1..20].fill(WALL);
+        }
+        let computed_grid = ComputedGrid::try_from(grid).unwrap();
+        assert_eq!(
+            computed_grid.dist(&Point2::new(30, 22), &Point2::new(1, 28)),
+            Some(3 * 29 + 2 * 3)
+        );
+    }
+
+    #[test]
+    fn compute_tunnel_adjacency() {
+        let (grid, (left, right)) = tunnel_grid();
+        let computed_grid = ComputedGrid::with_tunnels(grid, vec![(left, right)]).unwrap();
+
+        assert_eq!(computed_grid.next(&left, &Direction::Left), Some(right));
+        assert_eq!(computed_grid.next(&right, &Direction::Right), Some(left));
+        assert_eq!(
+            computed_grid.next(&left, &Direction::Down),
+            Some(Point2::new(0, 4))
+        );
+        assert!(computed_grid.neighbors(&left).contains(&right));
+        assert!(computed_grid.neighbors(&right).contains(&left));
+        assert_eq!(
+            computed_grid.valid_actions(left),
+            Some([true, true, true, false, false])
+        );
+        assert_eq!(
+            computed_grid.adjacent_direction(&right, &left),
+            Some(Direction::Right)
+        );
+        assert_eq!(
+            computed_grid.dist(&Point2::new(2, 5), &Point2::new(29, 5)),
+            Some(5)
+        );
+    }
+
+    #[test]
+    fn compute_preset_grids() {
+        StandardGrid::Pacman.compute_grid();
+        StandardGrid::Blank.compute_grid();
+    }
+
+    #[test]
+    fn compute_pellet_count() {
+        let mut grid = Grid::from(GRID_BLANK);
+        grid[1][1] = PELLET;
+        grid[1][2] = PELLET;
+        grid[6][1] = PELLET;
+
+        let computed_grid = ComputedGrid::try_from(grid).unwrap();
+        assert_eq!(computed_grid.pellet_count, 3);
+    }
+
+    #[test]
+    fn compute_power_pellets() {
+        let mut grid = Grid::from(GRID_BLANK);
+        grid[1][1] = POWER_PELLET;
+        grid[1][2] = POWER_PELLET;
+        grid[6][1] = POWER_PELLET;
+
+        let computed_grid = ComputedGrid::try_from(grid).unwrap();
+        assert_eq!(computed_grid.power_pellets.len(), 3);
+        assert!(computed_grid.power_pellets.contains(&Point2::new(1, 1)));
+        assert!(computed_grid.power_pellets.contains(&Point2::new(1, 2)));
+        assert!(computed_grid.power_pellets.contains(&Point2::new(6, 1)));
+    }
+
+    #[test]
+    fn compute_walkable_nodes() {
+        let mut grid = Grid::from(GRID_BLANK);
+        grid[1][1] = PELLET;
+        grid[1][2] = PELLET;
+        grid[6][1] = PELLET;
+
+        let computed_grid = ComputedGrid::try_from(grid).unwrap();
+        assert_eq!(computed_grid.walkable_nodes.len(), 3);
+        assert!(computed_grid.walkable_nodes.contains(&Point2::new(1, 1)));
+        assert!(computed_grid.walkable_nodes.contains(&Point2::new(1, 2)));
+        assert!(computed_grid.walkable_nodes.contains(&Point2::new(6, 1)));
+    }
+
+    #[test]
+    fn compute_coords_to_node() {
+        let mut grid = Grid::from(GRID_BLANK);
+        grid[1][1] = PELLET;
+        grid[1][2] = PELLET;
+        grid[6][1] = PELLET;
+
+        let computed_grid = ComputedGrid::try_from(grid).unwrap();
+        assert_eq!(computed_grid.coords_to_node.len(), 3);
+        let idx = *computed_grid
+            .coords_to_node
+            .get(&Point2::new(1, 1))
+            .unwrap();
+        assert_eq!(computed_grid.walkable_nodes[idx], Point2::new(1, 1));
+    }
+
+    #[test]
+    fn compute_valid_actions() {
+        let mut grid = Grid::from(GRID_BLANK);
+        grid[1][1] = PELLET;
+        grid[1][2] = PELLET;
+        grid[6][1] = PELLET;
+
+        let computed_grid = ComputedGrid::try_from(grid).unwrap();
+        assert_eq!(computed_grid.valid_actions.len(), 3);
+        let one_one_idx = *computed_grid
+            .coords_to_node
+            .get(&Point2::new(1, 1))
+            .unwrap();
+        assert_eq!(
+            computed_grid.valid_actions[one_one_idx],
+            [true, false, false, true, false]
+        );
+
+        let one_two_idx = *computed_grid
+            .coords_to_node
+            .get(&Point2::new(1, 2))
+            .unwrap();
+        assert_eq!(
+            computed_grid.valid_actions[one_two_idx],
+            [true, false, false, false, true]
+        );
+
+        let six_one_idx = *computed_grid
+            .coords_to_node
+            .get(&Point2::new(6, 1))
+            .unwrap();
+        assert_eq!(
+            computed_grid.valid_actions[six_one_idx],
+            [true, false, false, false, false]
+        );
+    }
+
+    #[test]
+    fn compute_distance_matrix() {
+        let mut grid = Grid::from(GRID_BLANK);
+        grid[1][1] = PELLET;
+        grid[1][2] = PELLET;
+        grid[6][1] = PELLET;
+
+        let points = [Point2::new(1, 1), Point2::new(1, 2), Point2::new(6, 1)];
+
+        let computed_grid = ComputedGrid::try_from(grid).unwrap();
+        assert_eq!(computed_grid.distance_matrix.len(), 3);
+        assert_eq!(computed_grid.distance_matrix[0].len(), 3);
+        assert_eq!(computed_grid.distance_matrix[1].len(), 3);
+        assert_eq!(computed_grid.distance_matrix[2].len(), 3);
+        assert_eq!(computed_grid.dist(&points[0], &points[0]), Some(0));
+        assert_eq!(computed_grid.dist(&points[0], &points[1]), Some(1));
+        assert_eq!(computed_grid.dist(&points[0], &points[2]), None);
+        assert_eq!(computed_grid.dist(&points[1], &points[0]), Some(1));
+        assert_eq!(computed_grid.dist(&points[1], &points[1]), Some(0));
+        assert_eq!(computed_grid.dist(&points[1], &points[2]), None);
+        assert_eq!(computed_grid.dist(&points[2], &points[0]), None);
+        assert_eq!(computed_grid.dist(&points[2], &points[1]), None);
+        assert_eq!(computed_grid.dist(&points[2], &points[2]), Some(0));
+    }
+
+    #[test]
+    fn compute_distance_matrix_shortest() {
+        let grid = StandardGrid::Pacman.compute_grid();
+
+        // neighbors can differ by at most one step from any other node
+        for a in grid.walkable_nodes() {
+            for b in grid.neighbors(a) {
+                for c in grid.walkable_nodes() {
+                    let d1 = grid.dist(a, c).unwrap() as i32;
+                    let d2 = grid.dist(&b, c).unwrap() as i32;
+                    assert!((d1 - d2).abs() <= 1);
+                }
+            }
+        }
+        assert_eq!(grid.dist(&Point2::new(1, 1), &Point2::new(26, 1)), Some(25));
+    }
+
+    #[test]
+    fn compute_shortest_paths() {
+        let grid = StandardGrid::Pacman.compute_grid();
+
+        for a in grid.walkable_nodes() {
+            for b in grid.walkable_nodes() {
+                let dist = grid.dist(a, b).unwrap();
+                let path = grid.shortest_path(a, b).unwrap();
+                assert_eq!(path.len(), dist as usize + 1);
+                assert_eq!(path.first(), Some(a));
+                assert_eq!(path.last(), Some(b));
+                for step in path.windows(2) {
+                    assert!(grid.neighbors(&step[0]).contains(&step[1]));
+                }
+
+                let moves = grid.optimal_first_moves(a, b);
+                assert_eq!(moves.is_empty(), a == b);
+                if let Some(direction) = grid.next_hop(a, b) {
+                    assert!(moves.contains(&direction));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shortest_path_unreachable() {
+        let mut grid = Grid::from(GRID_BLANK);
+        grid[6][1] = PELLET;
+        let grid = ComputedGrid::try_from(grid).unwrap();
+
+        let a = Point2::new(1, 1);
+        let b = Point2::new(6, 1);
+        assert_eq!(grid.shortest_path(&a, &a), Some(vec![a]));
+        assert_eq!(grid.shortest_path_directions(&a, &a), Some(vec![]));
+        assert_eq!(grid.shortest_path(&a, &b), None);
+        assert_eq!(grid.shortest_path(&a, &Point2::new(0, 0)), None);
+        assert_eq!(grid.next_hop(&a, &b), None);
+        assert!(grid.optimal_first_moves(&a, &b).is_empty());
+    }
+
+    #[test]
+    fn shortest_path_through_tunnel() {
+        let (grid, (left, right)) = tunnel_grid();
+        let grid = ComputedGrid::with_tunnels(grid, vec![(left, right)]).unwrap();
+
+        let start = Point2::new(1, 5);
+        let end = Point2::new(30, 5);
+        assert_eq!(
+            grid.shortest_path(&start, &end),
+            Some(vec![start, left, right, end])
+        );
+        assert_eq!(
+            grid.optimal_first_moves(&start, &end),
+            vec![Direction::Left]
+        );
+    }
+
+    #[test]
+    fn grid_next() {
+        let grid = StandardGrid::Blank.compute_grid();
+        assert_eq!(
+            grid.next(&Point2::new(1, 1), &Direction::Right),
+            Some(Point2::new(2, 1))
+        );
+        assert_eq!(
+            grid.next(&Point2::new(1, 1), &Direction::Left),
+            Some(Point2::new(0, 1))
+        );
+        assert_eq!(
+            grid.next(&Point2::new(1, 1), &Direction::Up),
+            Some(Point2::new(1, 2))
+        );
+        assert_eq!(
+            grid.next(&Point2::new(1, 1), &Direction::Down),
+            Some(Point2::new(1, 0))
+        );
+    }
+
+    #[test]
+    fn grid_next_oob() {
+        let grid = StandardGrid::Blank.compute_grid();
+        assert_eq!(grid.next(&Point2::new(0, 0), &Direction::Left), None);
+        assert_eq!(grid.next(&Point2::new(0, 0), &Direction::Down), None);
+        assert_eq!(
+            grid.next(&Point2::new(0, (grid.height() - 1) as u8), &Direction::Up),
+            None
+        );
+        assert_eq!(
+            grid.next(&Point2::new((grid.width() - 1) as u8, 0), &Direction::Right),
+            None
+        );
+    }
+
+    #[test]
+    fn grid_at() {
+        let grid = StandardGrid::Blank.compute_grid();
+        assert_eq!(grid.at(&Point2::new(0, 0)), Some(WALL));
+    }
+
+    #[test]
+    fn grid_at_oob() {
+        let grid = StandardGrid::Blank.compute_grid();
+        assert_eq!(grid.at(&Point2::new(0, grid.height() as u8)), None);
+        assert_eq!(grid.at(&Point2::new(grid.width() as u8, 0)), None);
+    }
+}
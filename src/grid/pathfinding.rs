@@ -0,0 +1,317 @@
+//! Weighted pathfinding on a [`ComputedGrid`].
+//!
+//! [`ComputedGrid::dist`] counts every step the same, which is not enough for strategy code that
+//! wants to avoid ghosts or prefer paths with pellets. These functions take a [`PathCost`], which
+//! can be a [`CostMap`] of per-cell costs or any closure that prices a single step.
+
+use crate::grid::ComputedGrid;
+use rapier2d::na::Point2;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+/// The cost of moving between adjacent cells, for weighted pathfinding.
+pub trait PathCost {
+    /// Returns the cost of moving from one cell to an adjacent one, or `None` if the move is not
+    /// allowed.
+    ///
+    /// Costs must not be negative.
+    fn cost(&self, from: &Point2<u8>, to: &Point2<u8>) -> Option<f32>;
+
+    /// Returns a lower bound on the cost of any single step, used to guide A* searches.
+    ///
+    /// The default of 0 is always correct, but makes [`ComputedGrid::find_path`] search as many
+    /// cells as Dijkstra's algorithm would.
+    fn min_cost(&self) -> f32 {
+        0.0
+    }
+}
+
+impl<F: Fn(&Point2<u8>, &Point2<u8>) -> Option<f32>> PathCost for F {
+    fn cost(&self, from: &Point2<u8>, to: &Point2<u8>) -> Option<f32> {
+        self(from, to)
+    }
+}
+
+/// A cost for entering each cell of a grid, with some cells blocked entirely.
+///
+/// # Examples
+///
+/// ```
+/// use rapier2d::na::Point2;
+/// use mdrc_pacbot_util::grid::pathfinding::CostMap;
+/// use mdrc_pacbot_util::standard_grids::StandardGrid;
+///
+/// let grid = StandardGrid::Pacman.compute_grid();
+/// let mut costs = CostMap::new(&grid, 1.0);
+/// costs.add_cost(&Point2::new(2, 1), 10.0);
+/// costs.block(&Point2::new(1, 2));
+///
+/// assert_eq!(costs.get(&Point2::new(2, 1)), Some(11.0));
+/// assert_eq!(costs.get(&Point2::new(1, 2)), None);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct CostMap {
+    width: usize,
+    height: usize,
+    /// indexed by column, then row; `None` is blocked
+    costs: Vec<Option<f32>>,
+}
+
+impl CostMap {
+    /// Creates a [`CostMap`] for the given grid where every cell has the same cost.
+    pub fn new(grid: &ComputedGrid, default_cost: f32) -> Self {
+        Self {
+            width: grid.width(),
+            height: grid.height(),
+            costs: vec![Some(default_cost); grid.width() * grid.height()],
+        }
+    }
+
+    fn index(&self, p: &Point2<u8>) -> Option<usize> {
+        let (x, y) = (p.x as usize, p.y as usize);
+        (x < self.width && y < self.height).then_some(x * self.height + y)
+    }
+
+    /// Returns the cost of entering a cell, or `None` if it is blocked or outside the grid.
+    pub fn get(&self, p: &Point2<u8>) -> Option<f32> {
+        self.index(p).and_then(|i| self.costs[i])
+    }
+
+    /// Sets the cost of entering a cell, unblocking it if necessary.
+    pub fn set_cost(&mut self, p: &Point2<u8>, cost: f32) {
+        if let Some(i) = self.index(p) {
+            self.costs[i] = Some(cost);
+        }
+    }
+
+    /// Adds to the cost of entering a cell, unless it is blocked.
+    pub fn add_cost(&mut self, p: &Point2<u8>, cost: f32) {
+        if let Some(i) = self.index(p) {
+            if let Some(c) = &mut self.costs[i] {
+                *c += cost;
+            }
+        }
+    }
+
+    /// Prevents paths from entering a cell.
+    pub fn block(&mut self, p: &Point2<u8>) {
+        if let Some(i) = self.index(p) {
+            self.costs[i] = None;
+        }
+    }
+}
+
+impl PathCost for CostMap {
+    fn cost(&self, _from: &Point2<u8>, to: &Point2<u8>) -> Option<f32> {
+        self.get(to)
+    }
+
+    fn min_cost(&self) -> f32 {
+        self.costs
+            .iter()
+            .flatten()
+            .copied()
+            .fold(f32::INFINITY, f32::min)
+            .clamp(0.0, f32::MAX)
+    }
+}
+
+/// A path found by [`ComputedGrid::find_path`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeightedPath {
+    /// The cells along the path, including the start and the end
+    pub cells: Vec<Point2<u8>>,
+    /// The total cost of every step along the path
+    pub cost: f32,
+}
+
+/// An entry in the search frontier, ordered so that the lowest estimate is popped first
+#[derive(PartialEq)]
+struct Frontier {
+    estimate: f32,
+    cost: f32,
+    node: usize,
+}
+
+impl Eq for Frontier {}
+
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .estimate
+            .total_cmp(&self.estimate)
+            .then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl ComputedGrid {
+    /// Searches outward from `from`, returning the cheapest cost to each node and the node it was
+    /// reached from, and stopping early once `to` is reached.
+    fn weighted_search(
+        &self,
+        from: &Point2<u8>,
+        to: Option<&Point2<u8>>,
+        costs: &impl PathCost,
+    ) -> (Vec<Option<f32>>, Vec<Option<usize>>) {
+        let node_count = self.walkable_nodes.len();
+        let mut best = vec![None; node_count];
+        let mut previous = vec![None; node_count];
+        let Some(&start) = self.coords_to_node.get(from) else {
+            return (best, previous);
+        };
+        let goal = to.and_then(|p| self.coords_to_node.get(p).copied());
+        let min_cost = costs.min_cost();
+        let heuristic = |node: usize| match goal {
+            Some(goal) => self.distance_matrix[node][goal].map_or(0.0, |d| d as f32 * min_cost),
+            None => 0.0,
+        };
+
+        let mut done = vec![false; node_count];
+        let mut frontier = BinaryHeap::from([Frontier {
+            estimate: heuristic(start),
+            cost: 0.0,
+            node: start,
+        }]);
+        best[start] = Some(0.0);
+        while let Some(Frontier { cost, node, .. }) = frontier.pop() {
+            if done[node] {
+                continue;
+            }
+            done[node] = true;
+            if Some(node) == goal {
+                break;
+            }
+            let p = self.walkable_nodes[node];
+            for neighbor in self.neighbors(&p) {
+                let Some(step) = costs.cost(&p, &neighbor) else {
+                    continue;
+                };
+                let next = self.coords_to_node[&neighbor];
+                let next_cost = cost + step.max(0.0);
+                if best[next].is_none_or(|c| next_cost < c) {
+                    best[next] = Some(next_cost);
+                    previous[next] = Some(node);
+                    frontier.push(Frontier {
+                        estimate: next_cost + heuristic(next),
+                        cost: next_cost,
+                        node: next,
+                    });
+                }
+            }
+        }
+        (best, previous)
+    }
+
+    /// Finds the cheapest path between two walkable positions with A*, or `None` if every path is
+    /// blocked.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::pathfinding::CostMap;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// let (start, end) = (Point2::new(1, 1), Point2::new(6, 5));
+    ///
+    /// let costs = CostMap::new(&grid, 1.0);
+    /// let path = grid.find_path(&start, &end, &costs).unwrap();
+    /// assert_eq!(path.cost, grid.dist(&start, &end).unwrap() as f32);
+    ///
+    /// // closures work too; this one makes moving up twice as expensive
+    /// let path = grid
+    ///     .find_path(&start, &end, &|from: &Point2<u8>, to: &Point2<u8>| {
+    ///         Some(if to.y > from.y { 2.0 } else { 1.0 })
+    ///     })
+    ///     .unwrap();
+    /// assert_eq!(path.cells.first(), Some(&start));
+    /// assert_eq!(path.cells.last(), Some(&end));
+    /// ```
+    pub fn find_path(
+        &self,
+        from: &Point2<u8>,
+        to: &Point2<u8>,
+        costs: &impl PathCost,
+    ) -> Option<WeightedPath> {
+        let goal = *self.coords_to_node.get(to)?;
+        let (best, previous) = self.weighted_search(from, Some(to), costs);
+        let cost = best[goal]?;
+
+        let mut cells = vec![*to];
+        let mut node = goal;
+        while let Some(p) = previous[node] {
+            cells.push(self.walkable_nodes[p]);
+            node = p;
+        }
+        cells.reverse();
+        Some(WeightedPath { cells, cost })
+    }
+
+    /// Returns the cheapest cost from a position to every walkable node, in the same order as
+    /// [`ComputedGrid::walkable_nodes`], using Dijkstra's algorithm.
+    ///
+    /// Nodes that can't be reached are `None`.
+    pub fn path_costs(&self, from: &Point2<u8>, costs: &impl PathCost) -> Vec<Option<f32>> {
+        self.weighted_search(from, None, costs).0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::standard_grids::StandardGrid;
+
+    #[test]
+    fn uniform_costs_match_dist() {
+        let grid = StandardGrid::Pacman.compute_grid();
+        let costs = CostMap::new(&grid, 1.0);
+        let start = Point2::new(1, 1);
+        let all = grid.path_costs(&start, &costs);
+        for (i, p) in grid.walkable_nodes().iter().enumerate() {
+            let expected = grid.dist(&start, p).map(|d| d as f32);
+            assert_eq!(all[i], expected);
+            let path = grid.find_path(&start, p, &costs).unwrap();
+            assert_eq!(Some(path.cost), expected);
+            assert_eq!(path.cells.len() as f32, path.cost + 1.0);
+            for pair in path.cells.windows(2) {
+                assert!(grid.neighbors(&pair[0]).contains(&pair[1]));
+            }
+        }
+    }
+
+    #[test]
+    fn blocked_cells_are_avoided() {
+        let grid = StandardGrid::Outer.compute_grid();
+        let (start, end) = (Point2::new(1, 1), Point2::new(3, 1));
+        let mut costs = CostMap::new(&grid, 1.0);
+        assert_eq!(grid.find_path(&start, &end, &costs).unwrap().cost, 2.0);
+
+        // the only other way is all the way around the loop
+        costs.block(&Point2::new(2, 1));
+        let path = grid.find_path(&start, &end, &costs).unwrap();
+        assert_eq!(path.cells.len(), grid.walkable_nodes().len() - 1);
+        assert!(!path.cells.contains(&Point2::new(2, 1)));
+
+        costs.block(&Point2::new(1, 2));
+        assert_eq!(grid.find_path(&start, &end, &costs), None);
+        assert_eq!(grid.find_path(&start, &Point2::new(0, 0), &costs), None);
+    }
+
+    #[test]
+    fn expensive_cells_are_avoided() {
+        let grid = StandardGrid::Outer.compute_grid();
+        let (start, end) = (Point2::new(1, 1), Point2::new(3, 1));
+        let mut costs = CostMap::new(&grid, 1.0);
+        costs.add_cost(&Point2::new(2, 1), 1000.0);
+        let path = grid.find_path(&start, &end, &costs).unwrap();
+        assert!(!path.cells.contains(&Point2::new(2, 1)));
+        assert_eq!(path.cost, (grid.walkable_nodes().len() - 2) as f32);
+    }
+}
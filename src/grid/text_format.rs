@@ -0,0 +1,353 @@
+//! Plain-text representation of a [`Grid`].
+//!
+//! Each line of text is one row of the [`Grid`], from top (`y = height - 1`) to bottom (`y = 0`),
+//! and each character is one cell, from left (`x = 0`) to right. The characters match the
+//! [`GridValue`] variant names: `I`, `o`, `e`, `O`, `n`, and `c`. The size of the [`Grid`] is
+//! taken from the number of lines and the length of the first line.
+
+use crate::grid::{Grid, GridValue, MAX_GRID_SIZE};
+use std::fmt::{Display, Formatter};
+
+impl GridValue {
+    /// Returns the character used for this [`GridValue`] in the text format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::grid::GridValue;
+    ///
+    /// assert_eq!(GridValue::I.to_char(), 'I');
+    /// assert_eq!(GridValue::o.to_char(), 'o');
+    /// ```
+    pub fn to_char(self) -> char {
+        match self {
+            GridValue::I => 'I',
+            GridValue::o => 'o',
+            GridValue::e => 'e',
+            GridValue::O => 'O',
+            GridValue::n => 'n',
+            GridValue::c => 'c',
+        }
+    }
+
+    /// Returns the [`GridValue`] for a character in the text format, or `None` if the character
+    /// does not represent a [`GridValue`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::grid::GridValue;
+    ///
+    /// assert_eq!(GridValue::from_char('O'), Some(GridValue::O));
+    /// assert_eq!(GridValue::from_char('x'), None);
+    /// ```
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'I' => Some(GridValue::I),
+            'o' => Some(GridValue::o),
+            'e' => Some(GridValue::e),
+            'O' => Some(GridValue::O),
+            'n' => Some(GridValue::n),
+            'c' => Some(GridValue::c),
+            _ => None,
+        }
+    }
+}
+
+/// The reason a [`Grid`] could not be parsed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GridParseErrorKind {
+    /// The character does not represent a [`GridValue`]
+    InvalidCharacter(char),
+    /// The line does not have the same number of characters as the first line
+    WrongLineLength {
+        /// The number of characters expected
+        expected: usize,
+        /// The number of characters found
+        found: usize,
+    },
+    /// The [`Grid`] is wider than [`MAX_GRID_SIZE`]
+    TooWide {
+        /// The number of characters found
+        found: usize,
+    },
+    /// The [`Grid`] is taller than [`MAX_GRID_SIZE`]
+    TooTall {
+        /// The number of lines found
+        found: usize,
+    },
+    /// The text has no lines
+    Empty,
+}
+
+/// An error encountered while parsing a [`Grid`] from text.
+///
+/// Line and column numbers start at 1, like in a text editor.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GridParseError {
+    /// The line where the error occurred
+    pub line: usize,
+    /// The column where the error occurred
+    pub column: usize,
+    /// What went wrong
+    pub kind: GridParseErrorKind,
+}
+
+impl Display for GridParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: ", self.line, self.column)?;
+        match self.kind {
+            GridParseErrorKind::InvalidCharacter(c) => {
+                write!(f, "invalid grid character {:?}", c)
+            }
+            GridParseErrorKind::WrongLineLength { expected, found } => {
+                write!(f, "expected {} cells in row, found {}", expected, found)
+            }
+            GridParseErrorKind::TooWide { found } => {
+                write!(
+                    f,
+                    "found {} cells in row, but the maximum is {}",
+                    found, MAX_GRID_SIZE
+                )
+            }
+            GridParseErrorKind::TooTall { found } => {
+                write!(
+                    f,
+                    "found {} rows, but the maximum is {}",
+                    found, MAX_GRID_SIZE
+                )
+            }
+            GridParseErrorKind::Empty => write!(f, "no rows found"),
+        }
+    }
+}
+
+impl std::error::Error for GridParseError {}
+
+/// Parses a [`Grid`] from its text representation.
+///
+/// Trailing blank lines and carriage returns are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use mdrc_pacbot_util::grid::ComputedGrid;
+/// use mdrc_pacbot_util::grid::text_format::{grid_to_string, parse_grid};
+/// use mdrc_pacbot_util::standard_grids::GRID_PACMAN;
+///
+/// let text = grid_to_string(&GRID_PACMAN.into());
+/// let grid = parse_grid(&text).unwrap();
+/// assert_eq!(grid.width(), 28);
+/// assert_eq!(grid.height(), 31);
+///
+/// let computed_grid = ComputedGrid::try_from(grid).unwrap();
+/// ```
+pub fn parse_grid(text: &str) -> Result<Grid, GridParseError> {
+    let mut lines: Vec<&str> = text
+        .lines()
+        .map(|line| line.strip_suffix('\r').unwrap_or(line))
+        .collect();
+    while lines.last().is_some_and(|line| line.trim().is_empty()) {
+        lines.pop();
+    }
+
+    let height = lines.len();
+    let width = lines.first().map_or(0, |line| line.chars().count());
+    if height == 0 {
+        return Err(GridParseError {
+            line: 1,
+            column: 1,
+            kind: GridParseErrorKind::Empty,
+        });
+    }
+    if height > MAX_GRID_SIZE {
+        return Err(GridParseError {
+            line: MAX_GRID_SIZE + 1,
+            column: 1,
+            kind: GridParseErrorKind::TooTall { found: height },
+        });
+    }
+    if width > MAX_GRID_SIZE {
+        return Err(GridParseError {
+            line: 1,
+            column: MAX_GRID_SIZE + 1,
+            kind: GridParseErrorKind::TooWide { found: width },
+        });
+    }
+
+    let mut grid = Grid::new(width, height);
+
+    for (row, line) in lines.iter().enumerate() {
+        let y = height - 1 - row;
+        let mut found = 0;
+        for (column, c) in line.chars().enumerate() {
+            found += 1;
+            if column >= width {
+                continue;
+            }
+            grid[column][y] = GridValue::from_char(c).ok_or(GridParseError {
+                line: row + 1,
+                column: column + 1,
+                kind: GridParseErrorKind::InvalidCharacter(c),
+            })?;
+        }
+        if found != width {
+            return Err(GridParseError {
+                line: row + 1,
+                column: found.min(width) + 1,
+                kind: GridParseErrorKind::WrongLineLength {
+                    expected: width,
+                    found,
+                },
+            });
+        }
+    }
+
+    Ok(grid)
+}
+
+/// Writes a [`Grid`] in its text representation.
+///
+/// The output ends with a newline, and can be read back with [`parse_grid`].
+///
+/// # Examples
+///
+/// ```
+/// use mdrc_pacbot_util::grid::text_format::grid_to_string;
+/// use mdrc_pacbot_util::standard_grids::GRID_BLANK;
+///
+/// let text = grid_to_string(&GRID_BLANK.into());
+/// let bottom_rows: Vec<&str> = text.lines().rev().take(2).collect();
+/// assert_eq!(bottom_rows[0], "I".repeat(32));
+/// assert!(bottom_rows[1].starts_with("Ie"));
+/// ```
+pub fn grid_to_string(grid: &Grid) -> String {
+    let mut s = String::with_capacity((grid.width() + 1) * grid.height());
+    for y in (0..grid.height()).rev() {
+        for column in grid.columns() {
+            s.push(column[y].to_char());
+        }
+        s.push('\n');
+    }
+    s
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::standard_grids::StandardGrid;
+
+    #[test]
+    fn round_trip_standard_grids() {
+        for standard_grid in StandardGrid::get_all() {
+            let grid = standard_grid.get_grid();
+            assert_eq!(parse_grid(&grid_to_string(&grid)), Ok(grid));
+        }
+    }
+
+    #[test]
+    fn parse_ignores_carriage_returns_and_trailing_lines() {
+        let grid = StandardGrid::Playground.get_grid();
+        let text = grid_to_string(&grid).replace('\n', "\r\n") + "\n\n";
+        assert_eq!(parse_grid(&text), Ok(grid));
+    }
+
+    #[test]
+    fn parse_invalid_character() {
+        let mut lines: Vec<String> = grid_to_string(&StandardGrid::Blank.get_grid())
+            .lines()
+            .map(|line| line.to_string())
+            .collect();
+        lines[3].replace_range(5..6, "x");
+
+        assert_eq!(
+            parse_grid(&lines.join("\n")),
+            Err(GridParseError {
+                line: 4,
+                column: 6,
+                kind: GridParseErrorKind::InvalidCharacter('x'),
+            })
+        );
+    }
+
+    #[test]
+    fn parse_small_grid() {
+        let grid = parse_grid("IIII\nIoeI\nIIII\n").unwrap();
+        assert_eq!(grid.width(), 4);
+        assert_eq!(grid.height(), 3);
+        assert_eq!(grid[1][1], GridValue::o);
+        assert_eq!(grid[2][1], GridValue::e);
+    }
+
+    #[test]
+    fn parse_wrong_line_length() {
+        let mut lines: Vec<String> = grid_to_string(&StandardGrid::Blank.get_grid())
+            .lines()
+            .map(|line| line.to_string())
+            .collect();
+        let width = lines[0].len();
+        lines[7].push('I');
+        lines[9].truncate(20);
+
+        assert_eq!(
+            parse_grid(&lines.join("\n")),
+            Err(GridParseError {
+                line: 8,
+                column: width + 1,
+                kind: GridParseErrorKind::WrongLineLength {
+                    expected: width,
+                    found: width + 1,
+                },
+            })
+        );
+
+        lines[7].pop();
+        assert_eq!(
+            parse_grid(&lines.join("\n")),
+            Err(GridParseError {
+                line: 10,
+                column: 21,
+                kind: GridParseErrorKind::WrongLineLength {
+                    expected: width,
+                    found: 20,
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn parse_wrong_size() {
+        assert_eq!(
+            parse_grid("\n\n"),
+            Err(GridParseError {
+                line: 1,
+                column: 1,
+                kind: GridParseErrorKind::Empty,
+            })
+        );
+
+        let wide = "I".repeat(MAX_GRID_SIZE + 1);
+        assert_eq!(
+            parse_grid(&wide),
+            Err(GridParseError {
+                line: 1,
+                column: MAX_GRID_SIZE + 1,
+                kind: GridParseErrorKind::TooWide {
+                    found: MAX_GRID_SIZE + 1
+                },
+            })
+        );
+
+        let tall = "III\n".repeat(MAX_GRID_SIZE + 1);
+        assert_eq!(
+            parse_grid(&tall),
+            Err(GridParseError {
+                line: MAX_GRID_SIZE + 1,
+                column: 1,
+                kind: GridParseErrorKind::TooTall {
+                    found: MAX_GRID_SIZE + 1
+                },
+            })
+        );
+    }
+}
@@ -0,0 +1,323 @@
+//! Mirrored, rotated and cropped copies of a [`Grid`].
+//!
+//! A [`GridTransform`] maps cells, directions and tunnels from one grid to another, so that
+//! anything placed on the original grid (like the agents in a
+//! [`PacmanAgentSetup`](crate::agent_setup::PacmanAgentSetup)) can be moved along with it.
+
+use crate::grid::{ComputedGrid, Direction, Grid, Tunnel};
+use anyhow::{anyhow, Error};
+use rapier2d::na::Point2;
+use serde::{Deserialize, Serialize};
+
+/// A way to rearrange the cells of a [`Grid`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum GridTransform {
+    /// Mirror the grid horizontally, swapping left and right
+    Mirror,
+    /// Rotate the grid by 180 degrees
+    Rotate180,
+    /// Keep only the cells in a rectangle, and surround them with a border of walls
+    ///
+    /// The cell at `(x, y)` in the original grid ends up at `(1, 1)`.
+    Crop {
+        /// The left column of the rectangle
+        x: u8,
+        /// The bottom row of the rectangle
+        y: u8,
+        /// The number of columns in the rectangle
+        width: u8,
+        /// The number of rows in the rectangle
+        height: u8,
+    },
+}
+
+impl GridTransform {
+    /// Returns the width and height of the transformed grid, or an error if the transform
+    /// doesn't fit the grid.
+    fn size(&self, grid: &Grid) -> Result<(usize, usize), Error> {
+        match *self {
+            Self::Mirror | Self::Rotate180 => Ok((grid.width(), grid.height())),
+            Self::Crop {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                if width == 0 || height == 0 {
+                    return Err(anyhow!("Crop rectangle is empty"));
+                }
+                if x as usize + width as usize > grid.width()
+                    || y as usize + height as usize > grid.height()
+                {
+                    return Err(anyhow!("Crop rectangle is outside the grid"));
+                }
+                Ok((width as usize + 2, height as usize + 2))
+            }
+        }
+    }
+
+    /// Returns where a cell of the given grid ends up after the transform, or `None` if it is
+    /// outside the grid or removed by a crop.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::transform::GridTransform;
+    /// use mdrc_pacbot_util::grid::Grid;
+    ///
+    /// let grid = Grid::new(10, 8);
+    /// let p = Point2::new(2, 3);
+    /// assert_eq!(GridTransform::Mirror.point(&grid, &p), Some(Point2::new(7, 3)));
+    /// assert_eq!(GridTransform::Rotate180.point(&grid, &p), Some(Point2::new(7, 4)));
+    ///
+    /// let crop = GridTransform::Crop { x: 2, y: 2, width: 3, height: 3 };
+    /// assert_eq!(crop.point(&grid, &p), Some(Point2::new(1, 2)));
+    /// assert_eq!(crop.point(&grid, &Point2::new(1, 3)), None);
+    /// ```
+    pub fn point(&self, grid: &Grid, p: &Point2<u8>) -> Option<Point2<u8>> {
+        let (width, height) = (grid.width() as u8, grid.height() as u8);
+        if p.x >= width || p.y >= height {
+            return None;
+        }
+        match *self {
+            Self::Mirror => Some(Point2::new(width - 1 - p.x, p.y)),
+            Self::Rotate180 => Some(Point2::new(width - 1 - p.x, height - 1 - p.y)),
+            Self::Crop {
+                x,
+                y,
+                width,
+                height,
+            } => {
+                let inside = (x..x.saturating_add(width)).contains(&p.x)
+                    && (y..y.saturating_add(height)).contains(&p.y);
+                inside.then(|| Point2::new(p.x - x + 1, p.y - y + 1))
+            }
+        }
+    }
+
+    /// Returns the direction that a direction becomes after the transform.
+    pub fn direction(&self, direction: Direction) -> Direction {
+        match (self, direction) {
+            (Self::Crop { .. }, d) => d,
+            (_, Direction::Right) => Direction::Left,
+            (_, Direction::Left) => Direction::Right,
+            (Self::Mirror, d) => d,
+            (_, Direction::Up) => Direction::Down,
+            (_, Direction::Down) => Direction::Up,
+        }
+    }
+
+    /// Returns where a cell and direction end up after the transform, or an error naming `what`
+    /// if the cell is removed.
+    pub(crate) fn place(
+        &self,
+        grid: &Grid,
+        (p, direction): &(Point2<u8>, Direction),
+        what: &str,
+    ) -> Result<(Point2<u8>, Direction), Error> {
+        let p = self
+            .point(grid, p)
+            .ok_or(anyhow!("{} is outside the transformed grid", what))?;
+        Ok((p, self.direction(*direction)))
+    }
+
+    /// Returns a transformed copy of a [`Grid`].
+    pub fn grid(&self, grid: &Grid) -> Result<Grid, Error> {
+        let (width, height) = self.size(grid)?;
+        let mut transformed = Grid::new(width, height);
+        for x in 0..grid.width() {
+            for y in 0..grid.height() {
+                if let Some(p) = self.point(grid, &Point2::new(x as u8, y as u8)) {
+                    transformed[p.x as usize][p.y as usize] = grid[x][y];
+                }
+            }
+        }
+        Ok(transformed)
+    }
+
+    /// Returns a transformed copy of a [`ComputedGrid`], including its tunnels.
+    ///
+    /// Cropping removes every tunnel, since its ends are no longer on the edges of the grid.
+    pub fn computed_grid(&self, grid: &ComputedGrid) -> Result<ComputedGrid, Error> {
+        let tunnels: Vec<Tunnel> = match self {
+            Self::Crop { .. } => vec![],
+            _ => grid
+                .tunnels()
+                .iter()
+                .filter_map(|(a, b)| {
+                    Some((self.point(grid.grid(), a)?, self.point(grid.grid(), b)?))
+                })
+                .collect(),
+        };
+        ComputedGrid::with_tunnels(self.grid(grid.grid())?, tunnels)
+    }
+}
+
+impl Grid {
+    /// Returns a copy of the [`Grid`] that is mirrored horizontally.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::grid::{Grid, GridValue};
+    ///
+    /// let mut grid = Grid::new(5, 4);
+    /// grid[1][2] = GridValue::o;
+    ///
+    /// let mirrored = grid.mirrored();
+    /// assert_eq!(mirrored[3][2], GridValue::o);
+    /// assert_eq!(mirrored.mirrored(), grid);
+    /// ```
+    pub fn mirrored(&self) -> Grid {
+        GridTransform::Mirror
+            .grid(self)
+            .expect("mirroring can't fail")
+    }
+
+    /// Returns a copy of the [`Grid`] that is rotated by 180 degrees.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::grid::{Grid, GridValue};
+    ///
+    /// let mut grid = Grid::new(5, 4);
+    /// grid[1][2] = GridValue::o;
+    ///
+    /// let rotated = grid.rotated_180();
+    /// assert_eq!(rotated[3][1], GridValue::o);
+    /// assert_eq!(rotated.rotated_180(), grid);
+    /// ```
+    pub fn rotated_180(&self) -> Grid {
+        GridTransform::Rotate180
+            .grid(self)
+            .expect("rotating can't fail")
+    }
+
+    /// Returns the cells in a rectangle of the [`Grid`], surrounded by a border of walls.
+    ///
+    /// The rectangle starts at `(x, y)` and must fit inside the grid.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::grid::{Grid, GridValue};
+    ///
+    /// let mut grid = Grid::new(8, 8);
+    /// grid[3][4] = GridValue::o;
+    ///
+    /// let cropped = grid.cropped(2, 2, 4, 3).unwrap();
+    /// assert_eq!((cropped.width(), cropped.height()), (6, 5));
+    /// assert_eq!(cropped[2][3], GridValue::o);
+    /// assert!(grid.cropped(6, 6, 4, 4).is_err());
+    /// ```
+    pub fn cropped(&self, x: u8, y: u8, width: u8, height: u8) -> Result<Grid, Error> {
+        GridTransform::Crop {
+            x,
+            y,
+            width,
+            height,
+        }
+        .grid(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::GridValue;
+    use crate::standard_grids::StandardGrid;
+
+    #[test]
+    fn transforms_preserve_computed_grids() {
+        let grid = StandardGrid::Pacman.compute_grid();
+        for transform in [GridTransform::Mirror, GridTransform::Rotate180] {
+            let transformed = transform.computed_grid(&grid).unwrap();
+            assert_eq!(
+                transformed.walkable_nodes().len(),
+                grid.walkable_nodes().len()
+            );
+            assert_eq!(transformed.pellet_count(), grid.pellet_count());
+
+            // distances are the same between the transformed cells
+            let map = |p| transform.point(grid.grid(), p).unwrap();
+            for p1 in grid.walkable_nodes().iter().step_by(11) {
+                for p2 in grid.walkable_nodes().iter().step_by(13) {
+                    assert_eq!(transformed.dist(&map(p1), &map(p2)), grid.dist(p1, p2));
+                }
+            }
+        }
+        // the standard grid is symmetric
+        assert_eq!(grid.grid().mirrored(), *grid.grid());
+    }
+
+    #[test]
+    fn tunnels_are_transformed() {
+        let mut grid = Grid::from(crate::standard_grids::GRID_BLANK);
+        for column in grid.columns_mut() {
+            column[5] = GridValue::e;
+        }
+        let tunnel = (Point2::new(0, 5), Point2::new(grid.width() as u8 - 1, 5));
+        let grid = ComputedGrid::with_tunnels(grid, vec![tunnel]).unwrap();
+
+        let rotated = GridTransform::Rotate180.computed_grid(&grid).unwrap();
+        let (width, height) = (grid.width() as u8, grid.height() as u8);
+        assert_eq!(
+            rotated.tunnels(),
+            &vec![(
+                Point2::new(width - 1, height - 6),
+                Point2::new(0, height - 6)
+            )]
+        );
+    }
+
+    #[test]
+    fn crop() {
+        let grid = StandardGrid::Pacman.compute_grid();
+        // cropping off the outer walls and padding them back on changes nothing
+        let (width, height) = (grid.width() as u8 - 2, grid.height() as u8 - 2);
+        assert_eq!(
+            grid.grid().cropped(1, 1, width, height).unwrap(),
+            *grid.grid()
+        );
+
+        // the bottom left corner of the maze
+        let crop = GridTransform::Crop {
+            x: 1,
+            y: 1,
+            width: 12,
+            height: 8,
+        };
+        let cropped = crop.computed_grid(&grid).unwrap();
+        assert_eq!((cropped.width(), cropped.height()), (14, 10));
+        assert_eq!(cropped.at(&Point2::new(1, 1)), grid.at(&Point2::new(1, 1)));
+        assert_eq!(crop.point(grid.grid(), &Point2::new(13, 1)), None);
+
+        assert!(grid.grid().cropped(1, 1, 0, 5).is_err());
+        assert!(grid.grid().cropped(20, 1, 10, 5).is_err());
+    }
+
+    #[test]
+    fn directions() {
+        let directions = [
+            Direction::Right,
+            Direction::Left,
+            Direction::Up,
+            Direction::Down,
+        ];
+        for transform in [GridTransform::Mirror, GridTransform::Rotate180] {
+            for d in directions {
+                assert_eq!(transform.direction(transform.direction(d)), d);
+            }
+        }
+        assert_eq!(
+            GridTransform::Mirror.direction(Direction::Up),
+            Direction::Up
+        );
+        assert_eq!(
+            GridTransform::Rotate180.direction(Direction::Up),
+            Direction::Down
+        );
+    }
+}
@@ -0,0 +1,453 @@
+//! Checks that a [`Grid`] follows the rules required by [`ComputedGrid`](crate::grid::ComputedGrid).
+//!
+//! A valid [`Grid`] must satisfy the following conditions:
+//! - The width and height must be between 3 and [`MAX_GRID_SIZE`].
+//! - The edges of the grid must all be walls, except for the ends of tunnels.
+//! - Each end of a tunnel must be walkable, on exactly one edge of the grid, and used only once.
+//! - There must be no 2x2 walkable squares.
+//! - There must be at least one walkable space.
+//! - No wall should have a walkable cell either both above and below or both to the left and right.
+//!
+//! [`ComputedGrid`](crate::grid::ComputedGrid) also rejects grids where two cells are more than
+//! [`u8::MAX`] steps apart, since it stores distances as `u8`s. This is only found while computing
+//! distances, so it is not checked here.
+
+use crate::grid::{tunnel_exit_direction, Direction, Grid, GridValue, Tunnel, MAX_GRID_SIZE};
+use rapier2d::na::Point2;
+use std::fmt::{Display, Formatter};
+
+/// A rule that a [`Grid`] breaks.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GridValidationError {
+    /// The number of cells does not match the width and height of the [`Grid`]
+    WrongCellCount {
+        /// `width * height`
+        expected: usize,
+        /// The number of cells found
+        found: usize,
+    },
+    /// The width or height is less than 3 or more than [`MAX_GRID_SIZE`]
+    InvalidSize {
+        /// The width of the [`Grid`]
+        width: usize,
+        /// The height of the [`Grid`]
+        height: usize,
+    },
+    /// A tunnel end is not on exactly one edge of the [`Grid`]
+    TunnelEndNotOnEdge(Point2<u8>),
+    /// A tunnel end is not walkable
+    TunnelEndNotWalkable(Point2<u8>),
+    /// A cell is the end of more than one tunnel
+    TunnelEndReused(Point2<u8>),
+    /// A cell on the edge of the [`Grid`] is not a wall or a tunnel end
+    EdgeNotWall {
+        /// The edge, as the direction that leaves the [`Grid`] from it
+        edge: Direction,
+        /// The cell that is not a wall
+        position: Point2<u8>,
+    },
+    /// A 2x2 square of cells is walkable; the position is its bottom left cell
+    OpenSquare(Point2<u8>),
+    /// No cell is walkable
+    NoWalkableSpace,
+    /// A wall has walkable cells both to its left and right
+    ThinVerticalWall(Point2<u8>),
+    /// A wall has walkable cells both above and below it
+    ThinHorizontalWall(Point2<u8>),
+    /// Two cells are more than [`u8::MAX`] steps apart
+    PathTooLong {
+        /// The cell the path starts at
+        from: Point2<u8>,
+        /// The cell the path ends at
+        to: Point2<u8>,
+    },
+}
+
+impl Display for GridValidationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::WrongCellCount { expected, found } => {
+                write!(
+                    f,
+                    "Grid has {} cells, but its size needs {}",
+                    found, expected
+                )
+            }
+            Self::InvalidSize { width, height } => write!(
+                f,
+                "Grid size {}x{} is not between 3x3 and {}x{}",
+                width, height, MAX_GRID_SIZE, MAX_GRID_SIZE
+            ),
+            Self::TunnelEndNotOnEdge(p) => write!(
+                f,
+                "Tunnel end ({}, {}) is not on exactly one edge of the grid",
+                p.x, p.y
+            ),
+            Self::TunnelEndNotWalkable(p) => {
+                write!(f, "Tunnel end ({}, {}) is not walkable", p.x, p.y)
+            }
+            Self::TunnelEndReused(p) => {
+                write!(f, "Tunnel end ({}, {}) is used more than once", p.x, p.y)
+            }
+            Self::EdgeNotWall { edge, position } => {
+                let edge = match edge {
+                    Direction::Right => "Right",
+                    Direction::Left => "Left",
+                    Direction::Up => "Top",
+                    Direction::Down => "Bottom",
+                };
+                write!(
+                    f,
+                    "{} edge of grid is not a wall at ({}, {})",
+                    edge, position.x, position.y
+                )
+            }
+            Self::OpenSquare(p) => write!(f, "2x2 walkable square at ({}, {})", p.x, p.y),
+            Self::NoWalkableSpace => write!(f, "No walkable spaces"),
+            Self::ThinVerticalWall(p) => write!(
+                f,
+                "Wall at ({}, {}) has walkable cells both to the left and right",
+                p.x, p.y
+            ),
+            Self::ThinHorizontalWall(p) => write!(
+                f,
+                "Wall at ({}, {}) has walkable cells both above and below",
+                p.x, p.y
+            ),
+            Self::PathTooLong { from, to } => write!(
+                f,
+                "Path from ({}, {}) to ({}, {}) is longer than {} steps",
+                from.x,
+                from.y,
+                to.x,
+                to.y,
+                u8::MAX
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GridValidationError {}
+
+/// Validates a [`Grid`], returning the first rule it breaks.
+///
+/// # Examples
+///
+/// ```
+/// use rapier2d::na::Point2;
+/// use mdrc_pacbot_util::grid::validation::{validate_grid, GridValidationError};
+/// use mdrc_pacbot_util::grid::{Grid, GridValue};
+/// use mdrc_pacbot_util::standard_grids::GRID_BLANK;
+///
+/// let mut grid = Grid::from(GRID_BLANK);
+/// assert_eq!(validate_grid(&grid, &[]), Ok(()));
+///
+/// grid[1][1] = GridValue::I;
+/// assert_eq!(validate_grid(&grid, &[]), Err(GridValidationError::NoWalkableSpace));
+/// ```
+pub fn validate_grid(grid: &Grid, tunnels: &[Tunnel]) -> Result<(), GridValidationError> {
+    let mut errors = vec![];
+    check_grid(grid, tunnels, true, &mut errors);
+    match errors.into_iter().next() {
+        Some(error) => Err(error),
+        None => Ok(()),
+    }
+}
+
+/// Validates a [`Grid`], returning every rule it breaks.
+///
+/// If the size of the [`Grid`] is invalid, that is the only error reported, since the other rules
+/// can't be checked.
+///
+/// # Examples
+///
+/// ```
+/// use rapier2d::na::Point2;
+/// use mdrc_pacbot_util::grid::validation::{validate_grid_all, GridValidationError};
+/// use mdrc_pacbot_util::grid::{Direction, Grid, GridValue};
+/// use mdrc_pacbot_util::standard_grids::GRID_BLANK;
+///
+/// let mut grid = Grid::from(GRID_BLANK);
+/// grid[0][1] = GridValue::e;
+/// grid[5][0] = GridValue::e;
+///
+/// assert_eq!(
+///     validate_grid_all(&grid, &[]),
+///     vec![
+///         GridValidationError::EdgeNotWall {
+///             edge: Direction::Left,
+///             position: Point2::new(0, 1),
+///         },
+///         GridValidationError::EdgeNotWall {
+///             edge: Direction::Down,
+///             position: Point2::new(5, 0),
+///         },
+///     ]
+/// );
+/// ```
+pub fn validate_grid_all(grid: &Grid, tunnels: &[Tunnel]) -> Vec<GridValidationError> {
+    let mut errors = vec![];
+    check_grid(grid, tunnels, false, &mut errors);
+    errors
+}
+
+/// Adds the rules that the [`Grid`] breaks to `errors`, stopping after the first one if
+/// `stop_at_first` is set
+fn check_grid(
+    grid: &Grid,
+    tunnels: &[Tunnel],
+    stop_at_first: bool,
+    errors: &mut Vec<GridValidationError>,
+) {
+    // returns from check_grid if it should stop after this error
+    macro_rules! report {
+        ($error:expr) => {
+            errors.push($error);
+            if stop_at_first {
+                return;
+            }
+        };
+    }
+
+    // the other checks can't index into a grid with the wrong size
+    if grid.cells.len() != grid.width * grid.height {
+        errors.push(GridValidationError::WrongCellCount {
+            expected: grid.width * grid.height,
+            found: grid.cells.len(),
+        });
+        return;
+    }
+    if !(3..=MAX_GRID_SIZE).contains(&grid.width) || !(3..=MAX_GRID_SIZE).contains(&grid.height) {
+        errors.push(GridValidationError::InvalidSize {
+            width: grid.width,
+            height: grid.height,
+        });
+        return;
+    }
+
+    let tunnel_ends: Vec<Point2<u8>> = tunnels.iter().flat_map(|&(a, b)| [a, b]).collect();
+    for (i, end) in tunnel_ends.iter().enumerate() {
+        if tunnel_exit_direction(grid, end).is_none() {
+            report!(GridValidationError::TunnelEndNotOnEdge(*end));
+            continue;
+        }
+        if !grid[end.x as usize][end.y as usize].walkable() {
+            report!(GridValidationError::TunnelEndNotWalkable(*end));
+        }
+        if tunnel_ends[..i].contains(end) {
+            report!(GridValidationError::TunnelEndReused(*end));
+        }
+    }
+
+    // the edges of the grid should all be walls
+    let (width, height) = (grid.width, grid.height);
+    let edges = [
+        (
+            Direction::Left,
+            (0..height).map(|y| (0, y)).collect::<Vec<_>>(),
+        ),
+        (
+            Direction::Right,
+            (0..height).map(|y| (width - 1, y)).collect(),
+        ),
+        (Direction::Down, (0..width).map(|x| (x, 0)).collect()),
+        (Direction::Up, (0..width).map(|x| (x, height - 1)).collect()),
+    ];
+    for (edge, cells) in edges {
+        for (x, y) in cells {
+            let position = Point2::new(x as u8, y as u8);
+            if grid[x][y] != GridValue::I && !tunnel_ends.contains(&position) {
+                report!(GridValidationError::EdgeNotWall { edge, position });
+            }
+        }
+    }
+
+    // there should be no 2x2 walkable squares
+    for x in 0..width - 1 {
+        for y in 0..height - 1 {
+            if grid[x][y].walkable()
+                && grid[x][y + 1].walkable()
+                && grid[x + 1][y].walkable()
+                && grid[x + 1][y + 1].walkable()
+            {
+                report!(GridValidationError::OpenSquare(Point2::new(
+                    x as u8, y as u8
+                )));
+            }
+        }
+    }
+
+    // there should be at least one walkable space
+    if !grid.cells.iter().any(|cell| cell.walkable()) {
+        report!(GridValidationError::NoWalkableSpace);
+    }
+
+    // no wall should have a walkable cell either both above and below or both to the left and right
+    for x in 1..width - 1 {
+        for y in 1..height - 1 {
+            if grid[x][y] == GridValue::I {
+                let position = Point2::new(x as u8, y as u8);
+                if grid[x - 1][y].walkable() && grid[x + 1][y].walkable() {
+                    report!(GridValidationError::ThinVerticalWall(position));
+                }
+                if grid[x][y - 1].walkable() && grid[x][y + 1].walkable() {
+                    report!(GridValidationError::ThinHorizontalWall(position));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::GridValue::{e as EMPTY, I as WALL};
+    use crate::standard_grids::*;
+
+    #[test]
+    fn valid_preset_grids() {
+        for standard_grid in StandardGrid::get_all() {
+            assert_eq!(validate_grid(&standard_grid.get_grid(), &[]), Ok(()));
+            assert!(validate_grid_all(&standard_grid.get_grid(), &[]).is_empty());
+        }
+    }
+
+    #[test]
+    fn validation_require_empty_space() {
+        let mut grid = Grid::from(GRID_BLANK);
+        grid[1][1] = WALL;
+
+        assert_eq!(
+            validate_grid(&grid, &[]),
+            Err(GridValidationError::NoWalkableSpace)
+        );
+    }
+
+    #[test]
+    fn validation_invalid_edges() {
+        for (x, y, edge) in [
+            (1, 0, Direction::Down),
+            (1, 31, Direction::Up),
+            (0, 1, Direction::Left),
+            (31, 1, Direction::Right),
+        ] {
+            let mut grid = Grid::from(GRID_BLANK);
+            grid[x][y] = EMPTY;
+
+            assert_eq!(
+                validate_grid(&grid, &[]),
+                Err(GridValidationError::EdgeNotWall {
+                    edge,
+                    position: Point2::new(x as u8, y as u8),
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn validation_invalid_size() {
+        let mut grid = Grid::new(MAX_GRID_SIZE + 1, 5);
+        grid[1][1] = EMPTY;
+        assert_eq!(
+            validate_grid_all(&grid, &[]),
+            vec![GridValidationError::InvalidSize {
+                width: MAX_GRID_SIZE + 1,
+                height: 5
+            }]
+        );
+
+        let grid = Grid::new(2, 2);
+        assert!(validate_grid(&grid, &[]).is_err());
+    }
+
+    #[test]
+    fn validation_invalid_2x2() {
+        let mut grid = Grid::from(GRID_BLANK);
+        grid[1][1] = EMPTY;
+        grid[1][2] = EMPTY;
+        grid[2][1] = EMPTY;
+        grid[2][2] = EMPTY;
+
+        let v = validate_grid(&grid, &[]);
+        assert_eq!(v, Err(GridValidationError::OpenSquare(Point2::new(1, 1))));
+        assert_eq!(v.unwrap_err().to_string(), "2x2 walkable square at (1, 1)");
+    }
+
+    #[test]
+    fn validation_thin_walls() {
+        let mut grid = Grid::from(GRID_BLANK);
+        grid[3][1] = EMPTY;
+        grid[1][3] = EMPTY;
+
+        assert_eq!(
+            validate_grid_all(&grid, &[]),
+            vec![
+                GridValidationError::ThinHorizontalWall(Point2::new(1, 2)),
+                GridValidationError::ThinVerticalWall(Point2::new(2, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn validation_collects_all_errors() {
+        let mut grid = Grid::from(GRID_BLANK);
+        grid[0][1] = EMPTY;
+        grid[0][2] = EMPTY;
+        for x in 4..6 {
+            for y in 4..6 {
+                grid[x][y] = EMPTY;
+            }
+        }
+
+        let errors = validate_grid_all(&grid, &[]);
+        assert_eq!(
+            errors,
+            vec![
+                GridValidationError::EdgeNotWall {
+                    edge: Direction::Left,
+                    position: Point2::new(0, 1),
+                },
+                GridValidationError::EdgeNotWall {
+                    edge: Direction::Left,
+                    position: Point2::new(0, 2),
+                },
+                GridValidationError::OpenSquare(Point2::new(4, 4)),
+            ]
+        );
+        assert_eq!(validate_grid(&grid, &[]), Err(errors[0]));
+    }
+
+    #[test]
+    fn validation_tunnel_allows_edge() {
+        let (grid, tunnel) = tunnel_grid();
+
+        assert!(validate_grid(&grid, &[]).is_err());
+        assert_eq!(validate_grid(&grid, &[tunnel]), Ok(()));
+    }
+
+    #[test]
+    fn validation_invalid_tunnel_end() {
+        let (grid, tunnel) = tunnel_grid();
+
+        for (end, error) in [
+            (
+                Point2::new(5, 5),
+                GridValidationError::TunnelEndNotOnEdge(Point2::new(5, 5)),
+            ),
+            (
+                Point2::new(0, 0),
+                GridValidationError::TunnelEndNotOnEdge(Point2::new(0, 0)),
+            ),
+            (
+                Point2::new(0, 7),
+                GridValidationError::TunnelEndNotWalkable(Point2::new(0, 7)),
+            ),
+            (
+                Point2::new(0, 5),
+                GridValidationError::TunnelEndReused(Point2::new(0, 5)),
+            ),
+        ] {
+            assert_eq!(validate_grid(&grid, &[tunnel, (end, tunnel.1)]), Err(error));
+        }
+    }
+}
@@ -0,0 +1,584 @@
+//! Rectangle decomposition of the walls in a [`ComputedGrid`], and queries about walls in
+//! continuous space.
+//!
+//! Walls are eroded by half a cell, so a unit square between four cell centers is solid when none
+//! of the four cells are walkable. Square `(x, y)` covers `x..x + 1` and `y..y + 1`, and the
+//! squares from `(-1, -1)` to `(width - 1, height - 1)` cover the whole [`Grid`](crate::grid::Grid).
+//! Everything outside of that area is also considered solid.
+
+use crate::grid::{ComputedGrid, Wall};
+use rapier2d::na::Point2;
+
+impl ComputedGrid {
+    /// Returns whether the unit square with the given bottom left corner is solid.
+    fn is_wall_square(&self, x: i32, y: i32) -> bool {
+        [(x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1)]
+            .iter()
+            .all(|&(x, y)| {
+                x < 0
+                    || y < 0
+                    || self
+                        .at(&Point2::new(x as u8, y as u8))
+                        .is_none_or(|v| !v.walkable())
+            })
+    }
+
+    /// Splits the solid squares of the grid into the fewest possible non-overlapping [`Wall`]s.
+    pub(super) fn compute_walls(&self) -> Vec<Wall> {
+        let solid: Vec<Vec<bool>> = (-1..self.width() as i32)
+            .map(|x| {
+                (-1..self.height() as i32)
+                    .map(|y| self.is_wall_square(x, y))
+                    .collect()
+            })
+            .collect();
+        partition_rectangles(&solid)
+            .into_iter()
+            .map(|(x, y, w, h)| Wall {
+                left_bottom: Point2::new(x as f32 - 1.0, y as f32 - 1.0),
+                right_top: Point2::new((x + w) as f32 - 1.0, (y + h) as f32 - 1.0),
+            })
+            .collect()
+    }
+
+    /// Returns whether a point in world coordinates is inside a wall.
+    ///
+    /// Points on the bottom or left edge of a wall are inside it, and points outside the grid are
+    /// always inside a wall.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// assert!(!grid.is_in_wall(&Point2::new(1.0, 1.0)));
+    /// assert!(!grid.is_in_wall(&Point2::new(1.0, 1.5)));
+    /// assert!(!grid.is_in_wall(&Point2::new(1.5, 1.5)));
+    /// assert!(grid.is_in_wall(&Point2::new(2.5, 2.5)));
+    /// assert!(grid.is_in_wall(&Point2::new(-5.0, 1.0)));
+    /// ```
+    pub fn is_in_wall(&self, p: &Point2<f32>) -> bool {
+        let (x, y) = (p.x.floor(), p.y.floor());
+        if !(-1.0..self.width() as f32).contains(&x) || !(-1.0..self.height() as f32).contains(&y) {
+            return true;
+        }
+        self.is_wall_square(x as i32, y as i32)
+    }
+
+    /// Returns the distance from a point in world coordinates to the nearest wall, or 0 if the
+    /// point is inside a wall.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// assert_eq!(grid.distance_to_wall(&Point2::new(1.0, 1.0)), 1.0);
+    /// assert_eq!(grid.distance_to_wall(&Point2::new(0.75, 1.0)), 0.75);
+    /// assert_eq!(grid.distance_to_wall(&Point2::new(2.5, 2.5)), 0.0);
+    /// ```
+    pub fn distance_to_wall(&self, p: &Point2<f32>) -> f32 {
+        if self.is_in_wall(p) {
+            return 0.0;
+        }
+        self.distance_to_squares(p, true)
+    }
+
+    /// Returns the distance from a point in world coordinates to the edge of the nearest wall,
+    /// which is negative if the point is inside a wall.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// assert_eq!(grid.signed_distance_to_wall(&Point2::new(0.75, 1.0)), 0.75);
+    /// assert_eq!(grid.signed_distance_to_wall(&Point2::new(-0.5, 1.0)), -0.5);
+    /// ```
+    pub fn signed_distance_to_wall(&self, p: &Point2<f32>) -> f32 {
+        if self.is_in_wall(p) {
+            -self.distance_to_squares(p, false)
+        } else {
+            self.distance_to_squares(p, true)
+        }
+    }
+
+    /// Returns the distance from a point to the nearest square that is solid or not, not counting
+    /// the square that contains the point.
+    fn distance_to_squares(&self, p: &Point2<f32>, solid: bool) -> f32 {
+        let (cx, cy) = (p.x.floor() as i32, p.y.floor() as i32);
+        let (width, height) = (self.width() as i32, self.height() as i32);
+        let is_solid = |x: i32, y: i32| {
+            x < -1 || y < -1 || x >= width || y >= height || self.is_wall_square(x, y)
+        };
+        let square_distance = |x: i32, y: i32| {
+            let dx = (x as f32 - p.x).max(p.x - (x + 1) as f32).max(0.0);
+            let dy = (y as f32 - p.y).max(p.y - (y + 1) as f32).max(0.0);
+            (dx * dx + dy * dy).sqrt()
+        };
+
+        // search rings of squares around the point; squares in ring k are at least k - 1 away
+        let limit = cx.abs() + cy.abs() + width + height;
+        let mut best = f32::INFINITY;
+        let mut k = 1;
+        while best > (k - 1) as f32 && k <= limit {
+            for i in -k..=k {
+                for (x, y) in [
+                    (cx + i, cy - k),
+                    (cx + i, cy + k),
+                    (cx - k, cy + i),
+                    (cx + k, cy + i),
+                ] {
+                    if is_solid(x, y) == solid {
+                        best = best.min(square_distance(x, y));
+                    }
+                }
+            }
+            k += 1;
+        }
+        best
+    }
+
+    /// Returns the distance a ray travels from a point in world coordinates before it hits a wall,
+    /// up to `max_range`.
+    ///
+    /// The angle is in radians, counter-clockwise from the `+x` direction. A ray that starts inside
+    /// a wall has length 0.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::f32::consts::PI;
+    /// use rapier2d::na::Point2;
+    /// use mdrc_pacbot_util::grid::ComputedGrid;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let grid = StandardGrid::Pacman.compute_grid();
+    /// assert_eq!(grid.cast_ray(&Point2::new(1.0, 1.0), PI, 10.0), 1.0);
+    /// assert_eq!(grid.cast_ray(&Point2::new(1.0, 1.0), 0.0, 10.0), 10.0);
+    /// assert_eq!(grid.cast_ray(&Point2::new(1.0, 1.0), 0.0, 100.0), 26.0);
+    /// ```
+    pub fn cast_ray(&self, origin: &Point2<f32>, angle: f32, max_range: f32) -> f32 {
+        if self.is_in_wall(origin) {
+            return 0.0;
+        }
+        let (dx, dy) = (angle.cos(), angle.sin());
+        let (mut x, mut y) = (origin.x.floor(), origin.y.floor());
+        let (step_x, step_y) = (dx.signum(), dy.signum());
+
+        // the distance along the ray to the next vertical and horizontal grid line
+        let next_line = |p: f32, square: f32, d: f32| {
+            if d.abs() < f32::EPSILON {
+                f32::INFINITY
+            } else if d > 0.0 {
+                (square + 1.0 - p) / d
+            } else {
+                (square - p) / d
+            }
+        };
+        let mut t_x = next_line(origin.x, x, dx);
+        let mut t_y = next_line(origin.y, y, dy);
+        let (delta_x, delta_y) = (1.0 / dx.abs(), 1.0 / dy.abs());
+
+        loop {
+            let t = if t_x < t_y {
+                x += step_x;
+                t_x += delta_x;
+                t_x - delta_x
+            } else {
+                y += step_y;
+                t_y += delta_y;
+                t_y - delta_y
+            };
+            if t >= max_range {
+                return max_range;
+            }
+            if self.is_in_wall(&Point2::new(x + 0.5, y + 0.5)) {
+                return t;
+            }
+        }
+    }
+}
+
+/// A cut between squares, from one concave corner to another
+#[derive(Clone, Copy, Debug)]
+struct Chord {
+    /// the fixed coordinate of the line the chord is on
+    line: usize,
+    /// the first and last points along the line
+    start: usize,
+    end: usize,
+}
+
+/// Partitions the `true` squares into the minimum number of rectangles, returned as
+/// `(x, y, width, height)` in square coordinates.
+///
+/// This is the classic construction for rectilinear polygons: the number of rectangles is
+/// minimized by cutting along as many non-intersecting chords between two concave corners as
+/// possible, which is a maximum independent set in the bipartite graph of horizontal and vertical
+/// chords. Every remaining concave corner is then resolved with a single cut that extends until it
+/// hits a wall or another cut.
+fn partition_rectangles(solid: &[Vec<bool>]) -> Vec<(usize, usize, usize, usize)> {
+    let width = solid.len();
+    let height = solid.first().map_or(0, |c| c.len());
+    let square = |x: isize, y: isize| {
+        x >= 0
+            && y >= 0
+            && (x as usize) < width
+            && (y as usize) < height
+            && solid[x as usize][y as usize]
+    };
+
+    // concave corners, with the direction to cut from them horizontally and vertically
+    let mut corners = vec![];
+    for x in 0..=width as isize {
+        for y in 0..=height as isize {
+            let around = [
+                square(x - 1, y - 1),
+                square(x, y - 1),
+                square(x - 1, y),
+                square(x, y),
+            ];
+            if around.iter().filter(|s| **s).count() != 3 {
+                continue;
+            }
+            // cut away from the empty square
+            let empty = around.iter().position(|s| !s).unwrap();
+            let dx = if empty % 2 == 0 { 1 } else { -1 };
+            let dy = if empty < 2 { 1 } else { -1 };
+            corners.push((x, y, dx, dy));
+        }
+    }
+
+    // whether the unit edge from a point along a line has solid squares on both sides
+    let interior_horizontal = |x: isize, y: isize| square(x, y - 1) && square(x, y);
+    let interior_vertical = |x: isize, y: isize| square(x - 1, y) && square(x, y);
+
+    // chords between pairs of corners that face each other
+    let mut horizontal = vec![];
+    let mut vertical = vec![];
+    for &(x, y, dx, dy) in &corners {
+        if dx == 1 {
+            let mut end = x;
+            while interior_horizontal(end, y) {
+                end += 1;
+            }
+            if corners.contains(&(end, y, -1, 1)) || corners.contains(&(end, y, -1, -1)) {
+                horizontal.push(Chord {
+                    line: y as usize,
+                    start: x as usize,
+                    end: end as usize,
+                });
+            }
+        }
+        if dy == 1 {
+            let mut end = y;
+            while interior_vertical(x, end) {
+                end += 1;
+            }
+            if corners.contains(&(x, end, 1, -1)) || corners.contains(&(x, end, -1, -1)) {
+                vertical.push(Chord {
+                    line: x as usize,
+                    start: y as usize,
+                    end: end as usize,
+                });
+            }
+        }
+    }
+
+    // maximum matching between intersecting horizontal and vertical chords
+    let intersects = |h: &Chord, v: &Chord| {
+        (h.start..=h.end).contains(&v.line) && (v.start..=v.end).contains(&h.line)
+    };
+    let conflicts: Vec<Vec<usize>> = horizontal
+        .iter()
+        .map(|h| {
+            (0..vertical.len())
+                .filter(|&v| intersects(h, &vertical[v]))
+                .collect()
+        })
+        .collect();
+    let mut matched_horizontal = vec![None; horizontal.len()];
+    let mut matched_vertical = vec![None; vertical.len()];
+    fn augment(
+        h: usize,
+        conflicts: &[Vec<usize>],
+        visited: &mut [bool],
+        matched_horizontal: &mut [Option<usize>],
+        matched_vertical: &mut [Option<usize>],
+    ) -> bool {
+        for &v in &conflicts[h] {
+            if visited[v] {
+                continue;
+            }
+            visited[v] = true;
+            let free = match matched_vertical[v] {
+                None => true,
+                Some(other) => augment(
+                    other,
+                    conflicts,
+                    visited,
+                    matched_horizontal,
+                    matched_vertical,
+                ),
+            };
+            if free {
+                matched_horizontal[h] = Some(v);
+                matched_vertical[v] = Some(h);
+                return true;
+            }
+        }
+        false
+    }
+    for h in 0..horizontal.len() {
+        let mut visited = vec![false; vertical.len()];
+        augment(
+            h,
+            &conflicts,
+            &mut visited,
+            &mut matched_horizontal,
+            &mut matched_vertical,
+        );
+    }
+
+    // by Konig's theorem, the chords reachable by alternating paths from unmatched horizontal
+    // chords give a maximum independent set: reachable horizontal and unreachable vertical chords
+    let mut reached_horizontal = vec![false; horizontal.len()];
+    let mut reached_vertical = vec![false; vertical.len()];
+    let mut stack: Vec<usize> = (0..horizontal.len())
+        .filter(|&h| matched_horizontal[h].is_none())
+        .collect();
+    while let Some(h) = stack.pop() {
+        if reached_horizontal[h] {
+            continue;
+        }
+        reached_horizontal[h] = true;
+        for &v in &conflicts[h] {
+            if !reached_vertical[v] {
+                reached_vertical[v] = true;
+                if let Some(next) = matched_vertical[v] {
+                    stack.push(next);
+                }
+            }
+        }
+    }
+
+    // cuts[x][y] along the edge starting at point (x, y), horizontally and vertically
+    let mut horizontal_cuts = vec![vec![false; height + 1]; width + 1];
+    let mut vertical_cuts = vec![vec![false; height + 1]; width + 1];
+    for (h, chord) in horizontal.iter().enumerate() {
+        if reached_horizontal[h] {
+            for column in &mut horizontal_cuts[chord.start..chord.end] {
+                column[chord.line] = true;
+            }
+        }
+    }
+    for (v, chord) in vertical.iter().enumerate() {
+        if !reached_vertical[v] {
+            vertical_cuts[chord.line][chord.start..chord.end].fill(true);
+        }
+    }
+
+    // every remaining concave corner gets a vertical cut that stops at the first obstacle
+    for &(x, y, dx, dy) in &corners {
+        let horizontal_edge = if dx == 1 { x } else { x - 1 } as usize;
+        let vertical_edge = if dy == 1 { y } else { y - 1 } as usize;
+        if horizontal_cuts[horizontal_edge][y as usize] || vertical_cuts[x as usize][vertical_edge]
+        {
+            continue;
+        }
+        let mut y = y;
+        loop {
+            let edge = if dy == 1 { y } else { y - 1 };
+            if !interior_vertical(x, edge) || vertical_cuts[x as usize][edge as usize] {
+                break;
+            }
+            vertical_cuts[x as usize][edge as usize] = true;
+            y += dy;
+            let (ux, uy) = (x as usize, y as usize);
+            if horizontal_cuts[ux][uy] || (ux > 0 && horizontal_cuts[ux - 1][uy]) {
+                break;
+            }
+        }
+    }
+
+    // the squares connected without crossing a cut form the rectangles
+    let mut rectangles = vec![];
+    let mut visited = vec![vec![false; height]; width];
+    for x in 0..width {
+        for y in 0..height {
+            if !solid[x][y] || visited[x][y] {
+                continue;
+            }
+            let (mut max_x, mut max_y) = (x, y);
+            let mut stack = vec![(x, y)];
+            visited[x][y] = true;
+            while let Some((sx, sy)) = stack.pop() {
+                max_x = max_x.max(sx);
+                max_y = max_y.max(sy);
+                let mut next = vec![];
+                if sx + 1 < width && !vertical_cuts[sx + 1][sy] {
+                    next.push((sx + 1, sy));
+                }
+                if sx > 0 && !vertical_cuts[sx][sy] {
+                    next.push((sx - 1, sy));
+                }
+                if sy + 1 < height && !horizontal_cuts[sx][sy + 1] {
+                    next.push((sx, sy + 1));
+                }
+                if sy > 0 && !horizontal_cuts[sx][sy] {
+                    next.push((sx, sy - 1));
+                }
+                for (nx, ny) in next {
+                    if solid[nx][ny] && !visited[nx][ny] {
+                        visited[nx][ny] = true;
+                        stack.push((nx, ny));
+                    }
+                }
+            }
+            // scanning in order means (x, y) is the bottom left corner
+            rectangles.push((x, y, max_x - x + 1, max_y - y + 1));
+        }
+    }
+    rectangles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::partition_rectangles;
+    use crate::grid::generator::{generate_maze, MazeOptions};
+    use crate::grid::ComputedGrid;
+    use crate::standard_grids::StandardGrid;
+    use rapier2d::na::Point2;
+
+    #[test]
+    fn walls_cover_solid_squares_once() {
+        for standard_grid in StandardGrid::get_all() {
+            let grid = standard_grid.compute_grid();
+            for x in -1..grid.width() as i32 {
+                for y in -1..grid.height() as i32 {
+                    let center = Point2::new(x as f32 + 0.5, y as f32 + 0.5);
+                    let covering = grid
+                        .walls()
+                        .iter()
+                        .filter(|w| {
+                            w.left_bottom.x <= center.x
+                                && w.left_bottom.y <= center.y
+                                && center.x < w.right_top.x
+                                && center.y < w.right_top.y
+                        })
+                        .count();
+                    let expected = if grid.is_in_wall(&center) { 1 } else { 0 };
+                    assert_eq!(covering, expected, "{:?} at ({}, {})", standard_grid, x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn wall_counts() {
+        // the previous row-by-row merge produced 42 and 55 walls
+        assert_eq!(StandardGrid::Pacman.compute_grid().walls().len(), 38);
+        assert_eq!(StandardGrid::Playground.compute_grid().walls().len(), 51);
+        assert_eq!(StandardGrid::Outer.compute_grid().walls().len(), 5);
+        assert_eq!(StandardGrid::Blank.compute_grid().walls().len(), 4);
+    }
+
+    fn parse_squares(rows: &[&str]) -> Vec<Vec<bool>> {
+        let height = rows.len();
+        (0..rows[0].len())
+            .map(|x| {
+                (0..height)
+                    .map(|y| rows[height - 1 - y].as_bytes()[x] == b'#')
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn minimum_partitions() {
+        let l_shape = parse_squares(&["#..", "#..", "###"]);
+        assert_eq!(partition_rectangles(&l_shape).len(), 2);
+
+        let plus = parse_squares(&[".#.", "###", ".#."]);
+        assert_eq!(partition_rectangles(&plus).len(), 3);
+
+        let ring = parse_squares(&["####", "#..#", "#..#", "####"]);
+        assert_eq!(partition_rectangles(&ring).len(), 4);
+
+        // taking the long bar first leaves six stubs, but cutting along the columns gives five
+        let comb = parse_squares(&["#.#.#", "#####", "#.#.#"]);
+        assert_eq!(partition_rectangles(&comb).len(), 5);
+
+        let pinched = parse_squares(&["##..", "##..", "..##", "..##"]);
+        assert_eq!(
+            partition_rectangles(&pinched),
+            vec![(0, 2, 2, 2), (2, 0, 2, 2)]
+        );
+    }
+
+    #[test]
+    fn generated_maze_walls() {
+        for seed in 0..5 {
+            let options = MazeOptions {
+                seed,
+                ..Default::default()
+            };
+            let grid = ComputedGrid::try_from(generate_maze(&options).unwrap()).unwrap();
+            let area: f32 = grid
+                .walls()
+                .iter()
+                .map(|w| (w.right_top.x - w.left_bottom.x) * (w.right_top.y - w.left_bottom.y))
+                .sum();
+            let solid = (-1..grid.width() as i32)
+                .flat_map(|x| (-1..grid.height() as i32).map(move |y| (x, y)))
+                .filter(|&(x, y)| grid.is_in_wall(&Point2::new(x as f32 + 0.5, y as f32 + 0.5)))
+                .count();
+            assert_eq!(area as usize, solid);
+        }
+    }
+
+    fn brute_force_distance(grid: &ComputedGrid, p: &Point2<f32>) -> f32 {
+        grid.walls()
+            .iter()
+            .map(|w| {
+                let dx = (w.left_bottom.x - p.x).max(p.x - w.right_top.x).max(0.0);
+                let dy = (w.left_bottom.y - p.y).max(p.y - w.right_top.y).max(0.0);
+                (dx * dx + dy * dy).sqrt()
+            })
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    #[test]
+    fn distance_to_wall_matches_walls() {
+        let grid = StandardGrid::Pacman.compute_grid();
+        for x in 0..grid.width() * 4 {
+            for y in 0..grid.height() * 4 {
+                let p = Point2::new(x as f32 / 4.0 + 0.1, y as f32 / 4.0 + 0.1);
+                if grid.is_in_wall(&p) {
+                    assert_eq!(grid.distance_to_wall(&p), 0.0);
+                } else {
+                    let expected = brute_force_distance(&grid, &p);
+                    assert!((grid.distance_to_wall(&p) - expected).abs() < 1e-5);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn outside_grid_is_wall() {
+        let grid = StandardGrid::Pacman.compute_grid();
+        assert!(grid.is_in_wall(&Point2::new(-1.5, 1.0)));
+        assert!(grid.is_in_wall(&Point2::new(1.0, 40.0)));
+        assert_eq!(grid.distance_to_wall(&Point2::new(100.0, 1.0)), 0.0);
+    }
+}
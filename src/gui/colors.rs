@@ -3,6 +3,7 @@ use eframe::egui::Color32;
 pub const WALL_COLOR: Color32 = Color32::LIGHT_GRAY;
 pub const PELLET_COLOR: Color32 = Color32::BLUE;
 pub const SUPER_PELLET_COLOR: Color32 = Color32::BLUE;
+pub const FRUIT_COLOR: Color32 = Color32::from_rgb(220, 20, 60);
 
 pub const PACMAN_COLOR: Color32 = Color32::YELLOW;
 pub const PACMAN_GUESS_COLOR: Color32 = Color32::GREEN;
@@ -1,12 +1,14 @@
 use crate::agent_setup::PacmanAgentSetup;
-use crate::game_state::{GhostType, PacmanState};
+use crate::game_rules::GameRules;
+use crate::game_state::{GameEvent, GhostType, PacmanState};
 use crate::grid::facing_direction;
 use crate::gui::colors::{
-    GHOST_BLUE_COLOR, GHOST_ORANGE_COLOR, GHOST_PINK_COLOR, GHOST_RED_COLOR, PELLET_COLOR,
-    SUPER_PELLET_COLOR, WALL_COLOR,
+    FRUIT_COLOR, GHOST_BLUE_COLOR, GHOST_ORANGE_COLOR, GHOST_PINK_COLOR, GHOST_RED_COLOR,
+    PELLET_COLOR, SUPER_PELLET_COLOR, WALL_COLOR,
 };
 use crate::gui::transforms::Transform;
 use crate::gui::App;
+use crate::history::StateHistory;
 use eframe::egui;
 use eframe::egui::{Painter, Pos2, Rect, Rounding, Stroke};
 use rand::prelude::ThreadRng;
@@ -20,14 +22,21 @@ use std::sync::{Arc, RwLock};
 pub struct PacmanStateRenderInfo {
     /// Initial positions of Pacman, ghosts, etc.
     pub agent_setup: PacmanAgentSetup,
+    /// Scoring and timing rules
+    pub rules: GameRules,
     /// Current game state
     pub pacman_state: PacmanState,
+    /// The events from the most recent frame that had any
+    pub events: Vec<GameEvent>,
+    /// Changes to the game state that can be undone
+    #[serde(skip)]
+    pub history: StateHistory,
 }
 
 pub(super) fn run_game(
     pacman_render: Arc<RwLock<PacmanStateRenderInfo>>,
     location_receive: Receiver<Point2<u8>>,
-    replay_send: Sender<()>,
+    replay_send: Sender<Vec<GameEvent>>,
 ) {
     let mut rng = ThreadRng::default();
 
@@ -36,23 +45,41 @@ pub(super) fn run_game(
     loop {
         // {} block to make sure `game` goes out of scope and the RwLockWriteGuard is released
         {
-            let mut state = pacman_render.write().unwrap();
+            let mut guard = pacman_render.write().unwrap();
+            let state = &mut *guard;
 
             // fetch updated pacbot position
+            let mut pacman_update = None;
             while let Ok(pacbot_location) = location_receive.try_recv() {
-                state.pacman_state.update_pacman(
+                pacman_update = Some((
                     pacbot_location,
                     facing_direction(&previous_pacman_location, &pacbot_location),
-                );
+                ));
                 previous_pacman_location = pacbot_location;
             }
 
             let agent_setup = state.agent_setup.clone();
-
-            // step the game
-            if !state.pacman_state.paused {
-                state.pacman_state.step(&agent_setup, &mut rng, true);
-                replay_send.send(()).unwrap()
+            let rules = state.rules.clone();
+
+            if state.pacman_state.paused {
+                // the robot moving while paused is not a change to the game that can be undone
+                if let Some((location, direction)) = pacman_update {
+                    state.pacman_state.update_pacman(location, direction);
+                }
+            } else {
+                // step the game, moving Pacman as part of the same change
+                let events = state
+                    .history
+                    .record(&mut state.pacman_state, |pacman_state| {
+                        if let Some((location, direction)) = pacman_update {
+                            pacman_state.update_pacman(location, direction);
+                        }
+                        pacman_state.step(&agent_setup, &rules, &mut rng, true)
+                    });
+                if !events.is_empty() {
+                    state.events = events.clone();
+                }
+                replay_send.send(events).unwrap()
             }
         }
 
@@ -86,16 +113,6 @@ impl App {
                 Stroke::new(1.0, WALL_COLOR),
             );
         }
-
-        // make sure the area outside the soft boundary is not drawn on
-        for (p1, p2) in self.selected_grid.get_outside_soft_boundaries() {
-            painter.rect(
-                Rect::from_two_pos(world_to_screen.map_point(p1), world_to_screen.map_point(p2)),
-                Rounding::ZERO,
-                ctx.style().visuals.panel_fill,
-                Stroke::new(1.0, ctx.style().visuals.panel_fill),
-            );
-        }
     }
 
     pub(super) fn draw_pacman_state(
@@ -110,7 +127,19 @@ impl App {
         egui::Window::new("Pacman").show(ctx, |ui| {
             ui.label(format!("Score: {}", pacman_state.score));
             ui.label(format!("Lives: {}", pacman_state.lives));
+            ui.label(format!("Level: {}", pacman_state.level));
+            ui.label(format!(
+                "Status: {:?}",
+                pacman_state.status(&pacman_state_info.rules)
+            ));
             ui.label(format!("Frame: {}", pacman_state.elapsed_time));
+            if let Some(fruit) = &pacman_state.fruit {
+                ui.label(format!("Fruit: {} frames left", fruit.remaining_time));
+            }
+            ui.separator();
+            for event in &pacman_state_info.events {
+                ui.label(format!("{:?}", event));
+            }
         });
 
         // ghosts
@@ -131,8 +160,8 @@ impl App {
         }
 
         // pellets
-        for i in 0..pacman_state.pellets.len() {
-            if pacman_state.pellets[i] {
+        for i in 0..pacman_state.pellets().len() {
+            if pacman_state.pellets()[i] {
                 painter.circle_filled(
                     world_to_screen.map_point(Pos2::new(
                         self.agent_setup.grid().walkable_nodes()[i].x as f32,
@@ -145,12 +174,22 @@ impl App {
         }
 
         // super pellets
-        for super_pellet in &pacman_state.power_pellets {
+        for super_pellet in pacman_state.power_pellets() {
             painter.circle_filled(
                 world_to_screen.map_point(Pos2::new(super_pellet.x as f32, super_pellet.y as f32)),
                 6.0,
                 SUPER_PELLET_COLOR,
             )
         }
+
+        // fruit
+        if let Some(fruit) = &pacman_state.fruit {
+            painter.circle_filled(
+                world_to_screen
+                    .map_point(Pos2::new(fruit.location.x as f32, fruit.location.y as f32)),
+                world_to_screen.map_dist(0.3),
+                FRUIT_COLOR,
+            )
+        }
     }
 }
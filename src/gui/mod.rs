@@ -11,22 +11,30 @@ use std::ops::Deref;
 use std::sync::mpsc::{channel, Receiver, Sender};
 use std::sync::{Arc, Mutex, RwLock};
 
+use anyhow::Error;
 use eframe::egui;
 use eframe::egui::{Frame, Key, Pos2, Ui};
+use native_dialog::FileDialog;
 use rapier2d::na::{Isometry2, Vector2};
 
 use crate::agent_setup::PacmanAgentSetup;
-use crate::constants::GUI_PARTICLE_FILTER_POINTS;
-use crate::game_state::PacmanState;
+use crate::constants::{GUI_HISTORY_LENGTH, GUI_PARTICLE_FILTER_POINTS};
+use crate::custom_grids::{CustomGrid, GridRegistry, MAP_FILE_EXTENSION};
+use crate::game_rules::{GameRules, RULES_FILE_EXTENSION};
+use crate::game_state::{GameEvent, PacmanState};
 use crate::grid::ComputedGrid;
 use crate::gui::game::{run_game, PacmanStateRenderInfo};
 use crate::gui::physics::{run_physics, PhysicsRenderInfo};
+use crate::history::StateHistory;
 use crate::robot::Robot;
 use crate::standard_grids::StandardGrid;
 use crate::util::stopwatch::Stopwatch;
 
 use self::transforms::Transform;
 
+/// The directory that custom maps are loaded from when the GUI starts
+const MAPS_DIRECTORY: &str = "maps";
+
 /// Launches the GUI application. Blocks until the application has quit.
 pub fn run_gui() {
     let native_options = eframe::NativeOptions::default();
@@ -57,6 +65,8 @@ struct App {
     mode: AppMode,
 
     selected_grid: StandardGrid,
+    /// The grids that can be selected, including custom maps
+    grid_registry: GridRegistry,
     grid: ComputedGrid,
     pointer_pos: String,
 
@@ -68,9 +78,11 @@ struct App {
 
     pacman_render: Arc<RwLock<PacmanStateRenderInfo>>,
     agent_setup: PacmanAgentSetup,
+    /// Whether a game can be played on the selected grid
+    has_game: bool,
 
     replay_manager: replay_manager::ReplayManager,
-    pacman_state_notify_recv: Receiver<()>,
+    pacman_state_notify_recv: Receiver<Vec<GameEvent>>,
     /// When in playback mode, the position of pacbot from the replay
     replay_pacman: Isometry2<f32>,
     save_pacbot_location: bool,
@@ -107,10 +119,14 @@ impl Default for App {
 
         // Set up game state thread
         let agent_setup = PacmanAgentSetup::default();
-        let pacman_state = PacmanState::new(&agent_setup);
+        let rules = GameRules::default();
+        let pacman_state = PacmanState::new(&agent_setup, &rules);
         let pacman_state_info = PacmanStateRenderInfo {
             pacman_state,
             agent_setup,
+            rules,
+            events: vec![],
+            history: StateHistory::with_max_len(GUI_HISTORY_LENGTH),
         };
         let pacman_render: Arc<RwLock<PacmanStateRenderInfo>> =
             Arc::new(RwLock::new(pacman_state_info));
@@ -146,10 +162,15 @@ impl Default for App {
 
         let pacbot_pos = phys_render.read().unwrap().pacbot_pos;
 
+        // Custom maps are optional, so a missing or invalid maps directory is not an error
+        let mut grid_registry = GridRegistry::default();
+        let _ = grid_registry.load_dir(MAPS_DIRECTORY);
+
         Self {
             mode: AppMode::Recording(GameServer::Simulated),
 
             selected_grid: StandardGrid::Pacman,
+            grid_registry,
             grid: StandardGrid::Pacman.compute_grid(),
             pointer_pos: "".to_string(),
 
@@ -160,6 +181,7 @@ impl Default for App {
 
             pacman_render,
             agent_setup: PacmanAgentSetup::default(),
+            has_game: true,
 
             replay_manager: App::new_replay_manager(
                 filename,
@@ -209,29 +231,111 @@ impl App {
     }
 
     fn add_grid_variants(&mut self, ui: &mut Ui) {
+        let mut selected = None;
         egui::ComboBox::from_label("")
-            .selected_text(format!("{:?}", self.selected_grid))
+            .selected_text(self.selected_grid.name())
             .show_ui(ui, |ui| {
-                StandardGrid::get_all().iter().for_each(|grid| {
+                for grid in self.grid_registry.grids() {
                     if ui
-                        .selectable_value(&mut self.selected_grid, *grid, format!("{:?}", grid))
+                        .selectable_label(self.selected_grid == *grid, grid.name())
                         .clicked()
                     {
-                        self.pacman_render.write().unwrap().pacman_state.pause();
-                        self.grid = grid.compute_grid();
-                        self.phys_render.write().unwrap().pacbot_pos =
-                            self.selected_grid.get_default_pacbot_isometry();
-                        self.phys_restart_send
-                            .send((
-                                self.selected_grid,
-                                Robot::default(),
-                                self.selected_grid.get_default_pacbot_isometry(),
-                            ))
-                            .unwrap();
-                        self.reset_replay();
+                        selected = Some(grid.to_owned());
                     }
-                });
+                }
             });
+        if let Some(grid) = selected {
+            self.select_grid(grid);
+        }
+    }
+
+    /// Switch to a different grid, restarting physics, the game and the replay
+    ///
+    /// If a game can't be set up on the grid, only the physics simulation runs.
+    fn select_grid(&mut self, grid: StandardGrid) {
+        self.pacman_render.write().unwrap().pacman_state.pause();
+        match PacmanAgentSetup::from_standard_grid(&grid) {
+            Ok(agent_setup) => {
+                let mut game = self.pacman_render.write().unwrap();
+                let rules = game.rules.clone();
+                game.pacman_state.reset(&agent_setup, &rules, true);
+                game.agent_setup = agent_setup.clone();
+                game.events.clear();
+                game.history.clear();
+                self.agent_setup = agent_setup;
+                self.has_game = true;
+            }
+            Err(_) => self.has_game = false,
+        }
+        self.grid = grid.compute_grid();
+        self.phys_render.write().unwrap().pacbot_pos = grid.get_default_pacbot_isometry();
+        self.phys_restart_send
+            .send((
+                grid.to_owned(),
+                Robot::default(),
+                grid.get_default_pacbot_isometry(),
+            ))
+            .unwrap();
+        self.selected_grid = grid;
+        self.reset_replay();
+    }
+
+    /// Restart physics on the same grid with the robot somewhere else
+    fn move_pacbot_to(&mut self, position: Isometry2<f32>) {
+        self.phys_render.write().unwrap().pacbot_pos = position;
+        self.phys_restart_send
+            .send((self.selected_grid.to_owned(), Robot::default(), position))
+            .unwrap();
+    }
+
+    /// Register every map file in a directory chosen by the user
+    fn load_maps(&mut self) -> Result<(), Error> {
+        if let Some(path) = FileDialog::new().show_open_single_dir()? {
+            self.grid_registry.load_dir(path)?;
+        }
+        Ok(())
+    }
+
+    /// Save the selected grid as a map file
+    fn save_map(&self) -> Result<(), Error> {
+        let custom_grid = match &self.selected_grid {
+            StandardGrid::Custom(custom_grid) => custom_grid.as_ref().to_owned(),
+            grid => CustomGrid::new(
+                grid.name().to_string(),
+                grid.get_grid(),
+                vec![],
+                grid.get_default_pacbot_isometry(),
+                grid.get_soft_boundaries(),
+            )?,
+        };
+        let path = FileDialog::new()
+            .add_filter("Pacbot Map", &[MAP_FILE_EXTENSION])
+            .set_filename(&format!("{}.{}", custom_grid.name(), MAP_FILE_EXTENSION))
+            .show_save_single_file()?;
+        if let Some(path) = path {
+            custom_grid.save(path)?;
+        }
+        Ok(())
+    }
+
+    /// Switch to different game rules, restarting the game
+    fn set_rules(&mut self, rules: GameRules) {
+        let mut game = self.pacman_render.write().unwrap();
+        game.pacman_state.reset(&self.agent_setup, &rules, true);
+        game.rules = rules;
+        game.events.clear();
+        game.history.clear();
+    }
+
+    /// Load game rules from a file chosen by the user
+    fn load_rules(&mut self) -> Result<(), Error> {
+        let path = FileDialog::new()
+            .add_filter("Pacbot Rules", &[RULES_FILE_EXTENSION])
+            .show_open_single_file()?;
+        if let Some(path) = path {
+            self.set_rules(GameRules::load(path)?);
+        }
+        Ok(())
     }
 }
 
@@ -287,13 +391,28 @@ impl eframe::App for App {
                                 self.save_pacbot_location = !self.save_pacbot_location;
                             }
                         });
+                        ui.menu_button("Maps", |ui| {
+                            if ui.button("Load Directory").clicked() {
+                                self.load_maps().expect("Failed to load maps!");
+                            }
+                            if ui.button("Save Current").clicked() {
+                                self.save_map().expect("Failed to save map!");
+                            }
+                        });
                         ui.menu_button("Game", |ui| {
                             if ui.button("Reset").clicked() {
-                                self.pacman_render
-                                    .write()
-                                    .unwrap()
-                                    .pacman_state
-                                    .reset(&self.agent_setup, true);
+                                let rules = self.pacman_render.read().unwrap().rules.clone();
+                                self.set_rules(rules);
+                            }
+                            ui.separator();
+                            if ui.button("Official Rules").clicked() {
+                                self.set_rules(GameRules::official());
+                            }
+                            if ui.button("Classic Rules").clicked() {
+                                self.set_rules(GameRules::classic());
+                            }
+                            if ui.button("Load Rules").clicked() {
+                                self.load_rules().expect("Failed to load rules!");
                             }
                         });
                     })
@@ -303,7 +422,7 @@ impl eframe::App for App {
                 });
             });
         });
-        if self.selected_grid == StandardGrid::Pacman {
+        if self.has_game {
             egui::TopBottomPanel::bottom("playback_controls")
                 .frame(
                     Frame::none()
@@ -320,7 +439,9 @@ impl eframe::App for App {
             .frame(Frame::none().fill(ctx.style().visuals.panel_fill))
             .show(ctx, |ui| {
                 let rect = ui.max_rect();
-                let (src_p1, src_p2) = self.selected_grid.get_soft_boundaries();
+                let boundaries = self.selected_grid.get_soft_boundaries();
+                let src_p1 = Pos2::new(boundaries.left_bottom.x, boundaries.right_top.y);
+                let src_p2 = Pos2::new(boundaries.right_top.x, boundaries.left_bottom.y);
 
                 let world_to_screen = Transform::new_letterboxed(
                     src_p1,
@@ -333,7 +454,7 @@ impl eframe::App for App {
                 self.draw_grid(ctx, &world_to_screen, &painter);
                 self.gui_stopwatch.mark_segment("Draw grid");
 
-                if self.selected_grid == StandardGrid::Pacman {
+                if self.has_game {
                     self.draw_pacman_state(ctx, &world_to_screen, &painter);
                 }
                 self.gui_stopwatch.mark_segment("Draw pacman state");
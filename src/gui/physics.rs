@@ -42,7 +42,7 @@ pub(super) fn run_physics(
     pf_stopwatch: Arc<Mutex<Stopwatch>>,
     physics_stopwatch: Arc<Mutex<Stopwatch>>,
 ) {
-    let grid = StandardGrid::Pacman.compute_grid();
+    let mut grid = StandardGrid::Pacman.compute_grid();
 
     let distance_sensors_ref = distance_sensors.clone();
 
@@ -57,13 +57,10 @@ pub(super) fn run_physics(
 
     loop {
         // Was a restart requested?
-        if let Ok((grid, robot, isometry)) = restart_recv.try_recv() {
-            simulation = PacbotSimulation::new(
-                grid.compute_grid(),
-                robot,
-                isometry,
-                distance_sensors.clone(),
-            );
+        if let Ok((standard_grid, robot, isometry)) = restart_recv.try_recv() {
+            grid = standard_grid.compute_grid();
+            simulation =
+                PacbotSimulation::new(grid.to_owned(), robot, isometry, distance_sensors.clone());
         }
 
         // Run simulation one step
@@ -61,7 +61,12 @@ impl App {
     /// When not in Playback mode, update_replay_playback has no effect
     pub fn update_replay_manager(&mut self) -> Result<(), Error> {
         // did pacman state request saving?
-        if self.pacman_state_notify_recv.try_recv().is_ok() && self.mode != AppMode::Playback {
+        if let Some(events) = self
+            .pacman_state_notify_recv
+            .try_recv()
+            .ok()
+            .filter(|_| self.mode != AppMode::Playback)
+        {
             let state = self.pacman_render.read().unwrap().pacman_state.to_owned();
             // if we aren't recording the physics position, we should record the game position
             if !self.save_pacbot_location {
@@ -75,6 +80,7 @@ impl App {
                         state.pacman.direction.get_rotation(),
                     ))?;
             }
+            self.replay_manager.replay.record_game_events(events)?;
             self.replay_manager.replay.record_pacman_state(state)?;
         }
 
@@ -165,13 +171,44 @@ impl App {
                 self.replay_manager.replay.go_to_beginning();
             }
             if ui
-                .add_enabled(advanced_controls, icon_button("⏪"))
+                .add_enabled(
+                    advanced_controls || (!playback_mode && game_paused),
+                    icon_button("⏪"),
+                )
                 .clicked()
                 || (k_left && !k_shift)
             {
-                self.replay_manager
-                    .replay
-                    .step_backwards_until_pacman_state();
+                if playback_mode {
+                    self.replay_manager
+                        .replay
+                        .step_backwards_until_pacman_state();
+                } else if game_paused {
+                    // game is live but paused
+                    let state = {
+                        let mut guard = self.pacman_render.write().unwrap();
+                        let game = &mut *guard;
+                        game.history
+                            .undo(&mut game.pacman_state, self.agent_setup.grid())
+                            .then(|| {
+                                game.pacman_state.pause();
+                                game.pacman_state.to_owned()
+                            })
+                    };
+                    if let Some(state) = state {
+                        // move the robot back too, so that it doesn't move Pacman straight back
+                        self.move_pacbot_to(Isometry2::from_parts(
+                            Translation::new(
+                                state.pacman.location.x as f32,
+                                state.pacman.location.y as f32,
+                            ),
+                            state.pacman.direction.get_rotation(),
+                        ));
+                        self.replay_manager
+                            .replay
+                            .record_pacman_state(state)
+                            .expect("Failed to record pacman state!");
+                    }
+                }
             }
             if playback_mode {
                 if self.replay_manager.playback_paused {
@@ -213,13 +250,27 @@ impl App {
                         .step_forwards_until_pacman_state();
                 } else {
                     // game is live but paused
-                    {
-                        let mut game = self.pacman_render.write().unwrap();
+                    let events = {
+                        let mut guard = self.pacman_render.write().unwrap();
+                        let game = &mut *guard;
                         game.pacman_state.resume();
-                        game.pacman_state
-                            .step(&self.agent_setup, &mut ThreadRng::default(), true);
+                        let events = game.history.step(
+                            &mut game.pacman_state,
+                            &self.agent_setup,
+                            &game.rules,
+                            &mut ThreadRng::default(),
+                            true,
+                        );
                         game.pacman_state.pause();
-                    }
+                        if !events.is_empty() {
+                            game.events = events.clone();
+                        }
+                        events
+                    };
+                    self.replay_manager
+                        .replay
+                        .record_game_events(events)
+                        .expect("Failed to record game events!");
                     self.replay_manager
                         .replay
                         .record_pacman_state(
@@ -253,7 +304,12 @@ impl App {
         let location = self.replay_manager.replay.get_pacbot_location();
 
         pacman_state.pause();
-        self.pacman_render.write().unwrap().pacman_state = pacman_state;
+        {
+            let mut game = self.pacman_render.write().unwrap();
+            game.pacman_state = pacman_state;
+            // the history doesn't know how the replay got to this state
+            game.history.clear();
+        }
 
         self.replay_pacman = location.to_owned();
     }
@@ -267,7 +323,11 @@ impl App {
 
         if let Some(path) = path {
             let bytes = self.replay_manager.replay.to_bytes()?;
-            let mut file = fs::OpenOptions::new().write(true).create(true).open(path)?;
+            let mut file = fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(path)?;
             file.write_all(&bytes)?;
         }
 
@@ -288,6 +348,16 @@ impl App {
 
             let replay = Replay::from_bytes(&buffer)?;
 
+            // switch to the replay's grid, so that custom maps can be played back too
+            let standard_grid = replay.standard_grid().to_owned();
+            if let StandardGrid::Custom(custom_grid) = &standard_grid {
+                self.grid_registry
+                    .register(custom_grid.as_ref().to_owned())?;
+            }
+            if standard_grid != self.selected_grid {
+                self.select_grid(standard_grid);
+            }
+
             self.mode = AppMode::Playback;
             self.replay_manager.replay = replay;
             self.update_with_replay();
@@ -300,7 +370,7 @@ impl App {
     pub fn reset_replay(&mut self) {
         self.replay_manager.replay = Replay::new(
             "replay".to_string(),
-            self.selected_grid,
+            self.selected_grid.to_owned(),
             self.agent_setup.to_owned(),
             self.pacman_render.read().unwrap().pacman_state.to_owned(),
             self.phys_render.read().unwrap().pacbot_pos,
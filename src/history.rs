@@ -0,0 +1,411 @@
+//! Undo and redo for a game of Pacman
+//!
+//! [`StateHistory`] makes changes to a [`GameState`] and remembers what each one changed, so that
+//! it can be undone and redone without keeping a copy of every state. Most steps only move the
+//! agents and eat at most one pellet, so only the agents, counters and eaten pellets are kept;
+//! the whole board is only kept when it is refilled for a new level.
+//!
+//! A history can be limited to a number of changes, in which case the oldest changes are
+//! forgotten first, so that long games don't keep growing it.
+
+use crate::agent_setup::PacmanAgentSetup;
+use crate::game_rules::GameRules;
+use crate::game_state::{GameEvent, GameState, Ghost, GhostList, PelletSet};
+use crate::grid::{ComputedGrid, Direction};
+use rand::Rng;
+use rapier2d::na::Point2;
+use std::collections::VecDeque;
+
+/// Everything in a [`GameState`] except the pellets
+type Snapshot<G> = GameState<(), (), G>;
+
+/// What a change did to the pellets
+#[derive(Clone, Debug)]
+enum PelletChange<P, S> {
+    /// Pacman ate the pellets and super pellets at these locations, in this order
+    Eaten(Vec<(Point2<u8>, bool)>),
+    /// The board was refilled for a new level
+    Refilled {
+        /// The pellets and super pellets before the change
+        before: (P, S),
+        /// The pellets and super pellets after the change
+        after: (P, S),
+    },
+}
+
+/// One change to a [`GameState`], which can be undone and redone
+#[derive(Clone, Debug)]
+struct Change<P, S, G> {
+    /// Everything except the pellets, before the change
+    before: Snapshot<G>,
+    /// Everything except the pellets, after the change
+    after: Snapshot<G>,
+    /// What happened to the pellets
+    pellets: PelletChange<P, S>,
+}
+
+/// The changes made to a [`GameState`], for undo and redo
+///
+/// The history only knows about changes made through it, so if the state is changed any other
+/// way, the history should be cleared.
+///
+/// # Examples
+///
+/// ```
+/// use mdrc_pacbot_util::agent_setup::PacmanAgentSetup;
+/// use mdrc_pacbot_util::game_rules::GameRules;
+/// use mdrc_pacbot_util::game_state::PacmanState;
+/// use mdrc_pacbot_util::grid::Direction;
+/// use mdrc_pacbot_util::history::StateHistory;
+/// use rand::rngs::StdRng;
+/// use rand::SeedableRng;
+/// use rapier2d::na::Point2;
+///
+/// let agent_setup = PacmanAgentSetup::default();
+/// let rules = GameRules::default();
+/// let mut rng = StdRng::seed_from_u64(0);
+/// let mut state = PacmanState::new(&agent_setup, &rules);
+/// let mut history: StateHistory = StateHistory::new();
+///
+/// let start = state.clone();
+/// history.update_pacman(&mut state, Point2::new(12, 7), Direction::Right);
+/// state.resume();
+/// history.step(&mut state, &agent_setup, &rules, &mut rng, false);
+/// let end = state.clone();
+///
+/// // undoing the step puts the pellet back
+/// assert!(history.undo(&mut state, agent_setup.grid()));
+/// assert!(history.undo(&mut state, agent_setup.grid()));
+/// assert!(!history.undo(&mut state, agent_setup.grid()));
+/// assert_eq!(state, start);
+///
+/// assert!(history.redo(&mut state, agent_setup.grid()));
+/// assert!(history.redo(&mut state, agent_setup.grid()));
+/// assert_eq!(state, end);
+/// ```
+#[derive(Clone, Debug)]
+pub struct StateHistory<P = Vec<bool>, S = Vec<Point2<u8>>, G = Vec<Ghost>> {
+    /// Changes that can be undone, most recent last
+    undo: VecDeque<Change<P, S, G>>,
+    /// Changes that were undone and can be redone, most recently undone last
+    redo: Vec<Change<P, S, G>>,
+    /// The most changes that can be undone, or `None` for no limit
+    max_len: Option<usize>,
+}
+
+impl<P, S, G> Default for StateHistory<P, S, G> {
+    fn default() -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: vec![],
+            max_len: None,
+        }
+    }
+}
+
+impl<P, S, G> StateHistory<P, S, G>
+where
+    P: PelletSet + Clone,
+    S: PelletSet + Clone,
+    G: GhostList + Clone,
+{
+    /// Create an empty history with no limit on its length
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create an empty history that only remembers the most recent `max_len` changes
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::agent_setup::PacmanAgentSetup;
+    /// use mdrc_pacbot_util::game_rules::GameRules;
+    /// use mdrc_pacbot_util::game_state::PacmanState;
+    /// use mdrc_pacbot_util::grid::Direction;
+    /// use mdrc_pacbot_util::history::StateHistory;
+    /// use rapier2d::na::Point2;
+    ///
+    /// let agent_setup = PacmanAgentSetup::default();
+    /// let mut state = PacmanState::new(&agent_setup, &GameRules::default());
+    /// let mut history: StateHistory = StateHistory::with_max_len(2);
+    ///
+    /// for x in [13, 12, 11] {
+    ///     history.update_pacman(&mut state, Point2::new(x, 7), Direction::Left);
+    /// }
+    /// assert_eq!(history.undo_len(), 2);
+    /// ```
+    pub fn with_max_len(max_len: usize) -> Self {
+        Self {
+            max_len: Some(max_len),
+            ..Self::default()
+        }
+    }
+
+    /// Forget every change
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Get the number of changes that can be undone
+    pub fn undo_len(&self) -> usize {
+        self.undo.len()
+    }
+
+    /// Get the number of changes that can be redone
+    pub fn redo_len(&self) -> usize {
+        self.redo.len()
+    }
+
+    /// Make a change to the state, and remember it so it can be undone
+    ///
+    /// `change` must return a [`GameEvent`] for every pellet and super pellet it eats, as
+    /// [`GameState::step`] does. Anything that could be redone is forgotten, as is the oldest
+    /// change if the history is full.
+    pub fn record(
+        &mut self,
+        state: &mut GameState<P, S, G>,
+        change: impl FnOnce(&mut GameState<P, S, G>) -> Vec<GameEvent>,
+    ) -> Vec<GameEvent> {
+        let before = Self::snapshot(state);
+        // only an empty board can be refilled, so this is cheap to keep
+        let empty_board = (state.pellets().remaining() == 0
+            && state.power_pellets().remaining() == 0)
+            .then(|| (state.pellets().clone(), state.power_pellets().clone()));
+
+        let events = change(state);
+
+        let pellets = match empty_board {
+            Some(before)
+                if events
+                    .iter()
+                    .any(|event| matches!(event, GameEvent::LevelStarted { .. })) =>
+            {
+                PelletChange::Refilled {
+                    before,
+                    after: (state.pellets().clone(), state.power_pellets().clone()),
+                }
+            }
+            _ => PelletChange::Eaten(
+                events
+                    .iter()
+                    .filter_map(|event| match event {
+                        GameEvent::PelletEaten { location, .. } => Some((*location, false)),
+                        GameEvent::PowerPelletEaten { location, .. } => Some((*location, true)),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+        };
+        self.undo.push_back(Change {
+            before,
+            after: Self::snapshot(state),
+            pellets,
+        });
+        if self
+            .max_len
+            .is_some_and(|max_len| self.undo.len() > max_len)
+        {
+            self.undo.pop_front();
+        }
+        self.redo.clear();
+        events
+    }
+
+    /// Step the state forward one frame, and remember the change
+    ///
+    /// See [`GameState::step`].
+    pub fn step<R: Rng>(
+        &mut self,
+        state: &mut GameState<P, S, G>,
+        agent_setup: &PacmanAgentSetup,
+        rules: &GameRules,
+        rng: &mut R,
+        use_physics: bool,
+    ) -> Vec<GameEvent> {
+        self.record(state, |state| {
+            state.step(agent_setup, rules, rng, use_physics)
+        })
+    }
+
+    /// Update Pacman's location and direction, and remember the change
+    pub fn update_pacman(&mut self, state: &mut GameState<P, S, G>, p: Point2<u8>, d: Direction) {
+        self.record(state, |state| {
+            state.update_pacman(p, d);
+            vec![]
+        });
+    }
+
+    /// Undo the most recent change, returning whether there was one
+    ///
+    /// The state must be the one the change was made to, with no changes since.
+    pub fn undo(&mut self, state: &mut GameState<P, S, G>, grid: &ComputedGrid) -> bool {
+        let Some(change) = self.undo.pop_back() else {
+            return false;
+        };
+        match &change.pellets {
+            PelletChange::Eaten(eaten) => {
+                for (location, power) in eaten.iter().rev() {
+                    if let Some(node) = grid.coords_to_node(location) {
+                        state.put_back_pellet(grid, *location, node, *power);
+                    }
+                }
+            }
+            PelletChange::Refilled { before, .. } => {
+                state.set_pellets(grid, before.0.clone(), before.1.clone());
+            }
+        }
+        state.restore_except_pellets(&change.before);
+        self.redo.push(change);
+        true
+    }
+
+    /// Redo the most recently undone change, returning whether there was one
+    pub fn redo(&mut self, state: &mut GameState<P, S, G>, grid: &ComputedGrid) -> bool {
+        let Some(change) = self.redo.pop() else {
+            return false;
+        };
+        match &change.pellets {
+            PelletChange::Eaten(eaten) => {
+                for (location, power) in eaten {
+                    if let Some(node) = grid.coords_to_node(location) {
+                        state.eat_pellet(*location, node, *power);
+                    }
+                }
+            }
+            PelletChange::Refilled { after, .. } => {
+                state.set_pellets(grid, after.0.clone(), after.1.clone());
+            }
+        }
+        state.restore_except_pellets(&change.after);
+        self.undo.push_back(change);
+        true
+    }
+
+    fn snapshot(state: &GameState<P, S, G>) -> Snapshot<G> {
+        state.with_storage((), (), state.ghosts.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::agent_setup::PacmanAgentSetup;
+    use crate::compact_state::CompactPacmanState;
+    use crate::game_rules::GameRules;
+    use crate::game_state::{play_seeded, GameEvent, PacmanState};
+    use crate::grid::Direction;
+    use crate::history::StateHistory;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use rapier2d::na::Point2;
+
+    #[test]
+    fn undo_and_redo_a_game() {
+        let agent_setup = PacmanAgentSetup::default();
+        let rules = GameRules {
+            levels_to_win: Some(2),
+            ..GameRules::default()
+        };
+        let grid = agent_setup.grid();
+        let mut history: StateHistory = StateHistory::new();
+
+        let mut states = vec![];
+        let mut levels_started = 0;
+        let mut state = play_seeded(&agent_setup, &rules, 5, |frame, state, location, rng| {
+            history.update_pacman(state, location, Direction::Right);
+            states.push(state.clone());
+            if frame == 150 {
+                // clear the board, so that the next steps start a new level
+                let pellets = vec![false; grid.walkable_nodes().len()];
+                state.set_pellets(grid, pellets, vec![location]);
+                history.clear();
+                states = vec![state.clone()];
+            }
+            state.resume();
+            let events = history.step(state, &agent_setup, &rules, rng, false);
+            state.pause();
+            levels_started += events
+                .iter()
+                .filter(|e| matches!(e, GameEvent::LevelStarted { .. }))
+                .count();
+            states.push(state.clone());
+            frame < 299
+        });
+        assert_eq!(levels_started, 1);
+        assert_eq!(history.undo_len(), states.len() - 1);
+
+        // every state is restored exactly, apart from pausing, which was not recorded
+        let end = state.clone();
+        for expected in states.iter().rev().skip(1) {
+            assert!(history.undo(&mut state, grid));
+            state.paused = expected.paused;
+            assert_eq!(state, *expected);
+        }
+        assert!(!history.undo(&mut state, grid));
+        for expected in &states[1..] {
+            assert!(history.redo(&mut state, grid));
+            state.paused = expected.paused;
+            assert_eq!(state, *expected);
+        }
+        assert!(!history.redo(&mut state, grid));
+        assert_eq!(state, end);
+
+        // a new change forgets what could be redone
+        history.undo(&mut state, grid);
+        history.update_pacman(&mut state, Point2::new(12, 7), Direction::Left);
+        assert_eq!(history.redo_len(), 0);
+    }
+
+    #[test]
+    fn limited_history() {
+        let agent_setup = PacmanAgentSetup::default();
+        let rules = GameRules::default();
+        let grid = agent_setup.grid();
+        let mut rng = StdRng::seed_from_u64(0);
+        let mut state = PacmanState::new(&agent_setup, &rules);
+        let mut history: StateHistory = StateHistory::with_max_len(3);
+
+        let mut states = vec![];
+        for _ in 0..10 {
+            state.resume();
+            history.step(&mut state, &agent_setup, &rules, &mut rng, false);
+            states.push(state.clone());
+        }
+        assert_eq!(history.undo_len(), 3);
+
+        // only the most recent changes can be undone
+        for expected in states.iter().rev().skip(1).take(3) {
+            assert!(history.undo(&mut state, grid));
+            assert_eq!(state, *expected);
+        }
+        assert!(!history.undo(&mut state, grid));
+        assert_eq!(history.redo_len(), 3);
+    }
+
+    #[test]
+    fn compact_history() {
+        let agent_setup = PacmanAgentSetup::default();
+        let rules = GameRules::default();
+        let grid = agent_setup.grid();
+        let mut state = CompactPacmanState::new(&agent_setup, &rules);
+        let start = state;
+        let mut history = StateHistory::new();
+
+        // eat the power pellet at (1, 7)
+        history.update_pacman(&mut state, Point2::new(1, 7), Direction::Right);
+        state.resume();
+        history.step(
+            &mut state,
+            &agent_setup,
+            &rules,
+            &mut StdRng::seed_from_u64(0),
+            false,
+        );
+        assert_eq!(state.power_pellets().len(), start.power_pellets().len() - 1);
+
+        history.undo(&mut state, grid);
+        history.undo(&mut state, grid);
+        assert_eq!(state, start);
+    }
+}
@@ -2,11 +2,16 @@
 //! Utilities for writing blazingly fast Pacbot code
 
 pub mod agent_setup;
+pub mod compact_state;
 pub mod constants;
+pub mod custom_grids;
+pub mod game_engine;
+pub mod game_rules;
 pub mod game_state;
 pub mod ghost;
 pub mod grid;
 pub mod gui;
+pub mod history;
 pub mod network;
 pub mod physics;
 pub mod replay;
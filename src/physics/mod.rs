@@ -6,6 +6,7 @@ use crate::constants::{
     NUM_PARTICLE_FILTER_POINTS, PARTICLE_FILTER_ELITE, PARTICLE_FILTER_PURGE,
     PARTICLE_FILTER_RANDOM,
 };
+use crate::grid::distance_field::RayTableOptions;
 use crate::grid::ComputedGrid;
 use crate::physics::particle_filter::{ParticleFilter, ParticleFilterOptions};
 use crate::robot::Robot;
@@ -48,10 +49,9 @@ pub struct PacbotSimulation {
 impl Default for PacbotSimulation {
     /// Creates a simulation with GRID_PACMAN, the default Robot, and starting position (14, 7)
     fn default() -> Self {
-        Self::new(
-            StandardGrid::Pacman.compute_grid(),
+        Self::from_standard_grid(
+            &StandardGrid::Pacman,
             Robot::default(),
-            StandardGrid::Pacman.get_default_pacbot_isometry(),
             Arc::new(Mutex::new(vec![
                 Some(0.0);
                 Robot::default().distance_sensors.len()
@@ -61,6 +61,39 @@ impl Default for PacbotSimulation {
 }
 
 impl PacbotSimulation {
+    /// Create a new simulation on a [`StandardGrid`], which may be a custom grid, with the robot at
+    /// the grid's default position
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use mdrc_pacbot_util::custom_grids::CustomGrid;
+    /// use mdrc_pacbot_util::physics::PacbotSimulation;
+    /// use mdrc_pacbot_util::robot::Robot;
+    /// use mdrc_pacbot_util::standard_grids::StandardGrid;
+    ///
+    /// let custom_grid = CustomGrid::parse("IIIIII\nIeeeeI\nIeIIeI\nIeIIeI\nIeeeeI\nIIIIII\n", "Tiny").unwrap();
+    /// let distance_sensors = Arc::new(Mutex::new(vec![Some(0.0); Robot::default().distance_sensors.len()]));
+    /// let mut simulation = PacbotSimulation::from_standard_grid(
+    ///     &StandardGrid::Custom(Box::new(custom_grid)),
+    ///     Robot::default(),
+    ///     distance_sensors,
+    /// );
+    /// ```
+    pub fn from_standard_grid(
+        standard_grid: &StandardGrid,
+        robot: Robot,
+        distance_sensors: Arc<Mutex<Vec<Option<f32>>>>,
+    ) -> Self {
+        Self::new(
+            standard_grid.compute_grid(),
+            robot,
+            standard_grid.get_default_pacbot_isometry(),
+            distance_sensors,
+        )
+    }
+
     /// Create a new simulation on a ComputedGrid with a starting Robot and position
     ///
     /// # Examples
@@ -141,6 +174,14 @@ impl PacbotSimulation {
                 elitism_bias: 1.0,
                 genetic_translation_limit: 0.1,
                 genetic_rotation_limit: 0.1,
+                ray_table: Some(RayTableOptions {
+                    max_range: robot
+                        .distance_sensors
+                        .iter()
+                        .map(|s| s.max_range)
+                        .fold(0.0, f32::max),
+                    ..Default::default()
+                }),
             },
         );
 
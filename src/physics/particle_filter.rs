@@ -1,5 +1,6 @@
 //! Tracks the robot's position over time
 
+use crate::grid::distance_field::{RayTable, RayTableOptions};
 use crate::grid::{ComputedGrid, Direction};
 use crate::physics::{PacbotSimulation, GROUP_ROBOT, GROUP_WALL};
 use crate::robot::{DistanceSensor, Robot};
@@ -34,6 +35,10 @@ pub struct ParticleFilterOptions {
     pub elitism_bias: f32,
     pub genetic_translation_limit: f32,
     pub genetic_rotation_limit: f32,
+
+    /// If set, expected distance sensor readings are looked up in a [`RayTable`] built with these
+    /// options instead of being cast through the physics simulation
+    pub ray_table: Option<RayTableOptions>,
 }
 
 /// Tracks the robot's position over time
@@ -49,6 +54,9 @@ pub struct ParticleFilter {
     /// The current best guess
     best_guess: Isometry2<f32>,
 
+    /// Precomputed distance sensor readings, if enabled in the options
+    ray_table: Option<RayTable>,
+
     /// Values that can be tweaked to improve the performance of the particle filter
     options: ParticleFilterOptions,
 }
@@ -64,12 +72,14 @@ impl ParticleFilter {
         distance_sensors: Arc<Mutex<Vec<Option<f32>>>>,
         options: ParticleFilterOptions,
     ) -> Self {
+        let ray_table = options.ray_table.as_ref().map(|o| grid.ray_table(o));
         Self {
             points: Vec::new(),
             distance_sensors,
             grid,
             robot,
             best_guess: start,
+            ray_table,
             options,
         }
     }
@@ -280,20 +290,9 @@ impl ParticleFilter {
 
         // randomize any points that are within a wall or out of bounds
         for i in 0..self.options.points {
-            // if the virtual distance sensor reads 0, it is inside a wall
-            if Self::distance_sensor_ray(
-                self.points[i],
-                self.robot.distance_sensors[0],
-                rigid_body_set,
-                collider_set,
-                query_pipeline,
-            )
-            .abs()
-                < 0.05
-                || self.points[i].translation.x < 0.0
-                || self.points[i].translation.y < 0.0
-                || self.points[i].translation.x > 32.0
-                || self.points[i].translation.y > 32.0
+            if self
+                .grid
+                .is_in_wall(&Point2::from(self.points[i].translation.vector))
             {
                 self.points[i] = self.random_point();
             }
@@ -334,6 +333,7 @@ impl ParticleFilter {
                         &robot,
                         *p,
                         &distance_sensors,
+                        self.ray_table.as_ref(),
                         rigid_body_set,
                         collider_set,
                         query_pipeline,
@@ -366,7 +366,8 @@ impl ParticleFilter {
     fn distance_sensor_diff(
         robot: &Robot,
         point: Isometry2<f32>,
-        actual_values: &Vec<Option<f32>>,
+        actual_values: &[Option<f32>],
+        ray_table: Option<&RayTable>,
         rigid_body_set: &RigidBodySet,
         collider_set: &ColliderSet,
         query_pipeline: &QueryPipeline,
@@ -377,13 +378,20 @@ impl ParticleFilter {
                 Some(x) => {
                     let sensor = robot.distance_sensors[i];
 
-                    let toi = Self::distance_sensor_ray(
-                        point,
-                        sensor,
-                        rigid_body_set,
-                        collider_set,
-                        query_pipeline,
-                    );
+                    let toi = match ray_table {
+                        Some(table) => {
+                            let origin = point.transform_point(&sensor.relative_position);
+                            let angle = point.rotation.angle() + sensor.relative_direction;
+                            table.cast(&origin, angle).min(sensor.max_range)
+                        }
+                        None => Self::distance_sensor_ray(
+                            point,
+                            sensor,
+                            rigid_body_set,
+                            collider_set,
+                            query_pipeline,
+                        ),
+                    };
 
                     (toi - x).abs()
                 }
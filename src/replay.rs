@@ -1,7 +1,7 @@
 //! A utility for recording over time
 
 use crate::agent_setup::PacmanAgentSetup;
-use crate::game_state::PacmanState;
+use crate::game_state::{GameEvent, PacmanState};
 use crate::standard_grids::StandardGrid;
 use anyhow::{anyhow, Error};
 use rapier2d::na::Isometry2;
@@ -15,6 +15,8 @@ enum ReplayFrameData {
     PacbotLocation(Isometry2<f32>),
     /// Information that changes frequently in Pacman, like ghost locations and pellets
     PacmanGameState(Box<PacmanState>),
+    /// What happened in the game since the previous PacmanState
+    GameEvents(Vec<GameEvent>),
 }
 
 /// The metadata included in one frame of a [`Replay`]
@@ -70,6 +72,7 @@ impl Replay {
     /// use rapier2d::math::Isometry;
     /// use rapier2d::na::Vector2;
     /// use mdrc_pacbot_util::agent_setup::PacmanAgentSetup;
+    /// use mdrc_pacbot_util::game_rules::GameRules;
     /// use mdrc_pacbot_util::game_state::PacmanState;
     /// use mdrc_pacbot_util::replay::Replay;
     /// use mdrc_pacbot_util::standard_grids::StandardGrid;
@@ -128,12 +131,14 @@ impl Replay {
     /// use rapier2d::math::Isometry;
     /// use rapier2d::na::Vector2;
     /// use mdrc_pacbot_util::agent_setup::PacmanAgentSetup;
+    /// use mdrc_pacbot_util::game_rules::GameRules;
     /// use mdrc_pacbot_util::game_state::PacmanState;
     /// use mdrc_pacbot_util::replay::Replay;
     /// use mdrc_pacbot_util::standard_grids::StandardGrid;
     ///
     /// let agent_setup = PacmanAgentSetup::default();
-    /// let mut pacman_state = PacmanState::new(&agent_setup);
+    /// let rules = GameRules::default();
+    /// let mut pacman_state = PacmanState::new(&agent_setup, &rules);
     /// let mut rng = ThreadRng::default();
     ///
     /// let mut replay = Replay::new(
@@ -148,7 +153,7 @@ impl Replay {
     /// assert_eq!(replay.current_frame(), 1);
     ///
     /// for i in 0..3 {
-    ///     pacman_state.step(&agent_setup, &mut rng, false);
+    ///     pacman_state.step(&agent_setup, &rules, &mut rng, false);
     ///     replay.record_pacman_state(pacman_state.to_owned()).unwrap();
     /// }
     ///
@@ -179,6 +184,7 @@ impl Replay {
             match other.frames[frame].data {
                 ReplayFrameData::PacbotLocation(_) => location_frame = frame,
                 ReplayFrameData::PacmanGameState(_) => pacman_state_frame = frame,
+                ReplayFrameData::GameEvents(_) => {}
             }
             frames.push(ReplayFrame {
                 data: other.frames[frame].data.to_owned(),
@@ -188,7 +194,7 @@ impl Replay {
 
         Self {
             start_time: frames[0].timestamp,
-            standard_grid: other.standard_grid,
+            standard_grid: other.standard_grid.to_owned(),
             agent_setup: other.agent_setup.to_owned(),
             label: other.label.to_owned(),
             frames,
@@ -277,18 +283,20 @@ impl Replay {
     /// ```
     /// use rand::rngs::ThreadRng;
     /// use mdrc_pacbot_util::agent_setup::PacmanAgentSetup;
+    /// use mdrc_pacbot_util::game_rules::GameRules;
     /// use mdrc_pacbot_util::game_state::PacmanState;
     /// use mdrc_pacbot_util::replay::Replay;
     ///
     /// let mut replay = Replay::default();
     /// let mut pacman_state = PacmanState::default();
     /// let mut agent_setup = PacmanAgentSetup::default();
+    /// let rules = GameRules::default();
     /// let mut rng = ThreadRng::default();
     ///
-    /// pacman_state.step(&agent_setup, &mut rng, false);
+    /// pacman_state.step(&agent_setup, &rules, &mut rng, false);
     /// replay.record_pacman_state(pacman_state.to_owned()).unwrap();
     /// let pacman_state_1 = pacman_state.to_owned();
-    /// pacman_state.step(&agent_setup, &mut rng, false);
+    /// pacman_state.step(&agent_setup, &rules, &mut rng, false);
     /// replay.record_pacman_state(pacman_state.to_owned()).unwrap();
     /// let pacman_state_2 = pacman_state.to_owned();
     ///
@@ -337,6 +345,7 @@ impl Replay {
         match &self.frames[self.current_frame].data {
             ReplayFrameData::PacbotLocation(_) => self.location_frame = self.current_frame,
             ReplayFrameData::PacmanGameState(_) => self.pacman_state_frame = self.current_frame,
+            ReplayFrameData::GameEvents(_) => {}
         };
     }
 
@@ -373,6 +382,7 @@ impl Replay {
             match &self.frames[frame].data {
                 ReplayFrameData::PacbotLocation(_) => self.location_frame = frame,
                 ReplayFrameData::PacmanGameState(_) => self.pacman_state_frame = frame,
+                ReplayFrameData::GameEvents(_) => {}
             }
         }
 
@@ -497,6 +507,55 @@ impl Replay {
         }
     }
 
+    /// Add the events from a game step to the end of the replay
+    ///
+    /// Nothing is recorded if there are no events. Returns err if the current frame is not the
+    /// last frame
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use mdrc_pacbot_util::game_state::{GameEvent, PacmanState};
+    /// use mdrc_pacbot_util::replay::Replay;
+    ///
+    /// let mut replay = Replay::default();
+    ///
+    /// replay.record_game_events(vec![GameEvent::GameOver { won: true }]).unwrap();
+    /// replay.record_pacman_state(PacmanState::default()).unwrap();
+    /// assert_eq!(replay.game_events(), vec![GameEvent::GameOver { won: true }]);
+    ///
+    /// // the log only includes events up to the current frame
+    /// replay.step_backwards_until_pacman_state();
+    /// assert_eq!(replay.game_events(), vec![]);
+    /// ```
+    pub fn record_game_events(&mut self, events: Vec<GameEvent>) -> Result<(), Error> {
+        if !self.is_at_end() {
+            Err(anyhow!("Tried to record to replay that was mid-playback"))
+        } else {
+            if !events.is_empty() {
+                self.frames.push(ReplayFrame {
+                    timestamp: SystemTime::now(),
+                    data: ReplayFrameData::GameEvents(events),
+                });
+                self.current_frame += 1;
+            }
+            Ok(())
+        }
+    }
+
+    /// Get every game event recorded up to the current frame, oldest first
+    pub fn game_events(&self) -> Vec<GameEvent> {
+        self.frames[..=self.current_frame]
+            .iter()
+            .filter_map(|frame| match &frame.data {
+                ReplayFrameData::GameEvents(events) => Some(events),
+                _ => None,
+            })
+            .flatten()
+            .copied()
+            .collect()
+    }
+
     /// Get the index of the current frame
     ///
     /// While this can be a good estimation of the progress through a replay, there is no guarantee
@@ -514,6 +573,11 @@ impl Replay {
         self.frames.len()
     }
 
+    /// Get the [`StandardGrid`] the recording uses, which may be a custom grid
+    pub fn standard_grid(&self) -> &StandardGrid {
+        &self.standard_grid
+    }
+
     /// Get the amount of time until the next frame
     ///
     /// If at the end, returns Duration::MAX
@@ -545,8 +609,11 @@ impl Replay {
 
 #[cfg(test)]
 mod test {
+    use crate::agent_setup::PacmanAgentSetup;
+    use crate::custom_grids::CustomGrid;
     use crate::game_state::PacmanState;
     use crate::replay::Replay;
+    use crate::standard_grids::StandardGrid;
     use rapier2d::na::Isometry2;
 
     #[test]
@@ -564,4 +631,21 @@ mod test {
         replay.get_pacman_state();
         replay.get_pacbot_location();
     }
+
+    #[test]
+    fn test_replay_with_custom_grid() {
+        let custom_grid =
+            CustomGrid::parse("IIIIII\nIeeeeI\nIeIIeI\nIeIIeI\nIeeeeI\nIIIIII\n", "Tiny").unwrap();
+        let standard_grid = StandardGrid::Custom(Box::new(custom_grid));
+        let replay = Replay::new(
+            "custom".to_string(),
+            standard_grid.to_owned(),
+            PacmanAgentSetup::default(),
+            PacmanState::default(),
+            standard_grid.get_default_pacbot_isometry(),
+        );
+
+        let replay = Replay::from_bytes(&replay.to_bytes().unwrap()).unwrap();
+        assert_eq!(replay.standard_grid(), &standard_grid);
+    }
 }
@@ -1,14 +1,20 @@
 #![cfg_attr(rustfmt, rustfmt_skip)]
 //! A set of pre-made general purpose grids
 
-use eframe::egui::Pos2;
 use rapier2d::na::{Isometry2, Vector2};
 use serde::{Deserialize, Serialize};
-use crate::grid::{ComputedGrid, Grid};
+use crate::custom_grids::{CustomGrid, SoftBoundaries};
+use crate::grid::{ComputedGrid, Grid, GridValue};
+#[cfg(test)]
+use crate::grid::Tunnel;
+#[cfg(test)]
+use rapier2d::na::Point2;
 use crate::grid::GridValue::*;
 
 /// An enum to support egui grid selection
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+///
+/// Grids loaded at runtime are [`StandardGrid::Custom`]; see [`crate::custom_grids`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum StandardGrid {
     /// The official Pacbot [`Grid`]
     Pacman,
@@ -18,10 +24,14 @@ pub enum StandardGrid {
     Outer,
     /// A (mostly) blank [`Grid`] - (1, 1) is walkable
     Blank,
+    /// A [`Grid`] loaded at runtime
+    Custom(Box<CustomGrid>),
 }
 
 impl StandardGrid {
-    /// Get a list of all available grids
+    /// Get a list of all built-in grids
+    ///
+    /// To include custom grids, use a [`GridRegistry`](crate::custom_grids::GridRegistry).
     pub fn get_all() -> Vec<Self> {
         vec![Self::Pacman, Self::Playground, Self::Outer, Self::Blank]
     }
@@ -29,16 +39,32 @@ impl StandardGrid {
     /// Get the [`Grid`] associated with this enum
     pub fn get_grid(&self) -> Grid {
         match self {
-            Self::Pacman => GRID_PACMAN,
-            Self::Playground => GRID_PLAYGROUND,
-            Self::Outer => GRID_OUTER,
-            Self::Blank => GRID_BLANK,
+            Self::Pacman => GRID_PACMAN.into(),
+            Self::Playground => GRID_PLAYGROUND.into(),
+            Self::Outer => GRID_OUTER.into(),
+            Self::Blank => GRID_BLANK.into(),
+            Self::Custom(custom) => custom.grid().to_owned(),
         }
     }
     
     /// Get the [`ComputedGrid`] associated with this enum
     pub fn compute_grid(&self) -> ComputedGrid {
-        ComputedGrid::try_from(self.get_grid()).expect("Failed to compute a StandardGrid")
+        let tunnels = match self {
+            Self::Custom(custom) => custom.tunnels().to_owned(),
+            _ => vec![],
+        };
+        ComputedGrid::with_tunnels(self.get_grid(), tunnels).expect("Failed to compute a StandardGrid")
+    }
+
+    /// Get the name of the grid, for display
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Pacman => "Pacman",
+            Self::Playground => "Playground",
+            Self::Outer => "Outer",
+            Self::Blank => "Blank",
+            Self::Custom(custom) => custom.name(),
+        }
     }
 
     /// Get the default Pacbot [`Isometry2`] associated with this enum
@@ -48,75 +74,59 @@ impl StandardGrid {
             StandardGrid::Playground => Isometry2::new(Vector2::new(1.0, 1.0), 0.0),
             StandardGrid::Outer => Isometry2::new(Vector2::new(1.0, 1.0), 0.0),
             StandardGrid::Blank => Isometry2::new(Vector2::new(1.0, 1.0), 0.0),
+            StandardGrid::Custom(custom) => *custom.pacbot_isometry(),
         }
     }
 
-    /// Get the part of the [`Grid`] that should actually show on the gui
-    pub fn get_soft_boundaries(&self) -> (Pos2, Pos2) {
+    /// Get the area that Pacbot is expected to stay within, in world coordinates
+    pub fn get_soft_boundaries(&self) -> SoftBoundaries {
         match self {
-            Self::Pacman => (Pos2::new(-1.0, 31.0), Pos2::new(28.0, -1.0)),
-            _ => (Pos2::new(-1.0, 32.0), Pos2::new(32.0, -1.0))
-        }
-    }
-
-    /// Get the rectangles (in grid coordinates) that should be repainted with the background color
-    pub fn get_outside_soft_boundaries(&self) -> Vec<(Pos2, Pos2)> {
-        match self {
-            Self::Pacman => vec![
-                (Pos2::new(-1.0, 31.0), Pos2::new(32.1, 32.1)),
-                (Pos2::new(28.0, -1.0), Pos2::new(32.1, 32.1)),
-            ],
-            _ => vec![]
+            StandardGrid::Custom(custom) => *custom.soft_boundaries(),
+            _ => SoftBoundaries::around(&self.get_grid()),
         }
     }
 }
 
-/// The official Pacbot [`Grid`]
-///
-/// Out-of-bounds areas are replaced with walls to adhere to ComputedGrid rules
+/// The official Pacbot [`Grid`], 28 cells wide and 31 cells tall
 ///
 /// ```
 /// use mdrc_pacbot_util::standard_grids::GRID_PACMAN;
 /// use mdrc_pacbot_util::grid::{ComputedGrid, Grid};
 ///
-/// let grid: Grid = GRID_PACMAN;
+/// let grid = Grid::from(GRID_PACMAN);
 /// let computed_grid: ComputedGrid = grid.try_into().unwrap();
 /// ```
-pub const GRID_PACMAN: Grid = [
+pub const GRID_PACMAN: [[GridValue; 31]; 28] = [
 //  bottom left of pacman board                                           // top left of pacman board
-    [I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I], // 0
-    [I, o, o, o, o, I, I, O, o, o, o, I, I, I, I, I, I, I, I, I, I, I, o, o, o, o, o, O, o, o, I, I],
-    [I, o, I, I, o, I, I, o, I, I, o, I, I, I, I, I, I, I, I, I, I, I, o, I, I, o, I, I, I, o, I, I],
-    [I, o, I, I, o, o, o, o, I, I, o, I, I, I, I, I, I, I, I, I, I, I, o, I, I, o, I, I, I, o, I, I],
-    [I, o, I, I, o, I, I, I, I, I, o, I, I, I, I, I, I, I, I, I, I, I, o, I, I, o, I, I, I, o, I, I],
-    [I, o, I, I, o, I, I, I, I, I, o, I, I, I, I, I, I, I, I, I, I, I, o, I, I, o, I, I, I, o, I, I], // 5
-    [I, o, I, I, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, I, I],
-    [I, o, I, I, I, I, I, o, I, I, o, I, I, I, I, I, e, I, I, I, I, I, I, I, I, o, I, I, I, o, I, I],
-    [I, o, I, I, I, I, I, o, I, I, o, I, I, I, I, I, e, I, I, I, I, I, I, I, I, o, I, I, I, o, I, I],
-    [I, o, I, I, o, o, o, o, I, I, o, e, e, e, e, e, e, e, e, e, I, I, o, o, o, o, I, I, I, o, I, I],
-    [I, o, I, I, o, I, I, o, I, I, o, I, I, e, I, I, I, I, I, e, I, I, o, I, I, o, I, I, I, o, I, I], // 10
-    [I, o, I, I, o, I, I, o, I, I, o, I, I, e, I, n, n, n, I, e, I, I, o, I, I, o, I, I, I, o, I, I],
-    [I, o, o, o, o, I, I, o, o, o, o, I, I, e, I, n, n, n, I, e, e, e, o, I, I, o, o, o, o, o, I, I],
-    [I, o, I, I, I, I, I, e, I, I, I, I, I, e, I, n, n, n, n, e, I, I, I, I, I, o, I, I, I, I, I, I],
-    [I, o, I, I, I, I, I, e, I, I, I, I, I, e, I, n, n, n, n, e, I, I, I, I, I, o, I, I, I, I, I, I],
-    [I, o, o, o, o, I, I, o, o, o, o, I, I, e, I, n, n, n, I, e, e, e, o, I, I, o, o, o, o, o, I, I], // 15
-    [I, o, I, I, o, I, I, o, I, I, o, I, I, e, I, n, n, n, I, e, I, I, o, I, I, o, I, I, I, o, I, I],
-    [I, o, I, I, o, I, I, o, I, I, o, I, I, e, I, I, I, I, I, e, I, I, o, I, I, o, I, I, I, o, I, I],
-    [I, o, I, I, o, o, o, o, I, I, o, e, e, e, e, e, e, e, e, e, I, I, o, o, o, o, I, I, I, o, I, I],
-    [I, o, I, I, I, I, I, o, I, I, o, I, I, I, I, I, e, I, I, I, I, I, I, I, I, o, I, I, I, o, I, I],
-    [I, o, I, I, I, I, I, o, I, I, o, I, I, I, I, I, e, I, I, I, I, I, I, I, I, o, I, I, I, o, I, I], // 20
-    [I, o, I, I, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, I, I],
-    [I, o, I, I, o, I, I, I, I, I, o, I, I, I, I, I, I, I, I, I, I, I, o, I, I, o, I, I, I, o, I, I],
-    [I, o, I, I, o, I, I, I, I, I, o, I, I, I, I, I, I, I, I, I, I, I, o, I, I, o, I, I, I, o, I, I],
-    [I, o, I, I, o, o, o, o, I, I, o, I, I, I, I, I, I, I, I, I, I, I, o, I, I, o, I, I, I, o, I, I],
-    [I, o, I, I, o, I, I, o, I, I, o, I, I, I, I, I, I, I, I, I, I, I, o, I, I, o, I, I, I, o, I, I], // 25
-    [I, o, o, o, o, I, I, O, o, o, o, I, I, I, I, I, I, I, I, I, I, I, o, o, o, o, o, O, o, o, I, I],
-    [I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I],
-    [I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I],
-    [I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I],
-    [I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I],
-    [I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I],
-//   |              |              |              |              |              |              |   top right of pacman board
+    [I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I], // 0
+    [I, o, o, o, o, I, I, O, o, o, o, I, I, I, I, I, I, I, I, I, I, I, o, o, o, o, o, O, o, o, I],
+    [I, o, I, I, o, I, I, o, I, I, o, I, I, I, I, I, I, I, I, I, I, I, o, I, I, o, I, I, I, o, I],
+    [I, o, I, I, o, o, o, o, I, I, o, I, I, I, I, I, I, I, I, I, I, I, o, I, I, o, I, I, I, o, I],
+    [I, o, I, I, o, I, I, I, I, I, o, I, I, I, I, I, I, I, I, I, I, I, o, I, I, o, I, I, I, o, I],
+    [I, o, I, I, o, I, I, I, I, I, o, I, I, I, I, I, I, I, I, I, I, I, o, I, I, o, I, I, I, o, I], // 5
+    [I, o, I, I, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, I],
+    [I, o, I, I, I, I, I, o, I, I, o, I, I, I, I, I, e, I, I, I, I, I, I, I, I, o, I, I, I, o, I],
+    [I, o, I, I, I, I, I, o, I, I, o, I, I, I, I, I, e, I, I, I, I, I, I, I, I, o, I, I, I, o, I],
+    [I, o, I, I, o, o, o, o, I, I, o, e, e, e, e, e, e, e, e, e, I, I, o, o, o, o, I, I, I, o, I],
+    [I, o, I, I, o, I, I, o, I, I, o, I, I, e, I, I, I, I, I, e, I, I, o, I, I, o, I, I, I, o, I], // 10
+    [I, o, I, I, o, I, I, o, I, I, o, I, I, e, I, n, n, n, I, e, I, I, o, I, I, o, I, I, I, o, I],
+    [I, o, o, o, o, I, I, o, o, o, o, I, I, e, I, n, n, n, I, e, e, e, o, I, I, o, o, o, o, o, I],
+    [I, o, I, I, I, I, I, e, I, I, I, I, I, e, I, n, n, n, n, e, I, I, I, I, I, o, I, I, I, I, I],
+    [I, o, I, I, I, I, I, e, I, I, I, I, I, e, I, n, n, n, n, e, I, I, I, I, I, o, I, I, I, I, I],
+    [I, o, o, o, o, I, I, o, o, o, o, I, I, e, I, n, n, n, I, e, e, e, o, I, I, o, o, o, o, o, I], // 15
+    [I, o, I, I, o, I, I, o, I, I, o, I, I, e, I, n, n, n, I, e, I, I, o, I, I, o, I, I, I, o, I],
+    [I, o, I, I, o, I, I, o, I, I, o, I, I, e, I, I, I, I, I, e, I, I, o, I, I, o, I, I, I, o, I],
+    [I, o, I, I, o, o, o, o, I, I, o, e, e, e, e, e, e, e, e, e, I, I, o, o, o, o, I, I, I, o, I],
+    [I, o, I, I, I, I, I, o, I, I, o, I, I, I, I, I, e, I, I, I, I, I, I, I, I, o, I, I, I, o, I],
+    [I, o, I, I, I, I, I, o, I, I, o, I, I, I, I, I, e, I, I, I, I, I, I, I, I, o, I, I, I, o, I], // 20
+    [I, o, I, I, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, o, I],
+    [I, o, I, I, o, I, I, I, I, I, o, I, I, I, I, I, I, I, I, I, I, I, o, I, I, o, I, I, I, o, I],
+    [I, o, I, I, o, I, I, I, I, I, o, I, I, I, I, I, I, I, I, I, I, I, o, I, I, o, I, I, I, o, I],
+    [I, o, I, I, o, o, o, o, I, I, o, I, I, I, I, I, I, I, I, I, I, I, o, I, I, o, I, I, I, o, I],
+    [I, o, I, I, o, I, I, o, I, I, o, I, I, I, I, I, I, I, I, I, I, I, o, I, I, o, I, I, I, o, I], // 25
+    [I, o, o, o, o, I, I, O, o, o, o, I, I, I, I, I, I, I, I, I, I, I, o, o, o, o, o, O, o, o, I],
+    [I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I],
+//   |              |              |              |              |              |              top right of pacman board
 //   0              5              10             15             20             25             30
 ];
 
@@ -126,10 +136,10 @@ pub const GRID_PACMAN: Grid = [
 /// use mdrc_pacbot_util::standard_grids::GRID_BLANK;
 /// use mdrc_pacbot_util::grid::{ComputedGrid, Grid};
 ///
-/// let grid: Grid = GRID_BLANK;
+/// let grid = Grid::from(GRID_BLANK);
 /// let computed_grid: ComputedGrid = grid.try_into().unwrap();
 /// ```
-pub const GRID_BLANK: Grid = [
+pub const GRID_BLANK: [[GridValue; 32]; 32] = [
     [I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I],
     [I, e, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I],
     [I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I],
@@ -164,16 +174,26 @@ pub const GRID_BLANK: Grid = [
     [I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I]
 ];
 
+/// [`GRID_BLANK`] with a row across the middle that wraps around through a tunnel
+#[cfg(test)]
+pub(crate) fn tunnel_grid() -> (Grid, Tunnel) {
+    let mut grid = Grid::from(GRID_BLANK);
+    for column in grid.columns_mut() {
+        column[5] = e;
+    }
+    (grid, (Point2::new(0, 5), Point2::new(31, 5)))
+}
+
 /// A [`Grid`] where the outermost path is empty
 ///
 /// ```
 /// use mdrc_pacbot_util::standard_grids::GRID_OUTER;
 /// use mdrc_pacbot_util::grid::{ComputedGrid, Grid};
 ///
-/// let grid: Grid = GRID_OUTER;
+/// let grid = Grid::from(GRID_OUTER);
 /// let computed_grid: ComputedGrid = grid.try_into().unwrap();
 /// ```
-pub const GRID_OUTER: Grid = [
+pub const GRID_OUTER: [[GridValue; 32]; 32] = [
     [I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I],
     [I, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, I],
     [I, e, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, e, I],
@@ -214,10 +234,10 @@ pub const GRID_OUTER: Grid = [
 /// use mdrc_pacbot_util::standard_grids::GRID_PLAYGROUND;
 /// use mdrc_pacbot_util::grid::{ComputedGrid, Grid};
 ///
-/// let grid: Grid = GRID_PLAYGROUND;
+/// let grid = Grid::from(GRID_PLAYGROUND);
 /// let computed_grid: ComputedGrid = grid.try_into().unwrap();
 /// ```
-pub const GRID_PLAYGROUND: Grid = [
+pub const GRID_PLAYGROUND: [[GridValue; 32]; 32] = [
     [I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I],
     [I, e, I, I, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, e, I],
     [I, e, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I, I],